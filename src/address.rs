@@ -0,0 +1,514 @@
+use std::fmt;
+
+use bech32::{FromBase32, ToBase32, Variant};
+use sha3::{Digest, Keccak256};
+
+use crate::cardano::cbor::{CborError, Decoder};
+use crate::{Blockchain, WalletCreationConfig};
+
+// base58check version bytes of Bitcoin addresses
+const P2PKH_MAINNET: u8 = 0x00;
+const P2SH_MAINNET: u8 = 0x05;
+const P2PKH_TESTNET: u8 = 0x6f;
+const P2SH_TESTNET: u8 = 0xc4;
+
+// bech32 human readable parts
+const BITCOIN_MAINNET_HRP: &str = "bc";
+const BITCOIN_TESTNET_HRP: &str = "tb";
+const BITCOIN_REGTEST_HRP: &str = "bcrt";
+const CARDANO_MAINNET_HRP: &str = "addr";
+const CARDANO_TESTNET_HRP: &str = "addr_test";
+
+// Cardano network ids in the low 4 bits of the Shelley header
+const CARDANO_MAINNET_ID: u8 = 1;
+const CARDANO_TESTNET_ID: u8 = 0;
+// key of the protocol magic in Byron address attributes. Only testnet addresses have it
+const BYRON_PROTOCOL_MAGIC_KEY: u64 = 2;
+// CBOR tag of embedded CBOR wrapping the Byron address payload
+const BYRON_PAYLOAD_TAG: u64 = 24;
+
+// Reason why a string is not a valid address of the blockchain
+#[derive(Clone, PartialEq, Debug)]
+pub enum AddressError {
+    Empty,
+    // address cannot be decoded in any address format of the blockchain
+    InvalidFormat {
+        blockchain: Blockchain,
+        address: String,
+    },
+    // checksum of a base58check, bech32, EIP-55 or Byron address does not match
+    InvalidChecksum {
+        address: String,
+    },
+    // segwit version that the wallet cannot send to
+    UnsupportedWitnessVersion {
+        version: u8,
+    },
+    // Cardano address type that cannot receive payments e.g. stake address
+    UnsupportedAddressType {
+        address: String,
+    },
+    // address of a testnet used on mainnet or the other way around
+    WrongNetwork {
+        address: String,
+        is_mainnet: bool,
+    },
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Empty => write!(f, "address is empty"),
+            AddressError::InvalidFormat {
+                blockchain,
+                address,
+            } => write!(f, "{} is not a valid {:?} address", address, blockchain),
+            AddressError::InvalidChecksum { address } => {
+                write!(f, "checksum of address {} is invalid", address)
+            }
+            AddressError::UnsupportedWitnessVersion { version } => {
+                write!(f, "segwit version {} is not supported", version)
+            }
+            AddressError::UnsupportedAddressType { address } => {
+                write!(f, "address {} cannot receive payments", address)
+            }
+            AddressError::WrongNetwork {
+                address,
+                is_mainnet,
+            } => {
+                let network = if *is_mainnet { "mainnet" } else { "testnet" };
+                write!(f, "address {} is not a {} address", address, network)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AddressError {}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum AddressKind {
+    // Bitcoin pay to public key hash
+    P2pkh,
+    // Bitcoin pay to script hash
+    P2sh,
+    // Bitcoin segwit v0 pay to witness public key hash
+    P2wpkh,
+    // Bitcoin segwit v0 pay to witness script hash
+    P2wsh,
+    // Bitcoin segwit v1 taproot
+    P2tr,
+    // EVM account
+    Evm,
+    // Cardano Shelley payment address. Type is the high 4 bits of the header e.g. 6 for enterprise
+    Shelley { address_type: u8 },
+    // Cardano Byron bootstrap address
+    Byron,
+}
+
+// A parsed address of a blockchain
+#[derive(Clone, PartialEq, Debug)]
+pub struct Address {
+    blockchain: Blockchain,
+    kind: AddressKind,
+    // None when the address does not encode a network e.g. EVM
+    is_mainnet: Option<bool>,
+    // hash or witness program for Bitcoin, 20 bytes account for EVM, address bytes for Cardano
+    payload: Vec<u8>,
+    canonical: String,
+}
+
+impl Address {
+    pub fn parse(blockchain: &Blockchain, address: &str) -> Result<Address, AddressError> {
+        let address = address.trim();
+        if address.is_empty() {
+            return Err(AddressError::Empty);
+        }
+        match blockchain {
+            Blockchain::BITCOIN => parse_bitcoin(address),
+            Blockchain::ETHEREUM | Blockchain::POLYGON => parse_evm(blockchain, address),
+            Blockchain::CARDANO => parse_cardano(address),
+        }
+    }
+
+    // parse and check that the address belongs to mainnet or testnet
+    pub fn parse_on_network(
+        blockchain: &Blockchain,
+        address: &str,
+        is_mainnet: bool,
+    ) -> Result<Address, AddressError> {
+        let parsed = Address::parse(blockchain, address)?;
+        if parsed
+            .is_mainnet
+            .map_or(false, |network| network != is_mainnet)
+        {
+            return Err(AddressError::WrongNetwork {
+                address: parsed.canonical,
+                is_mainnet,
+            });
+        }
+        Ok(parsed)
+    }
+
+    pub fn blockchain(&self) -> &Blockchain {
+        &self.blockchain
+    }
+
+    pub fn kind(&self) -> &AddressKind {
+        &self.kind
+    }
+
+    pub fn is_mainnet(&self) -> Option<bool> {
+        self.is_mainnet
+    }
+
+    pub fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    // Canonical form: lowercase bech32, EIP-55 checksum hex or base58 as given
+    pub fn as_str(&self) -> &str {
+        &self.canonical
+    }
+
+    // scriptPubKey paying a Bitcoin address
+    pub fn script_pubkey(&self) -> Option<Vec<u8>> {
+        let mut script = Vec::with_capacity(self.payload.len() + 5);
+        match self.kind {
+            AddressKind::P2pkh => {
+                // OP_DUP OP_HASH160 <hash> OP_EQUALVERIFY OP_CHECKSIG
+                script.extend_from_slice(&[0x76, 0xa9, 0x14]);
+                script.extend_from_slice(&self.payload);
+                script.extend_from_slice(&[0x88, 0xac]);
+            }
+            AddressKind::P2sh => {
+                // OP_HASH160 <hash> OP_EQUAL
+                script.extend_from_slice(&[0xa9, 0x14]);
+                script.extend_from_slice(&self.payload);
+                script.push(0x87);
+            }
+            AddressKind::P2wpkh | AddressKind::P2wsh => {
+                // OP_0 <program>
+                script.extend_from_slice(&[0x00, self.payload.len() as u8]);
+                script.extend_from_slice(&self.payload);
+            }
+            AddressKind::P2tr => {
+                // OP_1 <program>
+                script.extend_from_slice(&[0x51, self.payload.len() as u8]);
+                script.extend_from_slice(&self.payload);
+            }
+            _ => return None,
+        }
+        Some(script)
+    }
+
+    // Bitcoin address of a standard scriptPubKey, None for other scripts
+    pub fn from_script_pubkey(script: &[u8], is_mainnet: bool) -> Option<Address> {
+        let base58check =
+            |version: u8, hash: &[u8]| bs58::encode(hash).with_check_version(version).into_string();
+        let segwit = |version: u8, program: &[u8]| {
+            let hrp = if is_mainnet {
+                BITCOIN_MAINNET_HRP
+            } else {
+                BITCOIN_TESTNET_HRP
+            };
+            let variant = if version == 0 {
+                Variant::Bech32
+            } else {
+                Variant::Bech32m
+            };
+            let mut data = vec![bech32::u5::try_from_u8(version).expect("witness version")];
+            data.extend(program.to_base32());
+            bech32::encode(hrp, data, variant).expect("valid bech32 hrp")
+        };
+        let address = match script {
+            [0x76, 0xa9, 0x14, hash @ .., 0x88, 0xac] if hash.len() == 20 => {
+                let version = if is_mainnet {
+                    P2PKH_MAINNET
+                } else {
+                    P2PKH_TESTNET
+                };
+                base58check(version, hash)
+            }
+            [0xa9, 0x14, hash @ .., 0x87] if hash.len() == 20 => {
+                let version = if is_mainnet {
+                    P2SH_MAINNET
+                } else {
+                    P2SH_TESTNET
+                };
+                base58check(version, hash)
+            }
+            [0x00, len, program @ ..] if *len as usize == program.len() => segwit(0, program),
+            [0x51, 0x20, program @ ..] if program.len() == 32 => segwit(1, program),
+            _ => return None,
+        };
+        Address::parse(&Blockchain::BITCOIN, &address).ok()
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.canonical)
+    }
+}
+
+impl WalletCreationConfig {
+    // parse an address and check it belongs to the network of the wallet
+    pub fn parse_address(
+        &self,
+        blockchain: &Blockchain,
+        address: &str,
+    ) -> Result<Address, AddressError> {
+        Address::parse_on_network(blockchain, address, self.is_mainnet)
+    }
+}
+
+// canonical form of the address
+pub fn normalize_address(blockchain: &Blockchain, address: &str) -> Result<String, AddressError> {
+    Address::parse(blockchain, address).map(|parsed| parsed.canonical)
+}
+
+// EIP-55 mixed case checksum encoding of an EVM account with 0x prefix
+pub fn eip55_checksum(account: &[u8; 20]) -> String {
+    let lower = hex::encode(account);
+    let hash = Keccak256::digest(lower.as_bytes());
+    let checksummed: String = lower
+        .chars()
+        .enumerate()
+        .map(|(index, char)| {
+            let nibble = (hash[index / 2] >> (4 * (1 - index % 2))) & 0x0f;
+            if nibble >= 8 {
+                char.to_ascii_uppercase()
+            } else {
+                char
+            }
+        })
+        .collect();
+    format!("0x{}", checksummed)
+}
+
+fn parse_bitcoin(address: &str) -> Result<Address, AddressError> {
+    let lower = address.to_lowercase();
+    let is_bech32 = [
+        BITCOIN_MAINNET_HRP,
+        BITCOIN_TESTNET_HRP,
+        BITCOIN_REGTEST_HRP,
+    ]
+    .iter()
+    .any(|hrp| lower.starts_with(&format!("{}1", hrp)));
+    if is_bech32 {
+        parse_segwit(address)
+    } else {
+        parse_base58check(address)
+    }
+}
+
+fn parse_base58check(address: &str) -> Result<Address, AddressError> {
+    let bytes = bs58::decode(address)
+        .with_check(None)
+        .into_vec()
+        .map_err(|error| match error {
+            bs58::decode::Error::InvalidChecksum { .. } => AddressError::InvalidChecksum {
+                address: address.to_string(),
+            },
+            _ => invalid_format(&Blockchain::BITCOIN, address),
+        })?;
+    if bytes.len() != 21 {
+        return Err(invalid_format(&Blockchain::BITCOIN, address));
+    }
+    let (kind, is_mainnet) = match bytes[0] {
+        P2PKH_MAINNET => (AddressKind::P2pkh, true),
+        P2SH_MAINNET => (AddressKind::P2sh, true),
+        P2PKH_TESTNET => (AddressKind::P2pkh, false),
+        P2SH_TESTNET => (AddressKind::P2sh, false),
+        _ => return Err(invalid_format(&Blockchain::BITCOIN, address)),
+    };
+    Ok(Address {
+        blockchain: Blockchain::BITCOIN,
+        kind,
+        is_mainnet: Some(is_mainnet),
+        payload: bytes[1..].to_vec(),
+        canonical: address.to_string(),
+    })
+}
+
+fn parse_segwit(address: &str) -> Result<Address, AddressError> {
+    let (hrp, data, variant) = bech32::decode(address).map_err(|error| match error {
+        bech32::Error::InvalidChecksum => AddressError::InvalidChecksum {
+            address: address.to_string(),
+        },
+        _ => invalid_format(&Blockchain::BITCOIN, address),
+    })?;
+    let (version, program) = data
+        .split_first()
+        .ok_or_else(|| invalid_format(&Blockchain::BITCOIN, address))?;
+    let version = version.to_u8();
+    let program = Vec::<u8>::from_base32(program)
+        .map_err(|_| invalid_format(&Blockchain::BITCOIN, address))?;
+    // BIP-350: version 0 uses bech32, later versions use bech32m
+    let expected_variant = if version == 0 {
+        Variant::Bech32
+    } else {
+        Variant::Bech32m
+    };
+    if variant != expected_variant {
+        return Err(AddressError::InvalidChecksum {
+            address: address.to_string(),
+        });
+    }
+    let kind = match (version, program.len()) {
+        (0, 20) => AddressKind::P2wpkh,
+        (0, 32) => AddressKind::P2wsh,
+        (1, 32) => AddressKind::P2tr,
+        (0, _) | (1, _) => return Err(invalid_format(&Blockchain::BITCOIN, address)),
+        _ => return Err(AddressError::UnsupportedWitnessVersion { version }),
+    };
+    Ok(Address {
+        blockchain: Blockchain::BITCOIN,
+        kind,
+        is_mainnet: Some(hrp == BITCOIN_MAINNET_HRP),
+        payload: program,
+        canonical: address.to_lowercase(),
+    })
+}
+
+fn parse_evm(blockchain: &Blockchain, address: &str) -> Result<Address, AddressError> {
+    let digits = address
+        .strip_prefix("0x")
+        .or_else(|| address.strip_prefix("0X"))
+        .filter(|digits| digits.len() == 40)
+        .ok_or_else(|| invalid_format(blockchain, address))?;
+    let mut account = [0u8; 20];
+    hex::decode_to_slice(digits, &mut account).map_err(|_| invalid_format(blockchain, address))?;
+    let canonical = eip55_checksum(&account);
+    // all lowercase or all uppercase addresses carry no checksum
+    let is_mixed_case = digits.chars().any(|char| char.is_ascii_lowercase())
+        && digits.chars().any(|char| char.is_ascii_uppercase());
+    if is_mixed_case && canonical[2..] != *digits {
+        return Err(AddressError::InvalidChecksum {
+            address: address.to_string(),
+        });
+    }
+    Ok(Address {
+        blockchain: blockchain.clone(),
+        kind: AddressKind::Evm,
+        is_mainnet: None,
+        payload: account.to_vec(),
+        canonical,
+    })
+}
+
+fn parse_cardano(address: &str) -> Result<Address, AddressError> {
+    let lower = address.to_lowercase();
+    if lower.starts_with(&format!("{}1", CARDANO_MAINNET_HRP))
+        || lower.starts_with(&format!("{}1", CARDANO_TESTNET_HRP))
+    {
+        parse_shelley(address)
+    } else {
+        parse_byron(address)
+    }
+}
+
+fn parse_shelley(address: &str) -> Result<Address, AddressError> {
+    let (hrp, data, variant) = bech32::decode(address).map_err(|error| match error {
+        bech32::Error::InvalidChecksum => AddressError::InvalidChecksum {
+            address: address.to_string(),
+        },
+        _ => invalid_format(&Blockchain::CARDANO, address),
+    })?;
+    let bytes =
+        Vec::<u8>::from_base32(&data).map_err(|_| invalid_format(&Blockchain::CARDANO, address))?;
+    let header = *bytes
+        .first()
+        .ok_or_else(|| invalid_format(&Blockchain::CARDANO, address))?;
+    let address_type = header >> 4;
+    let network_id = header & 0x0f;
+    let valid_len = match address_type {
+        // base addresses with payment and stake credentials
+        0..=3 => bytes.len() == 57,
+        // pointer addresses with payment credential and a variable length pointer
+        4 | 5 => bytes.len() > 29,
+        // enterprise addresses with payment credential only
+        6 | 7 => bytes.len() == 29,
+        _ => {
+            return Err(AddressError::UnsupportedAddressType {
+                address: address.to_string(),
+            })
+        }
+    };
+    let is_mainnet = hrp == CARDANO_MAINNET_HRP;
+    let expected_network_id = if is_mainnet {
+        CARDANO_MAINNET_ID
+    } else {
+        CARDANO_TESTNET_ID
+    };
+    if variant != Variant::Bech32 || !valid_len || network_id != expected_network_id {
+        return Err(invalid_format(&Blockchain::CARDANO, address));
+    }
+    let canonical = bech32::encode(&hrp, bytes.to_base32(), Variant::Bech32)
+        .map_err(|_| invalid_format(&Blockchain::CARDANO, address))?;
+    Ok(Address {
+        blockchain: Blockchain::CARDANO,
+        kind: AddressKind::Shelley { address_type },
+        is_mainnet: Some(is_mainnet),
+        payload: bytes,
+        canonical,
+    })
+}
+
+// Byron address is base58 of CBOR [tag 24 bytes(payload), crc32(payload)] where payload is
+// [root, attributes, type]
+fn parse_byron(address: &str) -> Result<Address, AddressError> {
+    let bytes = bs58::decode(address)
+        .into_vec()
+        .map_err(|_| invalid_format(&Blockchain::CARDANO, address))?;
+    let (payload, crc) =
+        byron_payload(&bytes).map_err(|_| invalid_format(&Blockchain::CARDANO, address))?;
+    if crc32fast::hash(payload) as u64 != crc {
+        return Err(AddressError::InvalidChecksum {
+            address: address.to_string(),
+        });
+    }
+    let is_testnet = byron_has_protocol_magic(payload)
+        .map_err(|_| invalid_format(&Blockchain::CARDANO, address))?;
+    Ok(Address {
+        blockchain: Blockchain::CARDANO,
+        kind: AddressKind::Byron,
+        is_mainnet: Some(!is_testnet),
+        payload: bytes,
+        canonical: address.to_string(),
+    })
+}
+
+fn byron_payload(bytes: &[u8]) -> Result<(&[u8], u64), CborError> {
+    let mut decoder = Decoder::new(bytes);
+    decoder.expect_array(2)?;
+    decoder.expect_tag(BYRON_PAYLOAD_TAG)?;
+    let payload = decoder.read_bytes()?;
+    let crc = decoder.read_uint()?;
+    decoder.finish()?;
+    Ok((payload, crc))
+}
+
+fn byron_has_protocol_magic(payload: &[u8]) -> Result<bool, CborError> {
+    let mut decoder = Decoder::new(payload);
+    decoder.expect_array(3)?;
+    decoder.read_bytes()?;
+    let entries = decoder.read_map()?.ok_or(CborError::IndefiniteLength)?;
+    let mut has_protocol_magic = false;
+    for _ in 0..entries {
+        if decoder.read_uint()? == BYRON_PROTOCOL_MAGIC_KEY {
+            has_protocol_magic = true;
+        }
+        decoder.skip()?;
+    }
+    decoder.read_uint()?;
+    decoder.finish()?;
+    Ok(has_protocol_magic)
+}
+
+fn invalid_format(blockchain: &Blockchain, address: &str) -> AddressError {
+    AddressError::InvalidFormat {
+        blockchain: blockchain.clone(),
+        address: address.to_string(),
+    }
+}
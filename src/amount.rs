@@ -0,0 +1,350 @@
+use std::cmp::Ordering;
+use std::fmt;
+
+use bigdecimal::num_bigint::{BigInt, Sign};
+use bigdecimal::{BigDecimal, Signed, ToPrimitive, Zero};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    Blockchain, BlockchainCoinConfig, Coin, EthContractRequest, SendRequest, SendTokenRequest,
+    UnspentOutput,
+};
+
+// Reason why an amount cannot be represented in base units of a coin
+#[derive(Clone, PartialEq, Debug)]
+pub enum AmountError {
+    // amount has more decimal places than the coin supports e.g. 0.000000001 BTC
+    ExcessPrecision {
+        coin: Coin,
+        decimals: u32,
+        amount: BigDecimal,
+    },
+    // amounts must not be negative
+    Negative {
+        coin: Coin,
+        amount: BigDecimal,
+    },
+    // decimals from config must not be negative
+    InvalidDecimals {
+        coin: Coin,
+        decimals: i32,
+    },
+    // arithmetic between amounts of different coins or decimals
+    CoinMismatch {
+        left: Coin,
+        right: Coin,
+    },
+    // amount has more than MAX_DIGITS digits in base units
+    TooLarge {
+        coin: Coin,
+        amount: BigDecimal,
+    },
+}
+
+impl fmt::Display for AmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AmountError::ExcessPrecision {
+                coin,
+                decimals,
+                amount,
+            } => write!(
+                f,
+                "{} {:?} has more than {} decimal places",
+                display_amount(amount),
+                coin,
+                decimals
+            ),
+            AmountError::Negative { coin, amount } => {
+                write!(
+                    f,
+                    "{} {:?} must not be negative",
+                    display_amount(amount),
+                    coin
+                )
+            }
+            AmountError::InvalidDecimals { coin, decimals } => {
+                write!(f, "{:?} decimals {} must not be negative", coin, decimals)
+            }
+            AmountError::CoinMismatch { left, right } => {
+                write!(
+                    f,
+                    "cannot combine {:?} amount with {:?} amount",
+                    left, right
+                )
+            }
+            AmountError::TooLarge { coin, amount } => {
+                write!(f, "{} {:?} is too large", display_amount(amount), coin)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AmountError {}
+
+// digits of an amount in base units. uint256, the largest amount of any chain, has 78 digits
+pub(crate) const MAX_DIGITS: i64 = 78;
+
+// Amount of a coin held as an integer number of base units (satoshi, wei, lovelace, ...).
+// Conversions between coin unit and base units are lossless. It serializes as the decimal
+// amount in coin unit, the same as the BigDecimal amount fields of the model.
+#[derive(Clone, PartialEq, Debug)]
+pub struct CoinAmount {
+    coin: Coin,
+    decimals: u32,
+    base_units: BigInt,
+}
+
+impl CoinAmount {
+    // amount in coin unit e.g. 0.5 BTC. Fails when the amount has more decimal places than the coin
+    pub fn from_coin_unit(
+        coin: Coin,
+        decimals: u32,
+        amount: &BigDecimal,
+    ) -> Result<Self, AmountError> {
+        if amount.is_negative() {
+            return Err(AmountError::Negative {
+                coin,
+                amount: amount.clone(),
+            });
+        }
+        // Without trailing zeros the scale is the number of decimal places. The exponent comes
+        // from the input so it is checked before any power of ten is computed.
+        let (digits, scale) = strip_trailing_zeros(amount);
+        let shift = (decimals as i64).saturating_sub(scale);
+        if shift < 0 {
+            return Err(AmountError::ExcessPrecision {
+                coin,
+                decimals,
+                amount: amount.clone(),
+            });
+        }
+        if shift > MAX_DIGITS || digit_count(&digits) + shift > MAX_DIGITS {
+            return Err(AmountError::TooLarge {
+                coin,
+                amount: amount.clone(),
+            });
+        }
+        let base_units = digits * pow10(shift as u32);
+        Ok(CoinAmount {
+            coin,
+            decimals,
+            base_units,
+        })
+    }
+
+    // integer amount in base units e.g. 50000000 satoshi
+    pub fn from_base_units(
+        coin: Coin,
+        decimals: u32,
+        base_units: BigInt,
+    ) -> Result<Self, AmountError> {
+        if base_units.sign() == Sign::Minus {
+            return Err(AmountError::Negative {
+                coin,
+                amount: BigDecimal::new(base_units, decimals as i64),
+            });
+        }
+        Ok(CoinAmount {
+            coin,
+            decimals,
+            base_units,
+        })
+    }
+
+    // amount in coin unit of the native coin of the blockchain
+    pub fn native(blockchain: &Blockchain, amount: &BigDecimal) -> Result<Self, AmountError> {
+        CoinAmount::from_coin_unit(
+            blockchain.native_coin(),
+            blockchain.native_decimals(),
+            amount,
+        )
+    }
+
+    // amount in coin unit using decimals of the coin config
+    pub fn from_config(
+        config: &BlockchainCoinConfig,
+        amount: &BigDecimal,
+    ) -> Result<Self, AmountError> {
+        CoinAmount::from_coin_unit(
+            config.coin.clone(),
+            checked_decimals(&config.coin, config.decimals)?,
+            amount,
+        )
+    }
+
+    pub fn zero(coin: Coin, decimals: u32) -> Self {
+        CoinAmount {
+            coin,
+            decimals,
+            base_units: BigInt::zero(),
+        }
+    }
+
+    pub fn coin(&self) -> &Coin {
+        &self.coin
+    }
+
+    pub fn decimals(&self) -> u32 {
+        self.decimals
+    }
+
+    pub fn base_units(&self) -> &BigInt {
+        &self.base_units
+    }
+
+    // base units when they fit in u64 e.g. satoshi or lovelace
+    pub fn to_u64(&self) -> Option<u64> {
+        self.base_units.to_u64()
+    }
+
+    // base units when they fit in u128 e.g. wei
+    pub fn to_u128(&self) -> Option<u128> {
+        self.base_units.to_u128()
+    }
+
+    // amount in coin unit with exactly `decimals` decimal places
+    pub fn to_coin_unit(&self) -> BigDecimal {
+        BigDecimal::new(self.base_units.clone(), self.decimals as i64)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.base_units.is_zero()
+    }
+
+    pub fn checked_add(&self, other: &CoinAmount) -> Result<CoinAmount, AmountError> {
+        self.check_same_coin(other)?;
+        Ok(CoinAmount {
+            base_units: &self.base_units + &other.base_units,
+            ..self.clone()
+        })
+    }
+
+    // fails with Negative when other is larger than self
+    pub fn checked_sub(&self, other: &CoinAmount) -> Result<CoinAmount, AmountError> {
+        self.check_same_coin(other)?;
+        CoinAmount::from_base_units(
+            self.coin.clone(),
+            self.decimals,
+            &self.base_units - &other.base_units,
+        )
+    }
+
+    pub fn checked_cmp(&self, other: &CoinAmount) -> Result<Ordering, AmountError> {
+        self.check_same_coin(other)?;
+        Ok(self.base_units.cmp(&other.base_units))
+    }
+
+    fn check_same_coin(&self, other: &CoinAmount) -> Result<(), AmountError> {
+        if self.coin != other.coin || self.decimals != other.decimals {
+            return Err(AmountError::CoinMismatch {
+                left: self.coin.clone(),
+                right: other.coin.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for CoinAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {:?}", self.to_coin_unit(), self.coin)
+    }
+}
+
+impl Serialize for CoinAmount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_coin_unit().serialize(serializer)
+    }
+}
+
+impl SendRequest {
+    // amount of the native coin of the blockchain
+    pub fn coin_amount(&self, blockchain: &Blockchain) -> Result<CoinAmount, AmountError> {
+        CoinAmount::native(blockchain, &self.amount)
+    }
+}
+
+impl SendTokenRequest {
+    // amount of the token using decimals of the request
+    pub fn coin_amount(&self, coin: Coin) -> Result<CoinAmount, AmountError> {
+        let decimals = checked_decimals(&coin, self.decimals)?;
+        CoinAmount::from_coin_unit(coin, decimals, &self.amount)
+    }
+}
+
+impl EthContractRequest {
+    // amount of the native coin sent to the contract
+    pub fn coin_amount(&self, blockchain: &Blockchain) -> Result<CoinAmount, AmountError> {
+        CoinAmount::native(blockchain, &self.amount)
+    }
+}
+
+impl UnspentOutput {
+    // unspent amount of the native coin of the blockchain
+    pub fn coin_amount(&self, blockchain: &Blockchain) -> Result<CoinAmount, AmountError> {
+        CoinAmount::native(blockchain, &self.amount)
+    }
+}
+
+fn checked_decimals(coin: &Coin, decimals: i32) -> Result<u32, AmountError> {
+    u32::try_from(decimals).map_err(|_| AmountError::InvalidDecimals {
+        coin: coin.clone(),
+        decimals,
+    })
+}
+
+// Amount in scientific notation when writing it in full would take more than MAX_DIGITS zeros,
+// so an error about an absurd exponent stays short
+pub(crate) fn display_amount(amount: &BigDecimal) -> String {
+    let (digits, scale) = amount.as_bigint_and_exponent();
+    if scale.unsigned_abs() > MAX_DIGITS as u64 {
+        return format!("{}E{}", digits, scale.saturating_neg());
+    }
+    amount.to_string()
+}
+
+// Exact value of a whole number with at most MAX_DIGITS digits. None when it has a fraction or is
+// larger. The exponent is checked before any power of ten is computed.
+pub(crate) fn whole_number(amount: &BigDecimal) -> Option<BigInt> {
+    let (digits, scale) = strip_trailing_zeros(amount);
+    if scale > 0 {
+        return None;
+    }
+    let shift = scale.checked_neg()?;
+    if shift > MAX_DIGITS || digit_count(&digits) + shift > MAX_DIGITS {
+        return None;
+    }
+    Some(digits * pow10(shift as u32))
+}
+
+// number of decimal digits without the sign
+pub(crate) fn digit_count(value: &BigInt) -> i64 {
+    value.magnitude().to_string().len() as i64
+}
+
+// amount without trailing zeros. BigDecimal::normalized is not used as its cost grows with the
+// exponent
+pub(crate) fn normalized(amount: &BigDecimal) -> BigDecimal {
+    let (digits, scale) = strip_trailing_zeros(amount);
+    BigDecimal::new(digits, scale)
+}
+
+// digits without trailing zeros and their scale. BigDecimal::normalized is not used as its cost
+// grows with the exponent
+pub(crate) fn strip_trailing_zeros(amount: &BigDecimal) -> (BigInt, i64) {
+    let (mut digits, mut scale) = amount.as_bigint_and_exponent();
+    if digits.is_zero() {
+        return (digits, 0);
+    }
+    let ten = BigInt::from(10u8);
+    while (&digits % &ten).is_zero() {
+        digits /= &ten;
+        scale = scale.saturating_sub(1);
+    }
+    (digits, scale)
+}
+
+pub(crate) fn pow10(exponent: u32) -> BigInt {
+    BigInt::from(10u8).pow(exponent)
+}
@@ -0,0 +1,354 @@
+use std::fmt;
+
+use sha3::{Digest, Keccak256};
+
+use crate::bitcoin::transaction::BtcTransaction;
+use crate::cardano::transaction::{body_hash, with_vkey_witnesses};
+use crate::derivation::hash160;
+use crate::encoding::{HexBytes, PubkeyHex};
+use crate::evm::transaction::EvmTransaction;
+use crate::sighash::{spent_output, verify_hashes, SighashError, SpentScript};
+use crate::signature::SIGHASH_ALL;
+use crate::transaction::TransactionDecodeError;
+use crate::verification::{SignatureFailure, VerificationError, VerificationOutcome};
+use crate::{Blockchain, CreateSignTransactionRequest, CreateSignTransactionResult, UnspentOutput};
+
+// length of a compressed secp256k1 public key, the only form segwit accepts
+const COMPRESSED_PUBKEY_LEN: usize = 33;
+
+// Reason why a signed transaction cannot be assembled
+#[derive(Clone, PartialEq, Debug)]
+pub enum AssemblyError {
+    Verification(VerificationError),
+    // hash has no signature
+    MissingSignature {
+        index: usize,
+    },
+    // signature does not prove the pubkey signed the hash
+    InvalidSignature {
+        index: usize,
+        failure: SignatureFailure,
+    },
+    // raw transaction cannot be decoded or the hashes are not the ones it requires
+    Sighash(SighashError),
+    // P2WPKH inputs can only be spent with a compressed pubkey
+    UncompressedPubkey {
+        index: usize,
+    },
+    // spent output is locked to the hash of another key than pubkey
+    ForeignScript {
+        index: usize,
+    },
+}
+
+impl fmt::Display for AssemblyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssemblyError::Verification(error) => write!(f, "{}", error),
+            AssemblyError::MissingSignature { index } => {
+                write!(f, "hash {} has no signature", index)
+            }
+            AssemblyError::InvalidSignature { index, failure } => {
+                write!(f, "signature of hash {}: {}", index, failure)
+            }
+            AssemblyError::Sighash(error) => write!(f, "{}", error),
+            AssemblyError::UncompressedPubkey { index } => write!(
+                f,
+                "input {} is segwit and cannot be signed with an uncompressed pubkey",
+                index
+            ),
+            AssemblyError::ForeignScript { index } => {
+                write!(f, "input {} spends an output of another key", index)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AssemblyError {}
+
+impl From<VerificationError> for AssemblyError {
+    fn from(error: VerificationError) -> Self {
+        AssemblyError::Verification(error)
+    }
+}
+
+impl From<SighashError> for AssemblyError {
+    fn from(error: SighashError) -> Self {
+        AssemblyError::Sighash(error)
+    }
+}
+
+impl From<TransactionDecodeError> for AssemblyError {
+    fn from(error: TransactionDecodeError) -> Self {
+        AssemblyError::Sighash(SighashError::InvalidTransaction(error))
+    }
+}
+
+impl CreateSignTransactionRequest {
+    // Signed transaction in hex and its hash. Every signature is verified against pubkey first.
+    // BITCOIN: scriptSig of P2PKH inputs or witness of P2WPKH inputs, hash is the txid
+    // ETHEREUM, POLYGON: v, r, s of legacy or yParity, r, s of EIP-1559 transactions, hash is
+    // keccak256 of the signed transaction
+    // CARDANO: vkey witness of pubkey, hash is the transaction id
+    // unspent outputs are only needed for Bitcoin
+    pub fn assemble(
+        &self,
+        unspent_outputs: &[UnspentOutput],
+    ) -> Result<CreateSignTransactionResult, AssemblyError> {
+        for verification in self.verify_signatures()? {
+            match verification.outcome {
+                VerificationOutcome::Valid => {}
+                VerificationOutcome::MissingSignature => {
+                    return Err(AssemblyError::MissingSignature {
+                        index: verification.index,
+                    })
+                }
+                VerificationOutcome::Failed(failure) => {
+                    return Err(AssemblyError::InvalidSignature {
+                        index: verification.index,
+                        failure,
+                    })
+                }
+            }
+        }
+        let bytes = HexBytes::try_from(self.raw_transaction.trim())
+            .map_err(TransactionDecodeError::from)?
+            .to_bytes();
+        let (signed_transaction, transaction_hash) = match self.blockchain {
+            Blockchain::BITCOIN => self.assemble_bitcoin(&bytes, unspent_outputs)?,
+            Blockchain::ETHEREUM | Blockchain::POLYGON => self.assemble_evm(&bytes)?,
+            Blockchain::CARDANO => self.assemble_cardano(&bytes)?,
+        };
+        Ok(CreateSignTransactionResult {
+            signed_transaction: hex::encode(signed_transaction),
+            transaction_hash: hex::encode(transaction_hash),
+        })
+    }
+
+    // The script of the spent output decides how an input is spent: P2PKH with a scriptSig and
+    // P2WPKH with a witness. The hashes are recomputed from the spent outputs first.
+    fn assemble_bitcoin(
+        &self,
+        bytes: &[u8],
+        unspent_outputs: &[UnspentOutput],
+    ) -> Result<(Vec<u8>, [u8; 32]), AssemblyError> {
+        verify_hashes(
+            &self.blockchain,
+            &self.raw_transaction,
+            unspent_outputs,
+            &self.hashes,
+        )?;
+        let mut transaction =
+            BtcTransaction::decode(bytes).map_err(TransactionDecodeError::from)?;
+        let pubkey = self.pubkey_bytes()?;
+        let pubkey_hash = hash160(&pubkey);
+        for index in 0..transaction.inputs.len() {
+            let signature = self.signatures[index]
+                .to_bitcoin(SIGHASH_ALL)
+                .map_err(|error| AssemblyError::InvalidSignature {
+                    index,
+                    failure: SignatureFailure::Malformed(error),
+                })?;
+            let (_, script) = spent_output(&transaction, index, unspent_outputs)?;
+            let spent_script =
+                SpentScript::parse(&script).ok_or_else(|| SighashError::UnsupportedScript {
+                    index,
+                    script: hex::encode(&script),
+                })?;
+            let input = &mut transaction.inputs[index];
+            match spent_script {
+                SpentScript::P2pkh { key_hash } | SpentScript::P2wpkh { key_hash }
+                    if key_hash != pubkey_hash.as_slice() =>
+                {
+                    return Err(AssemblyError::ForeignScript { index });
+                }
+                SpentScript::P2pkh { .. } => {
+                    input.script_sig = Vec::new();
+                    push_data(&mut input.script_sig, &signature);
+                    push_data(&mut input.script_sig, &pubkey);
+                    input.witness = Vec::new();
+                }
+                SpentScript::P2wpkh { .. } if pubkey.len() != COMPRESSED_PUBKEY_LEN => {
+                    return Err(AssemblyError::UncompressedPubkey { index });
+                }
+                SpentScript::P2wpkh { .. } => {
+                    input.script_sig = Vec::new();
+                    input.witness = vec![signature, pubkey.clone()];
+                }
+            }
+        }
+        Ok((transaction.encode(), transaction.txid()))
+    }
+
+    fn assemble_evm(&self, bytes: &[u8]) -> Result<(Vec<u8>, [u8; 32]), AssemblyError> {
+        verify_hashes(&self.blockchain, &self.raw_transaction, &[], &self.hashes)?;
+        let transaction = EvmTransaction::decode(bytes).map_err(TransactionDecodeError::from)?;
+        let signed = transaction
+            .encode_signed(&self.signatures[0])
+            .map_err(|error| AssemblyError::InvalidSignature {
+                index: 0,
+                failure: SignatureFailure::Malformed(error),
+            })?;
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&Keccak256::digest(&signed));
+        Ok((signed, hash))
+    }
+
+    fn assemble_cardano(&self, bytes: &[u8]) -> Result<(Vec<u8>, [u8; 32]), AssemblyError> {
+        verify_hashes(&self.blockchain, &self.raw_transaction, &[], &self.hashes)?;
+        let mut vkey = [0u8; 32];
+        vkey.copy_from_slice(&self.pubkey_bytes()?);
+        let witnesses: Vec<([u8; 32], [u8; 64])> = self
+            .signatures
+            .iter()
+            .map(|signature| (vkey, signature.to_ed25519()))
+            .collect();
+        let signed =
+            with_vkey_witnesses(bytes, &witnesses).map_err(TransactionDecodeError::from)?;
+        let hash = body_hash(bytes).map_err(TransactionDecodeError::from)?;
+        Ok((signed, hash))
+    }
+
+    fn pubkey_bytes(&self) -> Result<Vec<u8>, AssemblyError> {
+        let key_scheme = self.blockchain.key_scheme();
+        PubkeyHex::parse(&key_scheme, &self.pubkey)
+            .map(|pubkey| pubkey.to_bytes())
+            .map_err(|error| VerificationError::InvalidPubkey { key_scheme, error }.into())
+    }
+}
+
+// push of up to 75 bytes, enough for a DER signature and a public key
+fn push_data(script: &mut Vec<u8>, data: &[u8]) {
+    script.push(data.len() as u8);
+    script.extend_from_slice(data);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+    use k256::ecdsa::SigningKey;
+
+    use super::*;
+    use crate::bitcoin::sighash::tests::BIP143_TRANSACTION;
+    use crate::encoding::Hash32Hex;
+    use crate::sighash::signing_hashes;
+    use crate::{Coin, SignatureRecidHex};
+
+    // key of input 1 of the BIP-143 native P2WPKH example
+    const PRIVATE_KEY: &str = "619c335025c7f4012e556c2a58b2506e30b8511b53ade95ea316fd8c3286feb9";
+    const PUBKEY: &str = "025476c2e83188368da1ff3e292e7acafcdb3566bb0ad253f62fc70f07aeee6357";
+    // witness signature of input 1 in the signed transaction of the example
+    const BIP143_SIGNATURE: &str = "304402203609e17b84f6a7d30c80bfa610b5b4542f32a8a0d5447a12fb\
+        1366d7f01cc44a0220573a954c4518331561406f90300e8f3358f51928d43c212a8caed02de67eebee01";
+
+    // outputs spent by the BIP-143 example with input 0 spending P2PKH of the key of input 1
+    fn unspent_outputs(key_hash: &[u8], amount: &str) -> Vec<UnspentOutput> {
+        let key_hash = hex::encode(key_hash);
+        vec![
+            UnspentOutput {
+                transaction_hash:
+                    "9f96ade4b41d5433f4eda31e1738ec2b36f6e7d1420d94a6af99801a88f7f7ff".to_string(),
+                index: 0,
+                script: format!("76a914{}88ac", key_hash),
+                amount: BigDecimal::from_str("6.25").unwrap(),
+            },
+            UnspentOutput {
+                transaction_hash:
+                    "8ac60eb9575db5b2d987e29f301b5b819ea83a5c6579d282d189cc04b8e151ef".to_string(),
+                index: 1,
+                script: format!("0014{}", key_hash),
+                amount: BigDecimal::from_str(amount).unwrap(),
+            },
+        ]
+    }
+
+    fn sign(hash: &Hash32Hex) -> SignatureRecidHex {
+        let key = SigningKey::from_slice(&hex::decode(PRIVATE_KEY).unwrap()).unwrap();
+        let (signature, recid) = key.sign_prehash_recoverable(&hash.to_bytes()).unwrap();
+        let (r, s) = signature.split_bytes();
+        SignatureRecidHex::from_parts(r.into(), s.into(), recid.to_byte())
+    }
+
+    fn request(pubkey: &str, unspent_outputs: &[UnspentOutput]) -> CreateSignTransactionRequest {
+        let hashes =
+            signing_hashes(&Blockchain::BITCOIN, BIP143_TRANSACTION, unspent_outputs).unwrap();
+        CreateSignTransactionRequest {
+            blockchain: Blockchain::BITCOIN,
+            coin: Coin::BTC,
+            pubkey: pubkey.to_string(),
+            raw_transaction: BIP143_TRANSACTION.to_string(),
+            signatures: hashes.iter().map(sign).collect(),
+            hashes,
+        }
+    }
+
+    fn pubkey_hash() -> [u8; 20] {
+        hash160(&hex::decode(PUBKEY).unwrap())
+    }
+
+    #[test]
+    fn spends_p2pkh_with_script_sig_and_p2wpkh_with_witness() {
+        let unspent_outputs = unspent_outputs(&pubkey_hash(), "6");
+        let request = request(PUBKEY, &unspent_outputs);
+
+        let result = request.assemble(&unspent_outputs).unwrap();
+
+        let signed =
+            BtcTransaction::decode(&hex::decode(&result.signed_transaction).unwrap()).unwrap();
+        let mut script_sig = Vec::new();
+        push_data(
+            &mut script_sig,
+            &request.signatures[0].to_bitcoin(SIGHASH_ALL).unwrap(),
+        );
+        push_data(&mut script_sig, &hex::decode(PUBKEY).unwrap());
+        assert_eq!(signed.inputs[0].script_sig, script_sig);
+        assert!(signed.inputs[0].witness.is_empty());
+        assert!(signed.inputs[1].script_sig.is_empty());
+        assert_eq!(
+            signed.inputs[1].witness,
+            vec![
+                hex::decode(BIP143_SIGNATURE).unwrap(),
+                hex::decode(PUBKEY).unwrap()
+            ]
+        );
+        assert_eq!(result.transaction_hash, hex::encode(signed.txid()));
+    }
+
+    #[test]
+    fn rejects_hashes_of_other_spent_amount() {
+        let request = request(PUBKEY, &unspent_outputs(&pubkey_hash(), "6"));
+
+        let error = request
+            .assemble(&unspent_outputs(&pubkey_hash(), "0.06"))
+            .unwrap_err();
+
+        assert!(matches!(
+            error,
+            AssemblyError::Sighash(SighashError::HashMismatch { index: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn rejects_outputs_of_other_key() {
+        let unspent_outputs = unspent_outputs(&[0x22; 20], "6");
+        let request = request(PUBKEY, &unspent_outputs);
+
+        let error = request.assemble(&unspent_outputs).unwrap_err();
+
+        assert_eq!(error, AssemblyError::ForeignScript { index: 0 });
+    }
+
+    #[test]
+    fn rejects_uncompressed_pubkey_for_p2wpkh() {
+        let key = SigningKey::from_slice(&hex::decode(PRIVATE_KEY).unwrap()).unwrap();
+        let uncompressed = key.verifying_key().to_encoded_point(false);
+        let unspent_outputs = unspent_outputs(&hash160(uncompressed.as_bytes()), "6");
+        let request = request(&hex::encode(uncompressed.as_bytes()), &unspent_outputs);
+
+        let error = request.assemble(&unspent_outputs).unwrap_err();
+
+        assert_eq!(error, AssemblyError::UncompressedPubkey { index: 1 });
+    }
+}
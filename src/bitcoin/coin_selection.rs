@@ -0,0 +1,515 @@
+use std::cmp::Reverse;
+use std::fmt;
+
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, Signed, ToPrimitive, Zero};
+
+use crate::amount::{display_amount, AmountError, CoinAmount};
+use crate::{Blockchain, RequestParamsBtc, SendRequest, UnspentOutput};
+
+// Transaction sizes in weight units. 4 weight units are one virtual byte.
+// version, locktime, input and output counts
+const OVERHEAD_WEIGHT: u64 = 10 * 4;
+// segwit marker and flag
+const SEGWIT_OVERHEAD_WEIGHT: u64 = 2;
+// outpoint, sequence and scriptSig with signature and compressed pubkey
+const P2PKH_INPUT_WEIGHT: u64 = 148 * 4;
+// outpoint, sequence and empty scriptSig plus witness with signature and compressed pubkey
+const P2WPKH_INPUT_WEIGHT: u64 = 41 * 4 + 108;
+// amount and script length of an output, the script itself is added
+const OUTPUT_BASE_WEIGHT: u64 = 9 * 4;
+const P2PKH_SCRIPT_LEN: usize = 25;
+const P2WPKH_SCRIPT_LEN: usize = 22;
+
+// outputs below these amounts are not relayed by the network
+const P2PKH_DUST: u64 = 546;
+const P2WPKH_DUST: u64 = 294;
+
+// digits of u64::MAX
+const U64_DIGITS: i64 = 20;
+
+// maximum number of branches the branch and bound search visits
+const BNB_MAX_TRIES: usize = 100_000;
+// the search recurses once per candidate so wallets with more outputs are selected largest first
+const BNB_MAX_CANDIDATES: usize = 1_000;
+
+// Reason why outputs cannot be selected
+#[derive(Clone, PartialEq, Debug)]
+pub enum CoinSelectionError {
+    // unspent outputs cannot pay the amount plus fee. Amounts are in satoshi
+    InsufficientFunds {
+        required: u64,
+        available: u64,
+        shortfall: u64,
+    },
+    // amount to send is below the dust limit
+    DustAmount {
+        amount: u64,
+        dust_limit: u64,
+    },
+    // fee per byte must be a non negative number
+    InvalidFeeRate {
+        fee_rate: BigDecimal,
+    },
+    // an amount does not fit in 64 bits of satoshi
+    AmountTooLarge {
+        amount: BigDecimal,
+    },
+    // an amount cannot be converted to satoshi
+    InvalidAmount(AmountError),
+}
+
+impl fmt::Display for CoinSelectionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CoinSelectionError::InsufficientFunds {
+                required,
+                available,
+                shortfall,
+            } => write!(
+                f,
+                "insufficient funds: required {} sat, available {} sat, short of {} sat",
+                required, available, shortfall
+            ),
+            CoinSelectionError::DustAmount { amount, dust_limit } => write!(
+                f,
+                "amount {} sat is below dust limit {} sat",
+                amount, dust_limit
+            ),
+            CoinSelectionError::InvalidFeeRate { fee_rate } => {
+                write!(f, "invalid fee per byte {}", display_amount(fee_rate))
+            }
+            CoinSelectionError::AmountTooLarge { amount } => {
+                write!(f, "amount {} BTC is too large", display_amount(amount))
+            }
+            CoinSelectionError::InvalidAmount(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for CoinSelectionError {}
+
+impl From<AmountError> for CoinSelectionError {
+    fn from(error: AmountError) -> Self {
+        CoinSelectionError::InvalidAmount(error)
+    }
+}
+
+// What to pay and how the wallet spends
+#[derive(Clone, PartialEq, Debug)]
+pub struct SelectionTarget {
+    // amount to send in satoshi
+    pub amount: u64,
+    // fee rate in satoshi per virtual byte
+    pub fee_rate: BigDecimal,
+    // wallet inputs and change are P2WPKH when true, P2PKH otherwise
+    pub is_segwit: bool,
+    // length of the recipient scriptPubKey
+    pub recipient_script_len: usize,
+}
+
+impl SelectionTarget {
+    // target where the recipient uses the same script type as the wallet
+    pub fn new(amount: u64, fee_rate: BigDecimal, is_segwit: bool) -> Self {
+        SelectionTarget {
+            amount,
+            fee_rate,
+            is_segwit,
+            recipient_script_len: wallet_script_len(is_segwit),
+        }
+    }
+}
+
+// Selected outputs to spend. Amounts are in satoshi
+#[derive(Clone, PartialEq, Debug)]
+pub struct CoinSelection {
+    pub inputs: Vec<UnspentOutput>,
+    pub input_total: u64,
+    pub amount: u64,
+    // change back to the wallet. Zero when there is no change output
+    pub change: u64,
+    pub fee: u64,
+    // estimated virtual size of the signed transaction
+    pub vsize: u64,
+}
+
+impl CoinSelection {
+    pub fn has_change(&self) -> bool {
+        self.change > 0
+    }
+}
+
+// virtual size of a transaction spending the wallet inputs to the outputs with the given script lengths
+pub fn estimate_vsize(input_count: usize, is_segwit: bool, output_script_lens: &[usize]) -> u64 {
+    let mut weight = OVERHEAD_WEIGHT + input_count as u64 * input_weight(is_segwit);
+    if is_segwit && input_count > 0 {
+        weight += SEGWIT_OVERHEAD_WEIGHT;
+    }
+    for script_len in output_script_lens {
+        weight += output_weight(*script_len);
+    }
+    (weight + 3) / 4
+}
+
+// Select unspent outputs paying the target amount and fee.
+// Branch and bound looks for a set of outputs that needs no change output. When there is none, or there are
+// too many outputs to search, outputs are added largest first and the rest is returned as change. Change below the dust limit is left to the fee.
+pub fn select_coins(
+    unspent_outputs: &[UnspentOutput],
+    target: &SelectionTarget,
+) -> Result<CoinSelection, CoinSelectionError> {
+    let fee_rate = FeeRate::new(&target.fee_rate)?;
+    let dust_limit = dust_limit(target.is_segwit);
+    if target.amount < dust_limit {
+        return Err(CoinSelectionError::DustAmount {
+            amount: target.amount,
+            dust_limit,
+        });
+    }
+
+    let mut candidates = Vec::with_capacity(unspent_outputs.len());
+    for output in unspent_outputs {
+        let value = satoshi(&output.coin_amount(&Blockchain::BITCOIN)?)?;
+        candidates.push((output, value));
+    }
+    candidates.sort_by_key(|(_, value)| Reverse(*value));
+    // every sum of selected outputs fits in 64 bits when the sum of all of them does
+    let values: Vec<u64> = candidates.iter().map(|(_, value)| *value).collect();
+    checked_sum(&values)?;
+
+    let input_fee = fee_rate.fee_for_weight(input_weight(target.is_segwit))?;
+    let selection = |selected: Vec<(&UnspentOutput, u64)>, change: u64, fee: u64| {
+        let input_total = selected.iter().map(|(_, value)| value).sum();
+        let vsize = estimate_vsize(
+            selected.len(),
+            target.is_segwit,
+            &output_script_lens(target, change > 0),
+        );
+        CoinSelection {
+            inputs: selected
+                .into_iter()
+                .map(|(output, _)| output.clone())
+                .collect(),
+            input_total,
+            amount: target.amount,
+            change,
+            fee,
+            vsize,
+        }
+    };
+
+    // outputs worth less than the fee to spend them are never selected by branch and bound
+    let effective: Vec<(usize, u64)> = candidates
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, value))| *value > input_fee)
+        .map(|(index, (_, value))| (index, value - input_fee))
+        .collect();
+    let fixed_fee_no_change = fee_rate.fee_for_vsize(estimate_vsize(
+        0,
+        target.is_segwit,
+        &output_script_lens(target, false),
+    ))?;
+    let change_output_fee =
+        fee_rate.fee_for_weight(output_weight(wallet_script_len(target.is_segwit)))?;
+    let cost_of_change = checked_sum(&[change_output_fee, input_fee])?;
+    let bnb_target = checked_sum(&[target.amount, fixed_fee_no_change])?;
+    let exact_match = if effective.len() <= BNB_MAX_CANDIDATES {
+        branch_and_bound(&effective, bnb_target, cost_of_change)
+    } else {
+        None
+    };
+    if let Some(indices) = exact_match {
+        let selected: Vec<(&UnspentOutput, u64)> =
+            indices.into_iter().map(|index| candidates[index]).collect();
+        let input_total: u64 = selected.iter().map(|(_, value)| value).sum();
+        let required_fee = fee_rate.fee_for_vsize(estimate_vsize(
+            selected.len(),
+            target.is_segwit,
+            &output_script_lens(target, false),
+        ))?;
+        // per input fees are rounded separately so check the whole transaction fee is still covered
+        if input_total >= checked_sum(&[target.amount, required_fee])? {
+            let fee = input_total - target.amount;
+            return Ok(selection(selected, 0, fee));
+        }
+    }
+
+    let mut selected = Vec::new();
+    let mut input_total = 0u64;
+    for candidate in &candidates {
+        selected.push(*candidate);
+        input_total += candidate.1;
+        let fee_with_change = fee_rate.fee_for_vsize(estimate_vsize(
+            selected.len(),
+            target.is_segwit,
+            &output_script_lens(target, true),
+        ))?;
+        if input_total >= checked_sum(&[target.amount, fee_with_change, dust_limit])? {
+            let change = input_total - target.amount - fee_with_change;
+            return Ok(selection(selected, change, fee_with_change));
+        }
+        let fee_without_change = fee_rate.fee_for_vsize(estimate_vsize(
+            selected.len(),
+            target.is_segwit,
+            &output_script_lens(target, false),
+        ))?;
+        if input_total >= checked_sum(&[target.amount, fee_without_change])? {
+            // change would be dust so it is left to the miner
+            let fee = input_total - target.amount;
+            return Ok(selection(selected, 0, fee));
+        }
+    }
+
+    let required = checked_sum(&[
+        target.amount,
+        fee_rate.fee_for_vsize(estimate_vsize(
+            candidates.len(),
+            target.is_segwit,
+            &output_script_lens(target, false),
+        ))?,
+    ])?;
+    Err(CoinSelectionError::InsufficientFunds {
+        required,
+        available: input_total,
+        shortfall: required.saturating_sub(input_total),
+    })
+}
+
+impl RequestParamsBtc {
+    // select outputs to pay the send request at the fee per byte of the params
+    pub fn select_coins(
+        &self,
+        send_request: &SendRequest,
+        is_segwit: bool,
+    ) -> Result<CoinSelection, CoinSelectionError> {
+        self.select_coins_at_rate(send_request, self.fee_per_byte.clone(), is_segwit)
+    }
+
+    // select outputs to pay the send request at the given fee rate in satoshi per virtual byte
+    pub fn select_coins_at_rate(
+        &self,
+        send_request: &SendRequest,
+        fee_rate: BigDecimal,
+        is_segwit: bool,
+    ) -> Result<CoinSelection, CoinSelectionError> {
+        let amount = send_request.coin_amount(&Blockchain::BITCOIN)?;
+        let target = SelectionTarget::new(satoshi(&amount)?, fee_rate, is_segwit);
+        select_coins(&self.unspent_outputs, &target)
+    }
+}
+
+// Depth first search for the subset of effective values in [target, target + tolerance] with the least excess.
+// Values must be sorted in descending order. Returns indices of the selected candidates.
+fn branch_and_bound(values: &[(usize, u64)], target: u64, tolerance: u64) -> Option<Vec<usize>> {
+    struct Search<'a> {
+        values: &'a [(usize, u64)],
+        target: u64,
+        upper: u64,
+        tries: usize,
+        current: Vec<usize>,
+        best: Option<(u64, Vec<usize>)>,
+    }
+
+    impl<'a> Search<'a> {
+        fn run(&mut self, depth: usize, selected: u64, remaining: u64) {
+            self.tries += 1;
+            if self.tries > BNB_MAX_TRIES
+                || selected > self.upper
+                || selected + remaining < self.target
+            {
+                return;
+            }
+            if selected >= self.target {
+                let excess = selected - self.target;
+                if self.best.as_ref().map_or(true, |(best, _)| excess < *best) {
+                    self.best = Some((excess, self.current.clone()));
+                }
+                return;
+            }
+            if depth == self.values.len() {
+                return;
+            }
+            let (index, value) = self.values[depth];
+            self.current.push(index);
+            self.run(depth + 1, selected + value, remaining - value);
+            self.current.pop();
+            self.run(depth + 1, selected, remaining - value);
+        }
+    }
+
+    let mut search = Search {
+        values,
+        target,
+        upper: target.saturating_add(tolerance),
+        tries: 0,
+        current: Vec::new(),
+        best: None,
+    };
+    let total = values.iter().map(|(_, value)| value).sum();
+    search.run(0, 0, total);
+    search.best.map(|(_, indices)| indices)
+}
+
+// fee rate in millisatoshi per virtual byte so fractional satoshi rates stay exact
+struct FeeRate {
+    millis: u64,
+    fee_per_byte: BigDecimal,
+}
+
+impl FeeRate {
+    fn new(fee_per_byte: &BigDecimal) -> Result<Self, CoinSelectionError> {
+        let invalid = || CoinSelectionError::InvalidFeeRate {
+            fee_rate: fee_per_byte.clone(),
+        };
+        if fee_per_byte.is_negative() {
+            return Err(invalid());
+        }
+        // x 1000 millisatoshi. The exponent comes from the request so it is bounded before a
+        // power of ten is computed
+        let (digits, scale) = fee_per_byte.as_bigint_and_exponent();
+        let scale = scale.saturating_sub(3);
+        let digit_count = digits.to_string().len() as i64;
+        let rounded_up = if digits.is_zero() {
+            digits
+        } else if scale >= digit_count {
+            // a fraction of a millisatoshi
+            BigInt::from(1u8)
+        } else if scale > 0 {
+            let divisor = BigInt::from(10u8).pow(scale as u32);
+            let quotient = &digits / &divisor;
+            if (&digits % &divisor).is_zero() {
+                quotient
+            } else {
+                quotient + 1
+            }
+        } else if digit_count.saturating_sub(scale) > U64_DIGITS {
+            return Err(invalid());
+        } else {
+            digits * BigInt::from(10u8).pow((-scale) as u32)
+        };
+        let millis = rounded_up.to_u64().ok_or_else(invalid)?;
+        Ok(FeeRate {
+            millis,
+            fee_per_byte: fee_per_byte.clone(),
+        })
+    }
+
+    fn fee_for_vsize(&self, vsize: u64) -> Result<u64, CoinSelectionError> {
+        self.fee(vsize, 1000)
+    }
+
+    fn fee_for_weight(&self, weight: u64) -> Result<u64, CoinSelectionError> {
+        self.fee(weight, 4000)
+    }
+
+    // size x rate / divisor rounded up. Fails when a rate is so high that the fee does not fit
+    fn fee(&self, size: u64, divisor: u64) -> Result<u64, CoinSelectionError> {
+        size.checked_mul(self.millis)
+            .and_then(|product| product.checked_add(divisor - 1))
+            .map(|product| product / divisor)
+            .ok_or_else(|| CoinSelectionError::InvalidFeeRate {
+                fee_rate: self.fee_per_byte.clone(),
+            })
+    }
+}
+
+// sum of satoshi amounts that must fit in 64 bits
+fn checked_sum(amounts: &[u64]) -> Result<u64, CoinSelectionError> {
+    amounts
+        .iter()
+        .try_fold(0u64, |sum, amount| sum.checked_add(*amount))
+        .ok_or_else(|| CoinSelectionError::AmountTooLarge {
+            amount: BigDecimal::new(
+                BigInt::from(amounts.iter().map(|amount| *amount as u128).sum::<u128>()),
+                Blockchain::BITCOIN.native_decimals() as i64,
+            ),
+        })
+}
+
+fn input_weight(is_segwit: bool) -> u64 {
+    if is_segwit {
+        P2WPKH_INPUT_WEIGHT
+    } else {
+        P2PKH_INPUT_WEIGHT
+    }
+}
+
+fn output_weight(script_len: usize) -> u64 {
+    OUTPUT_BASE_WEIGHT + script_len as u64 * 4
+}
+
+fn wallet_script_len(is_segwit: bool) -> usize {
+    if is_segwit {
+        P2WPKH_SCRIPT_LEN
+    } else {
+        P2PKH_SCRIPT_LEN
+    }
+}
+
+fn dust_limit(is_segwit: bool) -> u64 {
+    if is_segwit {
+        P2WPKH_DUST
+    } else {
+        P2PKH_DUST
+    }
+}
+
+fn output_script_lens(target: &SelectionTarget, with_change: bool) -> Vec<usize> {
+    let mut lens = vec![target.recipient_script_len];
+    if with_change {
+        lens.push(wallet_script_len(target.is_segwit));
+    }
+    lens
+}
+
+fn satoshi(amount: &CoinAmount) -> Result<u64, CoinSelectionError> {
+    amount
+        .to_u64()
+        .ok_or_else(|| CoinSelectionError::AmountTooLarge {
+            amount: amount.to_coin_unit(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn unspent_output(index: i32, amount: &str) -> UnspentOutput {
+        UnspentOutput {
+            transaction_hash: "00".repeat(32),
+            index,
+            script: String::new(),
+            amount: BigDecimal::from_str(amount).unwrap(),
+        }
+    }
+
+    fn target(amount: u64) -> SelectionTarget {
+        SelectionTarget::new(amount, BigDecimal::from(1), true)
+    }
+
+    #[test]
+    fn exact_match_needs_no_change() {
+        let outputs = [
+            unspent_output(0, "0.001"),
+            unspent_output(1, "0.0005"),
+            unspent_output(2, "0.0002"),
+        ];
+        let selection = select_coins(&outputs, &target(49_850)).unwrap();
+        assert!(!selection.has_change());
+        assert_eq!(selection.inputs, vec![outputs[1].clone()]);
+    }
+
+    // branch and bound would recurse once per output
+    #[test]
+    fn many_outputs_are_selected_largest_first() {
+        let outputs: Vec<UnspentOutput> = (0..200_000)
+            .map(|index| unspent_output(index, "0.00001"))
+            .collect();
+        let selection = select_coins(&outputs, &target(5_000)).unwrap();
+        assert!(selection.has_change());
+        assert!(selection.inputs.len() < BNB_MAX_CANDIDATES);
+    }
+}
@@ -0,0 +1,3 @@
+pub mod coin_selection;
+pub mod sighash;
+pub mod transaction;
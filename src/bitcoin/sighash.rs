@@ -0,0 +1,126 @@
+use sha2::{Digest, Sha256};
+
+use crate::bitcoin::transaction::{write_bytes, BtcTransaction};
+use crate::signature::SIGHASH_ALL;
+
+impl BtcTransaction {
+    // Legacy SIGHASH_ALL hash of an input: scriptSigs are cleared, the signed input carries the
+    // script code of the spent output and the sighash type is appended
+    pub fn legacy_sighash(&self, index: usize, script_code: &[u8]) -> [u8; 32] {
+        let mut copy = self.clone();
+        for (position, input) in copy.inputs.iter_mut().enumerate() {
+            input.script_sig = if position == index {
+                script_code.to_vec()
+            } else {
+                Vec::new()
+            };
+            input.witness.clear();
+        }
+        let mut bytes = copy.encode_without_witness();
+        bytes.extend_from_slice(&(SIGHASH_ALL as u32).to_le_bytes());
+        double_sha256(&bytes)
+    }
+
+    // BIP-143 SIGHASH_ALL hash of a segwit v0 input. It commits to the amount of the spent output
+    // in satoshi
+    pub fn segwit_v0_sighash(&self, index: usize, script_code: &[u8], amount: u64) -> [u8; 32] {
+        let mut prevouts = Vec::new();
+        let mut sequences = Vec::new();
+        for input in &self.inputs {
+            input.write_outpoint(&mut prevouts);
+            sequences.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        let mut outputs = Vec::new();
+        for output in &self.outputs {
+            output.write(&mut outputs);
+        }
+        let input = &self.inputs[index];
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        bytes.extend_from_slice(&double_sha256(&prevouts));
+        bytes.extend_from_slice(&double_sha256(&sequences));
+        input.write_outpoint(&mut bytes);
+        write_bytes(&mut bytes, script_code);
+        bytes.extend_from_slice(&amount.to_le_bytes());
+        bytes.extend_from_slice(&input.sequence.to_le_bytes());
+        bytes.extend_from_slice(&double_sha256(&outputs));
+        bytes.extend_from_slice(&self.lock_time.to_le_bytes());
+        bytes.extend_from_slice(&(SIGHASH_ALL as u32).to_le_bytes());
+        double_sha256(&bytes)
+    }
+}
+
+// script code that a P2WPKH input signs: the P2PKH script of its key hash
+pub fn p2wpkh_script_code(key_hash: &[u8]) -> Vec<u8> {
+    let mut script = vec![0x76, 0xa9, 0x14];
+    script.extend_from_slice(key_hash);
+    script.extend_from_slice(&[0x88, 0xac]);
+    script
+}
+
+pub fn double_sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Sha256::digest(Sha256::digest(bytes)));
+    hash
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use k256::ecdsa::signature::hazmat::PrehashVerifier;
+    use k256::ecdsa::{Signature, VerifyingKey};
+
+    use super::*;
+
+    // unsigned transaction of the native P2WPKH example of BIP-143. Input 0 spends a P2PK output
+    // and input 1 spends P2WPKH 1d0f172a0ecb48aee1be1f2687d2963ae33f71a1 of 6 BTC.
+    pub(crate) const BIP143_TRANSACTION: &str = "0100000002fff7f7881a8099afa6940d42d1e7f6362bec38\
+        171ea3edf433541db4e4ad969f0000000000eeffffffef51e1b804cc89d182d279655c3aa89e815b1b309fe2\
+        87d9b2b55d57b90ec68a0100000000ffffffff02202cb206000000001976a9148280b37df378db99f66f85c9\
+        5a783a76ac7a6d5988ac9093510d000000001976a9143bde42dbee7e4dbe6a21b2d50ce2f0167faa815988ac\
+        11000000";
+    pub(crate) const BIP143_KEY_HASH: &str = "1d0f172a0ecb48aee1be1f2687d2963ae33f71a1";
+
+    fn bip143_transaction() -> BtcTransaction {
+        BtcTransaction::decode(&hex::decode(BIP143_TRANSACTION).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn segwit_v0_sighash_of_bip143_example() {
+        let script_code = p2wpkh_script_code(&hex::decode(BIP143_KEY_HASH).unwrap());
+        assert_eq!(
+            hex::encode(bip143_transaction().segwit_v0_sighash(1, &script_code, 600_000_000)),
+            "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670"
+        );
+    }
+
+    // the signed transaction of BIP-143 signs input 0 with a legacy hash of its P2PK script
+    #[test]
+    fn legacy_sighash_verifies_signature_of_bip143_example() {
+        let pubkey = "03c9f4836b9a4f77fc0d81f7bcb01b7f1b35916864b9476c241ce9fc198bd25432";
+        let signature = "30450221008b9d1dc26ba6a9cb62127b02742fa9d754cd3bebf337f7a55d114c8e5c\
+            dd30be022040529b194ba3f9281a99f2b1c0a19c0489bc22ede944ccf4ecbab4cc618ef3ed";
+        let mut script_code = vec![0x21];
+        script_code.extend(hex::decode(pubkey).unwrap());
+        script_code.push(0xac);
+
+        let hash = bip143_transaction().legacy_sighash(0, &script_code);
+
+        assert_eq!(
+            hex::encode(hash),
+            "63cec688ee06a91e913875356dd4dea2f8e0f2a2659885372da2a37e32c7532e"
+        );
+        let key = VerifyingKey::from_sec1_bytes(&hex::decode(pubkey).unwrap()).unwrap();
+        let signature = Signature::from_der(&hex::decode(signature).unwrap()).unwrap();
+        assert!(key.verify_prehash(&hash, &signature).is_ok());
+    }
+
+    // the P2WPKH script code is the P2PKH script of the key hash
+    #[test]
+    fn legacy_sighash_of_p2pkh_input() {
+        let script = p2wpkh_script_code(&hex::decode(BIP143_KEY_HASH).unwrap());
+        assert_eq!(
+            hex::encode(bip143_transaction().legacy_sighash(0, &script)),
+            "47194bc3c303a30aa5f78e45c7c2980b3be1284a9d69b1ea9ec0d29aac5f6848"
+        );
+    }
+}
@@ -0,0 +1,288 @@
+use std::fmt;
+
+use crate::bitcoin::sighash::double_sha256;
+
+// segwit serialization puts marker 0x00 and flag 0x01 after the version
+const SEGWIT_MARKER: u8 = 0x00;
+const SEGWIT_FLAG: u8 = 0x01;
+
+// Reason why bytes are not a serialized Bitcoin transaction
+#[derive(Clone, PartialEq, Debug)]
+pub enum BtcTransactionError {
+    // input ended in the middle of a field
+    UnexpectedEnd,
+    // compact size is not in its shortest form
+    NonCanonicalCompactSize,
+    // segwit marker is not followed by flag 1
+    InvalidSegwitFlag { flag: u8 },
+    // count of items exceeds the remaining bytes
+    InvalidCount { count: u64 },
+    // bytes left after the lock time
+    TrailingBytes { position: usize },
+}
+
+impl fmt::Display for BtcTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BtcTransactionError::UnexpectedEnd => write!(f, "unexpected end of transaction"),
+            BtcTransactionError::NonCanonicalCompactSize => {
+                write!(f, "compact size is not canonical")
+            }
+            BtcTransactionError::InvalidSegwitFlag { flag } => {
+                write!(f, "invalid segwit flag {:#04x}", flag)
+            }
+            BtcTransactionError::InvalidCount { count } => {
+                write!(f, "count {} exceeds transaction size", count)
+            }
+            BtcTransactionError::TrailingBytes { position } => {
+                write!(f, "unexpected bytes after transaction at {}", position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BtcTransactionError {}
+
+// A Bitcoin transaction in the form it is serialized on the network
+#[derive(Clone, PartialEq, Debug)]
+pub struct BtcTransaction {
+    pub version: i32,
+    pub inputs: Vec<BtcInput>,
+    pub outputs: Vec<BtcOutput>,
+    pub lock_time: u32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct BtcInput {
+    // txid of the spent output in the byte order it is displayed, the same as UnspentOutput
+    pub previous_txid: [u8; 32],
+    pub previous_index: u32,
+    // empty until the input is signed
+    pub script_sig: Vec<u8>,
+    pub sequence: u32,
+    // witness stack, empty for legacy inputs
+    pub witness: Vec<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct BtcOutput {
+    // amount in satoshi
+    pub amount: u64,
+    pub script_pubkey: Vec<u8>,
+}
+
+impl BtcTransaction {
+    // Decode a transaction with or without witness data
+    pub fn decode(bytes: &[u8]) -> Result<BtcTransaction, BtcTransactionError> {
+        let mut reader = Reader { bytes, position: 0 };
+        let version = i32::from_le_bytes(reader.take_array()?);
+        let has_witness = reader.peek() == Some(SEGWIT_MARKER);
+        if has_witness {
+            reader.take(1)?;
+            let flag = reader.take(1)?[0];
+            if flag != SEGWIT_FLAG {
+                return Err(BtcTransactionError::InvalidSegwitFlag { flag });
+            }
+        }
+        let input_count = reader.read_count(41)?;
+        let mut inputs = Vec::with_capacity(input_count);
+        for _ in 0..input_count {
+            let mut previous_txid: [u8; 32] = reader.take_array()?;
+            previous_txid.reverse();
+            inputs.push(BtcInput {
+                previous_txid,
+                previous_index: u32::from_le_bytes(reader.take_array()?),
+                script_sig: reader.read_bytes()?.to_vec(),
+                sequence: u32::from_le_bytes(reader.take_array()?),
+                witness: Vec::new(),
+            });
+        }
+        let output_count = reader.read_count(9)?;
+        let mut outputs = Vec::with_capacity(output_count);
+        for _ in 0..output_count {
+            outputs.push(BtcOutput {
+                amount: u64::from_le_bytes(reader.take_array()?),
+                script_pubkey: reader.read_bytes()?.to_vec(),
+            });
+        }
+        if has_witness {
+            for input in inputs.iter_mut() {
+                let item_count = reader.read_count(1)?;
+                for _ in 0..item_count {
+                    input.witness.push(reader.read_bytes()?.to_vec());
+                }
+            }
+        }
+        let lock_time = u32::from_le_bytes(reader.take_array()?);
+        if reader.position != bytes.len() {
+            return Err(BtcTransactionError::TrailingBytes {
+                position: reader.position,
+            });
+        }
+        Ok(BtcTransaction {
+            version,
+            inputs,
+            outputs,
+            lock_time,
+        })
+    }
+
+    // true when any input has a witness so the transaction is serialized with segwit marker
+    pub fn has_witness(&self) -> bool {
+        self.inputs.iter().any(|input| !input.witness.is_empty())
+    }
+
+    // serialization with witness data when there is any
+    pub fn encode(&self) -> Vec<u8> {
+        self.serialize(self.has_witness())
+    }
+
+    // serialization without witness data that the txid is computed from
+    pub fn encode_without_witness(&self) -> Vec<u8> {
+        self.serialize(false)
+    }
+
+    // double SHA-256 of the serialization without witness in the byte order it is displayed
+    pub fn txid(&self) -> [u8; 32] {
+        let mut txid = double_sha256(&self.encode_without_witness());
+        txid.reverse();
+        txid
+    }
+
+    // sum of output amounts in satoshi, None on overflow
+    pub fn output_total(&self) -> Option<u64> {
+        self.outputs
+            .iter()
+            .try_fold(0u64, |total, output| total.checked_add(output.amount))
+    }
+
+    fn serialize(&self, with_witness: bool) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&self.version.to_le_bytes());
+        if with_witness {
+            bytes.extend_from_slice(&[SEGWIT_MARKER, SEGWIT_FLAG]);
+        }
+        write_compact_size(&mut bytes, self.inputs.len() as u64);
+        for input in &self.inputs {
+            input.write_outpoint(&mut bytes);
+            write_bytes(&mut bytes, &input.script_sig);
+            bytes.extend_from_slice(&input.sequence.to_le_bytes());
+        }
+        write_compact_size(&mut bytes, self.outputs.len() as u64);
+        for output in &self.outputs {
+            output.write(&mut bytes);
+        }
+        if with_witness {
+            for input in &self.inputs {
+                write_compact_size(&mut bytes, input.witness.len() as u64);
+                for item in &input.witness {
+                    write_bytes(&mut bytes, item);
+                }
+            }
+        }
+        bytes.extend_from_slice(&self.lock_time.to_le_bytes());
+        bytes
+    }
+}
+
+impl BtcInput {
+    // txid in internal byte order followed by the output index
+    pub fn write_outpoint(&self, bytes: &mut Vec<u8>) {
+        let mut txid = self.previous_txid;
+        txid.reverse();
+        bytes.extend_from_slice(&txid);
+        bytes.extend_from_slice(&self.previous_index.to_le_bytes());
+    }
+}
+
+impl BtcOutput {
+    pub fn write(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.amount.to_le_bytes());
+        write_bytes(bytes, &self.script_pubkey);
+    }
+}
+
+// variable length integer prefixing counts and scripts
+pub fn write_compact_size(bytes: &mut Vec<u8>, value: u64) {
+    match value {
+        0..=0xfc => bytes.push(value as u8),
+        0xfd..=0xffff => {
+            bytes.push(0xfd);
+            bytes.extend_from_slice(&(value as u16).to_le_bytes());
+        }
+        0x1_0000..=0xffff_ffff => {
+            bytes.push(0xfe);
+            bytes.extend_from_slice(&(value as u32).to_le_bytes());
+        }
+        _ => {
+            bytes.push(0xff);
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+    }
+}
+
+// bytes prefixed with their length
+pub fn write_bytes(bytes: &mut Vec<u8>, value: &[u8]) {
+    write_compact_size(bytes, value.len() as u64);
+    bytes.extend_from_slice(value);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], BtcTransactionError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(BtcTransactionError::UnexpectedEnd)?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], BtcTransactionError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn read_compact_size(&mut self) -> Result<u64, BtcTransactionError> {
+        let (value, min) = match self.take(1)?[0] {
+            0xfd => (u16::from_le_bytes(self.take_array()?) as u64, 0xfd),
+            0xfe => (u32::from_le_bytes(self.take_array()?) as u64, 0x1_0000),
+            0xff => (u64::from_le_bytes(self.take_array()?), 0x1_0000_0000),
+            byte => return Ok(byte as u64),
+        };
+        if value < min {
+            return Err(BtcTransactionError::NonCanonicalCompactSize);
+        }
+        Ok(value)
+    }
+
+    // count of items that take at least min_size bytes each, so a corrupt count cannot
+    // allocate more than the input
+    fn read_count(&mut self, min_size: usize) -> Result<usize, BtcTransactionError> {
+        let count = self.read_compact_size()?;
+        let remaining = (self.bytes.len() - self.position) as u64;
+        if count > remaining / min_size as u64 {
+            return Err(BtcTransactionError::InvalidCount { count });
+        }
+        Ok(count as usize)
+    }
+
+    fn read_bytes(&mut self) -> Result<&'a [u8], BtcTransactionError> {
+        let len = self.read_compact_size()?;
+        if len > (self.bytes.len() - self.position) as u64 {
+            return Err(BtcTransactionError::UnexpectedEnd);
+        }
+        self.take(len as usize)
+    }
+}
@@ -0,0 +1,51 @@
+use crate::{Blockchain, Coin, KeyScheme};
+
+// Protocol level facts about each supported blockchain. These do not depend on
+// any runtime configuration and are shared by validation and transaction code.
+impl Blockchain {
+    // coin used to pay fees and for plain SEND transactions
+    pub fn native_coin(&self) -> Coin {
+        match self {
+            Blockchain::BITCOIN => Coin::BTC,
+            Blockchain::ETHEREUM => Coin::ETH,
+            Blockchain::POLYGON => Coin::MATIC,
+            Blockchain::CARDANO => Coin::ADA,
+        }
+    }
+
+    // number of decimal places of the native coin e.g. 8 for satoshi per BTC
+    pub fn native_decimals(&self) -> u32 {
+        match self {
+            Blockchain::BITCOIN => 8,
+            Blockchain::ETHEREUM | Blockchain::POLYGON => 18,
+            Blockchain::CARDANO => 6,
+        }
+    }
+
+    // signature scheme used by wallets on this blockchain
+    pub fn key_scheme(&self) -> KeyScheme {
+        match self {
+            Blockchain::BITCOIN | Blockchain::ETHEREUM | Blockchain::POLYGON => KeyScheme::ECDSA,
+            Blockchain::CARDANO => KeyScheme::EDDSA,
+        }
+    }
+
+    // whether the blockchain runs the ethereum virtual machine
+    pub fn is_evm(&self) -> bool {
+        matches!(self, Blockchain::ETHEREUM | Blockchain::POLYGON)
+    }
+
+    // coins that can be transferred on this blockchain, native coin first
+    pub fn supported_coins(&self) -> &'static [Coin] {
+        match self {
+            Blockchain::BITCOIN => &[Coin::BTC],
+            Blockchain::ETHEREUM => &[Coin::ETH, Coin::USDT],
+            Blockchain::POLYGON => &[Coin::MATIC, Coin::USDT],
+            Blockchain::CARDANO => &[Coin::ADA],
+        }
+    }
+
+    pub fn supports_coin(&self, coin: &Coin) -> bool {
+        self.supported_coins().contains(coin)
+    }
+}
@@ -0,0 +1,422 @@
+use std::fmt;
+
+use bigdecimal::BigDecimal;
+
+use crate::address::{Address, AddressError};
+use crate::bitcoin::transaction::{BtcInput, BtcOutput, BtcTransaction};
+use crate::cardano::cbor::Encoder;
+use crate::cardano::fee::address_len;
+use crate::cardano::transaction::{FEE_KEY, INPUTS_KEY, OUTPUTS_KEY, TTL_KEY};
+use crate::encoding::{EncodingError, Hash32Hex, HexBytes};
+use crate::evm::erc20::Erc20Error;
+use crate::evm::fee::{gas_limit, wei_to_coin_unit, EvmFeeError};
+use crate::evm::transaction::{EvmTransaction, GasPrice};
+use crate::fee::{
+    base_units_to_coin_unit, is_segwit_address, missing_params, unsupported_transaction_type,
+    BitcoinFeeEstimator, EvmFeeEstimator, FeeError,
+};
+use crate::payload::TransactionPayload;
+use crate::sighash::{signing_hashes, SighashError};
+use crate::transaction::parse_chain_id;
+use crate::validation::ValidationError;
+use crate::{
+    Blockchain, CreateTransactionRequest, CreateTransactionResult, SendRequest, UnspentOutput,
+};
+
+// bitcoin transaction version and sequence of inputs without relative lock time or replace by fee
+const BTC_VERSION: i32 = 2;
+const BTC_SEQUENCE: u32 = 0xffff_ffff;
+
+// Reason why a transaction cannot be built from a request
+#[derive(Clone, PartialEq, Debug)]
+pub enum BuildError {
+    // params, amounts, addresses or fee of the request are invalid
+    Fee(FeeError),
+    // a request parameter is out of range e.g. a negative nonce or an invalid chain id
+    InvalidParams {
+        field: &'static str,
+        value: String,
+    },
+    // transaction hash or index of an unspent output is invalid
+    InvalidUnspentOutput {
+        transaction_hash: String,
+        index: i32,
+    },
+    // smart contract data is not hex
+    InvalidData(EncodingError),
+    // token transfer call data cannot be encoded
+    Token(Erc20Error),
+    // amount of native coin does not fit in the transaction
+    AmountTooLarge {
+        amount: BigDecimal,
+    },
+    // hashes of the built transaction cannot be computed
+    Sighash(SighashError),
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BuildError::Fee(error) => write!(f, "{}", error),
+            BuildError::InvalidParams { field, value } => {
+                write!(f, "{} {} is not valid", field, value)
+            }
+            BuildError::InvalidUnspentOutput {
+                transaction_hash,
+                index,
+            } => write!(
+                f,
+                "unspent output {}:{} is not valid",
+                transaction_hash, index
+            ),
+            BuildError::InvalidData(error) => write!(f, "data: {}", error),
+            BuildError::Token(error) => write!(f, "{}", error),
+            BuildError::AmountTooLarge { amount } => write!(f, "amount {} is too large", amount),
+            BuildError::Sighash(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+impl From<FeeError> for BuildError {
+    fn from(error: FeeError) -> Self {
+        BuildError::Fee(error)
+    }
+}
+
+impl From<SighashError> for BuildError {
+    fn from(error: SighashError) -> Self {
+        BuildError::Sighash(error)
+    }
+}
+
+// Builds unsigned transactions. Fees use the settings of the fee estimators so a built
+// transaction pays the fee estimated for the fee level of its signing request.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct TransactionBuilder {
+    pub bitcoin: BitcoinFeeEstimator,
+    pub evm: EvmFeeEstimator,
+}
+
+impl TransactionBuilder {
+    // Raw transaction in hex, its fee in native coin unit and the hashes to sign.
+    // BITCOIN: unsigned transaction spending the selected unspent outputs with change back to the sender
+    // ETHEREUM, POLYGON: legacy EIP-155 or EIP-1559 signing payload
+    // CARDANO: full transaction with an empty witness set
+    pub fn build(
+        &self,
+        request: &CreateTransactionRequest,
+    ) -> Result<CreateTransactionResult, BuildError> {
+        let (raw_transaction, fee, unspent_outputs) = match request.blockchain {
+            Blockchain::BITCOIN => self.bitcoin_transaction(request)?,
+            Blockchain::ETHEREUM | Blockchain::POLYGON => self.evm_transaction(request)?,
+            Blockchain::CARDANO => cardano_transaction(request)?,
+        };
+        let hashes = signing_hashes(&request.blockchain, &raw_transaction, &unspent_outputs)?;
+        Ok(CreateTransactionResult {
+            raw_transaction,
+            fee,
+            hashes,
+        })
+    }
+
+    fn bitcoin_transaction(
+        &self,
+        request: &CreateTransactionRequest,
+    ) -> Result<(String, BigDecimal, Vec<UnspentOutput>), BuildError> {
+        let params = request
+            .request_params_btc
+            .as_ref()
+            .ok_or_else(|| missing_params(request, "requestParamsBtc"))?;
+        let send_request = send_request(request)?;
+        let from = address(
+            request,
+            "fromAddress",
+            &request.signing_request.from_address,
+        )?;
+        let to = recipient(request, &from, &send_request.to_address)?;
+
+        let fee_level = &request.signing_request.fee_level;
+        let fee_rate = &params.fee_per_byte * self.bitcoin.fee_rate.for_level(fee_level);
+        let is_segwit = is_segwit_address(&request.signing_request.from_address);
+        let selection = params
+            .select_coins_at_rate(&send_request, fee_rate, is_segwit)
+            .map_err(FeeError::from)?;
+
+        let mut inputs = Vec::with_capacity(selection.inputs.len());
+        for output in &selection.inputs {
+            let (previous_txid, previous_index) = outpoint(output)?;
+            inputs.push(BtcInput {
+                previous_txid,
+                previous_index,
+                script_sig: Vec::new(),
+                sequence: BTC_SEQUENCE,
+                witness: Vec::new(),
+            });
+        }
+        let mut outputs = vec![BtcOutput {
+            amount: selection.amount,
+            script_pubkey: script_pubkey(&to),
+        }];
+        if selection.has_change() {
+            outputs.push(BtcOutput {
+                amount: selection.change,
+                script_pubkey: script_pubkey(&from),
+            });
+        }
+        let transaction = BtcTransaction {
+            version: BTC_VERSION,
+            inputs,
+            outputs,
+            lock_time: 0,
+        };
+        Ok((
+            hex::encode(transaction.encode()),
+            base_units_to_coin_unit(&request.blockchain, selection.fee),
+            selection.inputs,
+        ))
+    }
+
+    fn evm_transaction(
+        &self,
+        request: &CreateTransactionRequest,
+    ) -> Result<(String, BigDecimal, Vec<UnspentOutput>), BuildError> {
+        let blockchain = &request.blockchain;
+        let payload =
+            TransactionPayload::try_from(&request.signing_request).map_err(FeeError::from)?;
+        let fee_level = &request.signing_request.fee_level;
+        let config = &self.evm.config;
+        let (chain_id, nonce, gas_price) = if let Some(params) = &request.request_params_eth_eip1559
+        {
+            let fee = params
+                .fee_for_level(fee_level, config)
+                .map_err(FeeError::from)?;
+            (&params.chain_id, params.nonce, GasPrice::Eip1559(fee))
+        } else if let Some(params) = &request.request_params_eth_legacy {
+            let gas_price = params
+                .gas_price_for_level(fee_level, config)
+                .map_err(FeeError::from)?;
+            (
+                &params.chain_id,
+                params.nonce,
+                GasPrice::Legacy { gas_price },
+            )
+        } else {
+            return Err(missing_params(
+                request,
+                "requestParamsEthEip1559 or requestParamsEthLegacy",
+            )
+            .into());
+        };
+        let chain_id =
+            parse_chain_id(chain_id.trim()).ok_or_else(|| BuildError::InvalidParams {
+                field: "chainId",
+                value: chain_id.clone(),
+            })?;
+        let nonce = u64::try_from(nonce).map_err(|_| BuildError::InvalidParams {
+            field: "nonce",
+            value: nonce.to_string(),
+        })?;
+
+        let (to, value, data) = match &payload {
+            TransactionPayload::Send(send) => {
+                let amount = send.coin_amount(blockchain).map_err(FeeError::from)?;
+                (
+                    account(request, "sendRequest.toAddress", &send.to_address)?,
+                    wei(&amount.to_coin_unit(), amount.to_u128())?,
+                    Vec::new(),
+                )
+            }
+            TransactionPayload::SendToken(send_token) => {
+                let data = send_token
+                    .transfer_call(blockchain, request.coin.clone())
+                    .and_then(|call| call.encode())
+                    .map_err(BuildError::Token)?;
+                (
+                    account(
+                        request,
+                        "sendTokenRequest.tokenContractAddress",
+                        &send_token.token_contract_address,
+                    )?,
+                    0,
+                    data,
+                )
+            }
+            TransactionPayload::EthContractCall(contract) => {
+                let amount = contract.coin_amount(blockchain).map_err(FeeError::from)?;
+                let data = HexBytes::try_from(contract.data.trim())
+                    .map_err(BuildError::InvalidData)?
+                    .to_bytes();
+                (
+                    account(
+                        request,
+                        "ethSmartContractRequest.toAddress",
+                        &contract.to_address,
+                    )?,
+                    wei(&amount.to_coin_unit(), amount.to_u128())?,
+                    data,
+                )
+            }
+            TransactionPayload::PersonalSign(_) | TransactionPayload::EthSignTypedData(_) => {
+                return Err(unsupported_transaction_type(request).into())
+            }
+        };
+        let transaction = EvmTransaction {
+            chain_id: Some(chain_id),
+            nonce,
+            gas_price,
+            gas_limit: gas_limit(&payload).map_err(FeeError::from)?,
+            to: Some(to),
+            value,
+            data,
+            access_list: Vec::new(),
+            signature: None,
+        };
+        let fee = transaction
+            .max_cost()
+            .ok_or(FeeError::Evm(EvmFeeError::Overflow))?;
+        Ok((
+            hex::encode(transaction.signing_payload()),
+            wei_to_coin_unit(blockchain, fee),
+            Vec::new(),
+        ))
+    }
+}
+
+impl CreateTransactionRequest {
+    // build the unsigned transaction with the default builder settings
+    pub fn build(&self) -> Result<CreateTransactionResult, BuildError> {
+        TransactionBuilder::default().build(self)
+    }
+}
+
+// Cardano has no fee market so the builder has no settings for it
+fn cardano_transaction(
+    request: &CreateTransactionRequest,
+) -> Result<(String, BigDecimal, Vec<UnspentOutput>), BuildError> {
+    let params = request
+        .request_params_ada
+        .as_ref()
+        .ok_or_else(|| missing_params(request, "requestParamsAda"))?;
+    let send_request = send_request(request)?;
+    let from = address(
+        request,
+        "fromAddress",
+        &request.signing_request.from_address,
+    )?;
+    let to = recipient(request, &from, &send_request.to_address)?;
+    let ttl = u64::try_from(params.ttl).map_err(|_| BuildError::InvalidParams {
+        field: "ttl",
+        value: params.ttl.to_string(),
+    })?;
+    let selection = params
+        .select_for_send(
+            &send_request,
+            address_len(&send_request.to_address),
+            address_len(&request.signing_request.from_address),
+        )
+        .map_err(FeeError::from)?;
+
+    let mut outputs = vec![(to.payload(), selection.amount)];
+    if selection.change > 0 {
+        outputs.push((from.payload(), selection.change));
+    }
+    let mut encoder = Encoder::new();
+    // [body, witness set, is valid, auxiliary data]
+    encoder.write_array(4).write_map(4);
+    encoder
+        .write_uint(INPUTS_KEY)
+        .write_array(selection.inputs.len() as u64);
+    for output in &selection.inputs {
+        let (transaction_id, index) = outpoint(output)?;
+        encoder
+            .write_array(2)
+            .write_bytes(&transaction_id)
+            .write_uint(index as u64);
+    }
+    encoder
+        .write_uint(OUTPUTS_KEY)
+        .write_array(outputs.len() as u64);
+    for (address, amount) in outputs {
+        encoder
+            .write_array(2)
+            .write_bytes(address)
+            .write_uint(amount);
+    }
+    encoder.write_uint(FEE_KEY).write_uint(selection.fee);
+    encoder.write_uint(TTL_KEY).write_uint(ttl);
+    encoder.write_map(0).write_bool(true).write_null();
+    Ok((
+        hex::encode(encoder.into_bytes()),
+        base_units_to_coin_unit(&request.blockchain, selection.fee),
+        selection.inputs,
+    ))
+}
+
+// payment of a Bitcoin or Cardano transaction. Only native coin can be sent
+fn send_request(request: &CreateTransactionRequest) -> Result<SendRequest, BuildError> {
+    match TransactionPayload::try_from(&request.signing_request).map_err(FeeError::from)? {
+        TransactionPayload::Send(send_request) => Ok(send_request),
+        _ => Err(unsupported_transaction_type(request).into()),
+    }
+}
+
+fn address(
+    request: &CreateTransactionRequest,
+    field: &'static str,
+    address: &str,
+) -> Result<Address, BuildError> {
+    Address::parse(&request.blockchain, address).map_err(|error| invalid_address(field, error))
+}
+
+// recipient of a payment on the network of the sender
+fn recipient(
+    request: &CreateTransactionRequest,
+    from: &Address,
+    to_address: &str,
+) -> Result<Address, BuildError> {
+    let is_mainnet = from.is_mainnet().unwrap_or(true);
+    Address::parse_on_network(&request.blockchain, to_address, is_mainnet)
+        .map_err(|error| invalid_address("sendRequest.toAddress", error))
+}
+
+fn invalid_address(field: &'static str, error: AddressError) -> BuildError {
+    FeeError::InvalidRequest(ValidationError::InvalidAddress { field, error }).into()
+}
+
+// 20 bytes account of an EVM address
+fn account(
+    request: &CreateTransactionRequest,
+    field: &'static str,
+    address_str: &str,
+) -> Result<[u8; 20], BuildError> {
+    let mut account = [0u8; 20];
+    account.copy_from_slice(address(request, field, address_str)?.payload());
+    Ok(account)
+}
+
+fn script_pubkey(address: &Address) -> Vec<u8> {
+    address
+        .script_pubkey()
+        .expect("bitcoin addresses have a scriptPubKey")
+}
+
+// transaction hash and index of an unspent output
+fn outpoint(output: &UnspentOutput) -> Result<([u8; 32], u32), BuildError> {
+    let invalid = || BuildError::InvalidUnspentOutput {
+        transaction_hash: output.transaction_hash.clone(),
+        index: output.index,
+    };
+    let transaction_hash = Hash32Hex::try_from(output.transaction_hash.trim())
+        .map_err(|_| invalid())?
+        .to_array();
+    let index = u32::try_from(output.index).map_err(|_| invalid())?;
+    Ok((transaction_hash, index))
+}
+
+fn wei(amount: &BigDecimal, wei: Option<u128>) -> Result<u128, BuildError> {
+    wei.ok_or_else(|| BuildError::AmountTooLarge {
+        amount: amount.clone(),
+    })
+}
@@ -0,0 +1,346 @@
+use std::fmt;
+
+// CBOR major types
+pub const UNSIGNED: u8 = 0;
+pub const NEGATIVE: u8 = 1;
+pub const BYTES: u8 = 2;
+pub const TEXT: u8 = 3;
+pub const ARRAY: u8 = 4;
+pub const MAP: u8 = 5;
+pub const TAG: u8 = 6;
+pub const SIMPLE: u8 = 7;
+
+// additional information of an indefinite length item
+const INDEFINITE: u8 = 31;
+// "break" stop code ending an indefinite length item
+const BREAK: u8 = 0xff;
+// simple values false, true and null
+const FALSE: u8 = 0xf4;
+const TRUE: u8 = 0xf5;
+const NULL: u8 = 0xf6;
+// nesting of arrays, maps and tags. Plutus data and metadata nest deeper than transaction bodies
+const MAX_DEPTH: usize = 64;
+
+// Reason why bytes are not the expected CBOR
+#[derive(Clone, PartialEq, Debug)]
+pub enum CborError {
+    // input ended in the middle of an item
+    UnexpectedEnd,
+    // item has a different major type than expected
+    UnexpectedType { expected: u8, actual: u8 },
+    // additional information 28 to 30 is reserved
+    InvalidHeader { byte: u8 },
+    // indefinite length where a definite length is required
+    IndefiniteLength,
+    // array has a different number of items than expected
+    UnexpectedLength { expected: u64, actual: u64 },
+    // tag has a different number than expected
+    UnexpectedTag { expected: u64, actual: u64 },
+    // bytes left after the last item
+    TrailingBytes { position: usize },
+    // number of items does not fit in memory
+    LengthOverflow { len: u64 },
+    // items are nested deeper than MAX_DEPTH
+    TooDeep,
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CborError::UnexpectedEnd => write!(f, "unexpected end of CBOR"),
+            CborError::UnexpectedType { expected, actual } => write!(
+                f,
+                "expected CBOR major type {} but got {}",
+                expected, actual
+            ),
+            CborError::InvalidHeader { byte } => write!(f, "invalid CBOR header {:#04x}", byte),
+            CborError::IndefiniteLength => write!(f, "indefinite length is not supported"),
+            CborError::UnexpectedLength { expected, actual } => write!(
+                f,
+                "expected CBOR array of {} items but got {}",
+                expected, actual
+            ),
+            CborError::UnexpectedTag { expected, actual } => {
+                write!(f, "expected CBOR tag {} but got {}", expected, actual)
+            }
+            CborError::TrailingBytes { position } => {
+                write!(f, "unexpected bytes after CBOR at {}", position)
+            }
+            CborError::LengthOverflow { len } => write!(f, "CBOR length {} is too large", len),
+            CborError::TooDeep => write!(f, "CBOR items are nested too deep"),
+        }
+    }
+}
+
+impl std::error::Error for CborError {}
+
+// Reads CBOR items one after another from a byte slice
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Decoder { bytes, position: 0 }
+    }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn is_end(&self) -> bool {
+        self.position >= self.bytes.len()
+    }
+
+    // fails when bytes are left after the last item
+    pub fn finish(&self) -> Result<(), CborError> {
+        if !self.is_end() {
+            return Err(CborError::TrailingBytes {
+                position: self.position,
+            });
+        }
+        Ok(())
+    }
+
+    // major type of the next item
+    pub fn peek_type(&self) -> Result<u8, CborError> {
+        self.bytes
+            .get(self.position)
+            .map(|byte| byte >> 5)
+            .ok_or(CborError::UnexpectedEnd)
+    }
+
+    // true and consumes the stop code when the next byte ends an indefinite length item
+    pub fn read_break(&mut self) -> bool {
+        if self.bytes.get(self.position) == Some(&BREAK) {
+            self.position += 1;
+            return true;
+        }
+        false
+    }
+
+    pub fn read_uint(&mut self) -> Result<u64, CborError> {
+        self.read_definite(UNSIGNED)
+    }
+
+    // definite length byte string
+    pub fn read_bytes(&mut self) -> Result<&'a [u8], CborError> {
+        let len = self.read_definite(BYTES)?;
+        self.take(usize::try_from(len).map_err(|_| CborError::LengthOverflow { len })?)
+    }
+
+    // number of items or None when the array has an indefinite length
+    pub fn read_array(&mut self) -> Result<Option<u64>, CborError> {
+        self.read_length(ARRAY)
+    }
+
+    // number of entries or None when the map has an indefinite length
+    pub fn read_map(&mut self) -> Result<Option<u64>, CborError> {
+        self.read_length(MAP)
+    }
+
+    pub fn read_tag(&mut self) -> Result<u64, CborError> {
+        self.read_definite(TAG)
+    }
+
+    // definite length array of exactly len items
+    pub fn expect_array(&mut self, len: u64) -> Result<(), CborError> {
+        let actual = self.read_definite(ARRAY)?;
+        if actual != len {
+            return Err(CborError::UnexpectedLength {
+                expected: len,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    pub fn expect_tag(&mut self, tag: u64) -> Result<(), CborError> {
+        let actual = self.read_tag()?;
+        if actual != tag {
+            return Err(CborError::UnexpectedTag {
+                expected: tag,
+                actual,
+            });
+        }
+        Ok(())
+    }
+
+    // Raw bytes of the next item including nested items. Used where a hash covers the exact
+    // bytes that were signed e.g. a transaction body.
+    pub fn read_raw(&mut self) -> Result<&'a [u8], CborError> {
+        let start = self.position;
+        self.skip()?;
+        Ok(&self.bytes[start..self.position])
+    }
+
+    // skips the next item including nested items
+    pub fn skip(&mut self) -> Result<(), CborError> {
+        self.skip_nested(0)
+    }
+
+    fn skip_nested(&mut self, depth: usize) -> Result<(), CborError> {
+        if depth == MAX_DEPTH {
+            return Err(CborError::TooDeep);
+        }
+        let (major, argument) = self.read_header()?;
+        match (major, argument) {
+            (UNSIGNED | NEGATIVE | SIMPLE, _) => Ok(()),
+            (BYTES | TEXT, Some(len)) => {
+                let len = usize::try_from(len).map_err(|_| CborError::LengthOverflow { len })?;
+                self.take(len).map(|_| ())
+            }
+            (TAG, _) => self.skip_nested(depth + 1),
+            (ARRAY | MAP, Some(len)) => {
+                let items = if major == MAP {
+                    len.checked_mul(2)
+                        .ok_or(CborError::LengthOverflow { len })?
+                } else {
+                    len
+                };
+                // every item takes at least a byte so a too large length ends with UnexpectedEnd
+                for _ in 0..items {
+                    self.skip_nested(depth + 1)?;
+                }
+                Ok(())
+            }
+            // indefinite length strings, arrays and maps end with a break
+            _ => {
+                while !self.read_break() {
+                    self.skip_nested(depth + 1)?;
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn read_length(&mut self, expected: u8) -> Result<Option<u64>, CborError> {
+        let (major, argument) = self.read_header()?;
+        if major != expected {
+            return Err(CborError::UnexpectedType {
+                expected,
+                actual: major,
+            });
+        }
+        Ok(argument)
+    }
+
+    fn read_definite(&mut self, expected: u8) -> Result<u64, CborError> {
+        self.read_length(expected)?
+            .ok_or(CborError::IndefiniteLength)
+    }
+
+    // major type and argument, None for an indefinite length
+    fn read_header(&mut self) -> Result<(u8, Option<u64>), CborError> {
+        let byte = *self.take(1)?.first().ok_or(CborError::UnexpectedEnd)?;
+        let major = byte >> 5;
+        let info = byte & 0x1f;
+        let argument = match info {
+            0..=23 => Some(info as u64),
+            24 => Some(self.take(1)?[0] as u64),
+            25 => Some(u16::from_be_bytes(self.take_array()?) as u64),
+            26 => Some(u32::from_be_bytes(self.take_array()?) as u64),
+            27 => Some(u64::from_be_bytes(self.take_array()?)),
+            INDEFINITE if major != UNSIGNED && major != NEGATIVE && major != TAG => None,
+            _ => return Err(CborError::InvalidHeader { byte }),
+        };
+        Ok((major, argument))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CborError> {
+        let end = self
+            .position
+            .checked_add(len)
+            .filter(|end| *end <= self.bytes.len())
+            .ok_or(CborError::UnexpectedEnd)?;
+        let bytes = &self.bytes[self.position..end];
+        self.position = end;
+        Ok(bytes)
+    }
+
+    fn take_array<const N: usize>(&mut self) -> Result<[u8; N], CborError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+}
+
+// Writes CBOR items with the shortest definite length headers
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Encoder {
+    bytes: Vec<u8>,
+}
+
+impl Encoder {
+    pub fn new() -> Self {
+        Encoder::default()
+    }
+
+    pub fn write_uint(&mut self, value: u64) -> &mut Self {
+        self.write_header(UNSIGNED, value)
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> &mut Self {
+        self.write_header(BYTES, bytes.len() as u64);
+        self.bytes.extend_from_slice(bytes);
+        self
+    }
+
+    // header of an array of len items. The items are written next
+    pub fn write_array(&mut self, len: u64) -> &mut Self {
+        self.write_header(ARRAY, len)
+    }
+
+    // header of a map of len entries. Keys and values are written next one after another
+    pub fn write_map(&mut self, len: u64) -> &mut Self {
+        self.write_header(MAP, len)
+    }
+
+    pub fn write_tag(&mut self, tag: u64) -> &mut Self {
+        self.write_header(TAG, tag)
+    }
+
+    pub fn write_bool(&mut self, value: bool) -> &mut Self {
+        self.bytes.push(if value { TRUE } else { FALSE });
+        self
+    }
+
+    pub fn write_null(&mut self) -> &mut Self {
+        self.bytes.push(NULL);
+        self
+    }
+
+    // already encoded item e.g. a body decoded with Decoder::read_raw
+    pub fn write_raw(&mut self, bytes: &[u8]) -> &mut Self {
+        self.bytes.extend_from_slice(bytes);
+        self
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn write_header(&mut self, major: u8, argument: u64) -> &mut Self {
+        let major = major << 5;
+        match argument {
+            0..=23 => self.bytes.push(major | argument as u8),
+            24..=0xff => self.bytes.extend_from_slice(&[major | 24, argument as u8]),
+            0x100..=0xffff => {
+                self.bytes.push(major | 25);
+                self.bytes
+                    .extend_from_slice(&(argument as u16).to_be_bytes());
+            }
+            0x1_0000..=0xffff_ffff => {
+                self.bytes.push(major | 26);
+                self.bytes
+                    .extend_from_slice(&(argument as u32).to_be_bytes());
+            }
+            _ => {
+                self.bytes.push(major | 27);
+                self.bytes.extend_from_slice(&argument.to_be_bytes());
+            }
+        }
+        self
+    }
+}
@@ -0,0 +1,325 @@
+use std::cmp::Reverse;
+use std::fmt;
+
+use crate::amount::{AmountError, CoinAmount};
+use crate::{Blockchain, RequestParamsAda, SendRequest, UnspentOutput};
+
+// bytes added to the serialized output size by the babbage minimum utxo rule
+const UTXO_ENTRY_OVERHEAD: u64 = 160;
+
+// Serialized sizes in bytes of a transaction [body, witness set, is valid, auxiliary data].
+// Sizes are upper bounds so the estimated fee is never below the fee of the built transaction.
+// transaction array, body map, is valid and auxiliary data null
+const TX_OVERHEAD: u64 = 1 + 1 + 1 + 1;
+// inputs key and array header
+const INPUTS_OVERHEAD: u64 = 1 + 3;
+// [transaction hash, index]
+const INPUT_SIZE: u64 = 1 + 2 + 32 + 3;
+// outputs key and array header
+const OUTPUTS_OVERHEAD: u64 = 1 + 3;
+// fee key and 64 bits unsigned integer
+const FEE_SIZE: u64 = 1 + 9;
+// ttl key and 64 bits unsigned integer
+const TTL_SIZE: u64 = 1 + 9;
+// witness set map, vkey witnesses key and array header with one [vkey, signature] witness as every
+// input is spent from the same wallet address
+const WITNESS_SET_SIZE: u64 = 1 + 1 + 1 + (1 + 2 + 32 + 2 + 64);
+
+// Reason why a fee or input selection cannot be calculated
+#[derive(Clone, PartialEq, Debug)]
+pub enum AdaFeeError {
+    // network parameters must not be negative
+    InvalidParams {
+        field: &'static str,
+        value: i32,
+    },
+    // an output amount is below the minimum lovelace it must carry
+    BelowMinUtxo {
+        amount: u64,
+        min_utxo: u64,
+    },
+    // unspent outputs cannot pay the amount plus fee. Amounts are in lovelace
+    InsufficientFunds {
+        required: u64,
+        available: u64,
+        shortfall: u64,
+    },
+    // an amount does not fit in 64 bits of lovelace
+    AmountTooLarge,
+    // an amount cannot be converted to lovelace
+    InvalidAmount(AmountError),
+}
+
+impl fmt::Display for AdaFeeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdaFeeError::InvalidParams { field, value } => {
+                write!(f, "{} must not be negative but got {}", field, value)
+            }
+            AdaFeeError::BelowMinUtxo { amount, min_utxo } => write!(
+                f,
+                "output of {} lovelace is below minimum utxo {} lovelace",
+                amount, min_utxo
+            ),
+            AdaFeeError::InsufficientFunds {
+                required,
+                available,
+                shortfall,
+            } => write!(
+                f,
+                "insufficient funds: required {} lovelace, available {} lovelace, short of {} lovelace",
+                required, available, shortfall
+            ),
+            AdaFeeError::AmountTooLarge => write!(f, "amount is too large"),
+            AdaFeeError::InvalidAmount(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for AdaFeeError {}
+
+impl From<AmountError> for AdaFeeError {
+    fn from(error: AmountError) -> Self {
+        AdaFeeError::InvalidAmount(error)
+    }
+}
+
+// Selected outputs to spend. Amounts are in lovelace
+#[derive(Clone, PartialEq, Debug)]
+pub struct AdaSelection {
+    pub inputs: Vec<UnspentOutput>,
+    pub input_total: u64,
+    pub amount: u64,
+    // change back to the wallet. Zero when there is no change output
+    pub change: u64,
+    pub fee: u64,
+    // estimated size of the signed transaction in bytes
+    pub tx_size: u64,
+}
+
+impl RequestParamsAda {
+    // fee in lovelace for a signed transaction of the given size: coeff x bytes + constant
+    pub fn linear_fee(&self, tx_size: u64) -> Result<u64, AdaFeeError> {
+        let coeff = non_negative("feeCoeff", self.fee_coeff)?;
+        let constant = non_negative("feeConstant", self.fee_constant)?;
+        coeff
+            .checked_mul(tx_size)
+            .and_then(|fee| fee.checked_add(constant))
+            .ok_or(AdaFeeError::AmountTooLarge)
+    }
+
+    // Minimum lovelace an ada only output must carry: (160 + serialized output size) x coinsPerUTxOByte.
+    // The output size depends on the amount itself so the smallest amount covering its own size is returned.
+    pub fn min_utxo(&self, address_len: usize) -> Result<u64, AdaFeeError> {
+        let coin_per_byte = non_negative("coinPerUtxoByte", self.coin_per_utxo_byte)?;
+        let mut min_utxo = 0;
+        loop {
+            let required =
+                (UTXO_ENTRY_OVERHEAD + output_size(address_len, min_utxo)) * coin_per_byte;
+            if required <= min_utxo {
+                return Ok(min_utxo);
+            }
+            min_utxo = required;
+        }
+    }
+
+    // Select unspent outputs, largest first, to pay the amount and the fee.
+    // Change is only created when it carries at least the minimum utxo. More inputs are added to fund
+    // such change and only when none can, the leftover below minimum utxo is added to the fee.
+    pub fn select_inputs(
+        &self,
+        amount: u64,
+        recipient_address_len: usize,
+        change_address_len: usize,
+    ) -> Result<AdaSelection, AdaFeeError> {
+        let recipient_min_utxo = self.min_utxo(recipient_address_len)?;
+        if amount < recipient_min_utxo {
+            return Err(AdaFeeError::BelowMinUtxo {
+                amount,
+                min_utxo: recipient_min_utxo,
+            });
+        }
+        let change_min_utxo = self.min_utxo(change_address_len)?;
+
+        let mut candidates = Vec::with_capacity(self.unspent_outputs.len());
+        for output in &self.unspent_outputs {
+            candidates.push((output, lovelace(output)?));
+        }
+        // every partial sum of the selection is below the sum of all candidates
+        let values: Vec<u64> = candidates.iter().map(|(_, value)| *value).collect();
+        checked_sum(&values)?;
+        candidates.sort_by_key(|(_, value)| Reverse(*value));
+
+        let recipient_output = output_size(recipient_address_len, amount);
+        let mut selected: Vec<UnspentOutput> = Vec::new();
+        let mut input_total = 0u64;
+        let mut without_change = None;
+        for (output, value) in &candidates {
+            selected.push((*output).clone());
+            input_total = checked_sum(&[input_total, *value])?;
+
+            let change_output = output_size(change_address_len, u64::MAX);
+            let tx_size_with_change =
+                estimate_tx_size(selected.len(), &[recipient_output, change_output]);
+            let fee_with_change = self.linear_fee(tx_size_with_change)?;
+            if input_total >= checked_sum(&[amount, fee_with_change, change_min_utxo])? {
+                return Ok(AdaSelection {
+                    inputs: selected,
+                    input_total,
+                    amount,
+                    change: input_total - amount - fee_with_change,
+                    fee: fee_with_change,
+                    tx_size: tx_size_with_change,
+                });
+            }
+
+            let tx_size = estimate_tx_size(selected.len(), &[recipient_output]);
+            let fee = self.linear_fee(tx_size)?;
+            if without_change.is_none() && input_total >= checked_sum(&[amount, fee])? {
+                // change would be below minimum utxo so it is paid as fee
+                without_change = Some(AdaSelection {
+                    inputs: selected.clone(),
+                    input_total,
+                    amount,
+                    change: 0,
+                    fee: input_total - amount,
+                    tx_size,
+                });
+            }
+        }
+        if let Some(selection) = without_change {
+            return Ok(selection);
+        }
+
+        let required = checked_sum(&[
+            amount,
+            self.linear_fee(estimate_tx_size(candidates.len(), &[recipient_output]))?,
+        ])?;
+        Err(AdaFeeError::InsufficientFunds {
+            required,
+            available: input_total,
+            shortfall: required.saturating_sub(input_total),
+        })
+    }
+
+    // select inputs paying the send request
+    pub fn select_for_send(
+        &self,
+        send_request: &SendRequest,
+        recipient_address_len: usize,
+        change_address_len: usize,
+    ) -> Result<AdaSelection, AdaFeeError> {
+        let amount = send_request.coin_amount(&Blockchain::CARDANO)?;
+        let amount = amount.to_u64().ok_or(AdaFeeError::AmountTooLarge)?;
+        self.select_inputs(amount, recipient_address_len, change_address_len)
+    }
+}
+
+// Length in bytes of a Cardano address. Exact for bech32 Shelley addresses, for base58 Byron
+// addresses the length is rounded up from the number of characters.
+pub fn address_len(address: &str) -> usize {
+    match address.rfind('1') {
+        Some(separator) if address.starts_with("addr") => {
+            // 5 bits per character without the 6 characters checksum
+            let data_chars = address.len().saturating_sub(separator + 1 + 6);
+            data_chars * 5 / 8
+        }
+        // log(58) / log(256) bytes per character
+        _ => (address.len() * 733 + 999) / 1000,
+    }
+}
+
+// serialized size in bytes of an ada only output [address, amount]
+pub fn output_size(address_len: usize, amount: u64) -> u64 {
+    1 + bytes_size(address_len) + uint_size(amount)
+}
+
+// upper bound of the signed transaction size in bytes with one wallet witness
+pub fn estimate_tx_size(input_count: usize, output_sizes: &[u64]) -> u64 {
+    TX_OVERHEAD
+        + INPUTS_OVERHEAD
+        + input_count as u64 * INPUT_SIZE
+        + OUTPUTS_OVERHEAD
+        + output_sizes.iter().sum::<u64>()
+        + FEE_SIZE
+        + TTL_SIZE
+        + WITNESS_SET_SIZE
+}
+
+// size of a CBOR unsigned integer
+fn uint_size(value: u64) -> u64 {
+    match value {
+        0..=23 => 1,
+        24..=0xff => 2,
+        0x100..=0xffff => 3,
+        0x1_0000..=0xffff_ffff => 5,
+        _ => 9,
+    }
+}
+
+// size of a CBOR byte string
+fn bytes_size(len: usize) -> u64 {
+    uint_size(len as u64) + len as u64
+}
+
+// sum of lovelace amounts that must fit in 64 bits
+fn checked_sum(amounts: &[u64]) -> Result<u64, AdaFeeError> {
+    amounts
+        .iter()
+        .try_fold(0u64, |sum, amount| sum.checked_add(*amount))
+        .ok_or(AdaFeeError::AmountTooLarge)
+}
+
+fn lovelace(output: &UnspentOutput) -> Result<u64, AdaFeeError> {
+    CoinAmount::native(&Blockchain::CARDANO, &output.amount)?
+        .to_u64()
+        .ok_or(AdaFeeError::AmountTooLarge)
+}
+
+fn non_negative(field: &'static str, value: i32) -> Result<u64, AdaFeeError> {
+    u64::try_from(value).map_err(|_| AdaFeeError::InvalidParams { field, value })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use bigdecimal::BigDecimal;
+
+    use super::*;
+
+    fn params(amounts: &[&str]) -> RequestParamsAda {
+        RequestParamsAda {
+            unspent_outputs: amounts
+                .iter()
+                .enumerate()
+                .map(|(index, amount)| UnspentOutput {
+                    transaction_hash: "00".repeat(32),
+                    index: index as i32,
+                    script: String::new(),
+                    amount: BigDecimal::from_str(amount).unwrap(),
+                })
+                .collect(),
+            ttl: 0,
+            fee_coeff: 44,
+            fee_constant: 155381,
+            coin_per_utxo_byte: 4310,
+        }
+    }
+
+    #[test]
+    fn amount_plus_fee_overflow_is_an_error() {
+        assert_eq!(
+            params(&["100"]).select_inputs(u64::MAX - 10, 57, 57),
+            Err(AdaFeeError::AmountTooLarge)
+        );
+    }
+
+    #[test]
+    fn input_total_overflow_is_an_error() {
+        assert_eq!(
+            params(&["18446744073709", "18446744073709"]).select_inputs(10_000_000, 57, 57),
+            Err(AdaFeeError::AmountTooLarge)
+        );
+    }
+}
@@ -0,0 +1,3 @@
+pub mod cbor;
+pub mod fee;
+pub mod transaction;
@@ -0,0 +1,339 @@
+use std::fmt;
+
+use bech32::{ToBase32, Variant};
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+
+use crate::cardano::cbor::{CborError, Decoder, Encoder, ARRAY, MAP, TAG, UNSIGNED};
+
+// transaction body map keys
+pub const INPUTS_KEY: u64 = 0;
+pub const OUTPUTS_KEY: u64 = 1;
+pub const FEE_KEY: u64 = 2;
+pub const TTL_KEY: u64 = 3;
+// auxiliary data hash, validity interval start and network id do not move funds
+const AUXILIARY_DATA_HASH_KEY: u64 = 7;
+const VALIDITY_START_KEY: u64 = 8;
+const NETWORK_ID_KEY: u64 = 15;
+// witness set key of [vkey, signature] witnesses
+const VKEY_WITNESSES_KEY: u64 = 0;
+// post Alonzo map output keys
+const OUTPUT_ADDRESS_KEY: u64 = 0;
+const OUTPUT_VALUE_KEY: u64 = 1;
+// Conway encodes sets as tag 258 arrays
+const SET_TAG: u64 = 258;
+// first byte of a Byron address, the CBOR array of its payload and crc
+const BYRON_ADDRESS_PREFIX: u8 = 0x82;
+
+// Reason why bytes are not a Cardano transaction
+#[derive(Clone, PartialEq, Debug)]
+pub enum AdaTransactionError {
+    Cbor(CborError),
+    // required body field is not present
+    MissingField { key: u64 },
+    // body field is present more than once
+    DuplicateField { key: u64 },
+}
+
+impl fmt::Display for AdaTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AdaTransactionError::Cbor(error) => write!(f, "{}", error),
+            AdaTransactionError::MissingField { key } => {
+                write!(f, "transaction body has no field {}", key)
+            }
+            AdaTransactionError::DuplicateField { key } => {
+                write!(f, "transaction body has field {} more than once", key)
+            }
+        }
+    }
+}
+
+impl std::error::Error for AdaTransactionError {}
+
+impl From<CborError> for AdaTransactionError {
+    fn from(error: CborError) -> Self {
+        AdaTransactionError::Cbor(error)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AdaInput {
+    pub transaction_id: [u8; 32],
+    pub index: u64,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AdaOutput {
+    // address bytes: Shelley header and hashes or the Byron CBOR
+    pub address: Vec<u8>,
+    // amount in lovelace
+    pub amount: u64,
+    // output also carries native assets
+    pub has_assets: bool,
+}
+
+// Fields of a transaction body that move funds. Other fields are only listed by key.
+#[derive(Clone, PartialEq, Debug)]
+pub struct AdaTransactionBody {
+    pub inputs: Vec<AdaInput>,
+    pub outputs: Vec<AdaOutput>,
+    // fee in lovelace
+    pub fee: u64,
+    pub ttl: Option<u64>,
+    // keys of fields that are not decoded e.g. certificates, withdrawals or mint
+    pub other_fields: Vec<u64>,
+}
+
+impl AdaTransactionBody {
+    // Decode the body of a full transaction [body, witness set, is valid, auxiliary data]
+    // or a body on its own
+    pub fn decode(bytes: &[u8]) -> Result<AdaTransactionBody, AdaTransactionError> {
+        let mut decoder = Decoder::new(bytes);
+        let body = if decoder.peek_type()? == ARRAY {
+            let len = decoder.read_array()?;
+            let body = AdaTransactionBody::decode_map(&mut decoder)?;
+            skip_rest(&mut decoder, len, 1)?;
+            body
+        } else {
+            AdaTransactionBody::decode_map(&mut decoder)?
+        };
+        decoder.finish()?;
+        Ok(body)
+    }
+
+    // sum of output amounts in lovelace, None on overflow
+    pub fn output_total(&self) -> Option<u64> {
+        self.outputs
+            .iter()
+            .try_fold(0u64, |total, output| total.checked_add(output.amount))
+    }
+
+    fn decode_map(decoder: &mut Decoder) -> Result<AdaTransactionBody, AdaTransactionError> {
+        let mut inputs = None;
+        let mut outputs = None;
+        let mut fee = None;
+        let mut ttl = None;
+        let mut other_fields = Vec::new();
+        let mut keys = Vec::new();
+        let len = decoder.read_map()?;
+        while has_next(decoder, len, keys.len() as u64) {
+            let key = decoder.read_uint()?;
+            if keys.contains(&key) {
+                return Err(AdaTransactionError::DuplicateField { key });
+            }
+            keys.push(key);
+            match key {
+                INPUTS_KEY => inputs = Some(decode_inputs(decoder)?),
+                OUTPUTS_KEY => outputs = Some(decode_outputs(decoder)?),
+                FEE_KEY => fee = Some(decoder.read_uint()?),
+                TTL_KEY => ttl = Some(decoder.read_uint()?),
+                AUXILIARY_DATA_HASH_KEY | VALIDITY_START_KEY | NETWORK_ID_KEY => decoder.skip()?,
+                _ => {
+                    decoder.skip()?;
+                    other_fields.push(key);
+                }
+            }
+        }
+        Ok(AdaTransactionBody {
+            inputs: inputs.ok_or(AdaTransactionError::MissingField { key: INPUTS_KEY })?,
+            outputs: outputs.ok_or(AdaTransactionError::MissingField { key: OUTPUTS_KEY })?,
+            fee: fee.ok_or(AdaTransactionError::MissingField { key: FEE_KEY })?,
+            ttl,
+            other_fields,
+        })
+    }
+}
+
+// Body of a full transaction or a body on its own exactly as it is encoded
+pub fn body_bytes(bytes: &[u8]) -> Result<&[u8], AdaTransactionError> {
+    let mut decoder = Decoder::new(bytes);
+    if decoder.peek_type()? == ARRAY {
+        decoder.read_array()?;
+    }
+    Ok(decoder.read_raw()?)
+}
+
+// blake2b-256 of the encoded body. It is the transaction id and the hash witnesses sign
+pub fn body_hash(bytes: &[u8]) -> Result<[u8; 32], AdaTransactionError> {
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(&Blake2b::<U32>::digest(body_bytes(bytes)?));
+    Ok(hash)
+}
+
+// Full transaction with a witness set of the given [vkey, signature] witnesses in place of the
+// witness set of the bytes. Body and the items after the witness set are kept as they are
+// encoded. A body on its own becomes [body, witness set, true, null].
+pub fn with_vkey_witnesses(
+    bytes: &[u8],
+    witnesses: &[([u8; 32], [u8; 64])],
+) -> Result<Vec<u8>, AdaTransactionError> {
+    let mut decoder = Decoder::new(bytes);
+    // None for a body on its own
+    let (body, rest) = if decoder.peek_type()? == ARRAY {
+        let len = decoder.read_array()?;
+        let body = decoder.read_raw()?;
+        let mut rest = Vec::new();
+        let mut read = 1;
+        while has_next(&mut decoder, len, read) {
+            let item = decoder.read_raw()?;
+            // the first item after the body is the witness set that is replaced
+            if read > 1 {
+                rest.push(item);
+            }
+            read += 1;
+        }
+        (body, Some(rest))
+    } else {
+        (decoder.read_raw()?, None)
+    };
+    decoder.finish()?;
+
+    let mut encoder = Encoder::new();
+    let len = rest.as_ref().map_or(4, |rest| 2 + rest.len() as u64);
+    encoder.write_array(len).write_raw(body);
+    encoder
+        .write_map(1)
+        .write_uint(VKEY_WITNESSES_KEY)
+        .write_array(witnesses.len() as u64);
+    for (vkey, signature) in witnesses {
+        encoder
+            .write_array(2)
+            .write_bytes(vkey)
+            .write_bytes(signature);
+    }
+    match rest {
+        Some(rest) => {
+            for item in rest {
+                encoder.write_raw(item);
+            }
+        }
+        None => {
+            encoder.write_bool(true).write_null();
+        }
+    }
+    Ok(encoder.into_bytes())
+}
+
+impl AdaOutput {
+    // bech32 for Shelley addresses with the prefix of the network in the header,
+    // base58 for Byron addresses
+    pub fn address_string(&self) -> String {
+        match self.address.first() {
+            Some(&BYRON_ADDRESS_PREFIX) | None => bs58::encode(&self.address).into_string(),
+            Some(header) => {
+                let hrp = if header & 0x0f == 0 {
+                    "addr_test"
+                } else {
+                    "addr"
+                };
+                bech32::encode(hrp, self.address.to_base32(), Variant::Bech32)
+                    .expect("valid bech32 hrp")
+            }
+        }
+    }
+}
+
+fn decode_inputs(decoder: &mut Decoder) -> Result<Vec<AdaInput>, AdaTransactionError> {
+    if decoder.peek_type()? == TAG {
+        decoder.expect_tag(SET_TAG)?;
+    }
+    let len = decoder.read_array()?;
+    let mut inputs = Vec::new();
+    while has_next(decoder, len, inputs.len() as u64) {
+        decoder.expect_array(2)?;
+        let id = decoder.read_bytes()?;
+        if id.len() != 32 {
+            return Err(CborError::UnexpectedLength {
+                expected: 32,
+                actual: id.len() as u64,
+            }
+            .into());
+        }
+        let mut transaction_id = [0u8; 32];
+        transaction_id.copy_from_slice(id);
+        inputs.push(AdaInput {
+            transaction_id,
+            index: decoder.read_uint()?,
+        });
+    }
+    Ok(inputs)
+}
+
+fn decode_outputs(decoder: &mut Decoder) -> Result<Vec<AdaOutput>, AdaTransactionError> {
+    let len = decoder.read_array()?;
+    let mut outputs = Vec::new();
+    while has_next(decoder, len, outputs.len() as u64) {
+        outputs.push(decode_output(decoder)?);
+    }
+    Ok(outputs)
+}
+
+// legacy [address, value, datum hash?] or post Alonzo {0: address, 1: value, ...}
+fn decode_output(decoder: &mut Decoder) -> Result<AdaOutput, AdaTransactionError> {
+    if decoder.peek_type()? == MAP {
+        let len = decoder.read_map()?;
+        let mut address = None;
+        let mut value = None;
+        let mut read = 0;
+        while has_next(decoder, len, read) {
+            read += 1;
+            match decoder.read_uint()? {
+                OUTPUT_ADDRESS_KEY => address = Some(decoder.read_bytes()?.to_vec()),
+                OUTPUT_VALUE_KEY => value = Some(decode_value(decoder)?),
+                _ => decoder.skip()?,
+            }
+        }
+        let (amount, has_assets) = value.ok_or(AdaTransactionError::MissingField {
+            key: OUTPUT_VALUE_KEY,
+        })?;
+        return Ok(AdaOutput {
+            address: address.ok_or(AdaTransactionError::MissingField {
+                key: OUTPUT_ADDRESS_KEY,
+            })?,
+            amount,
+            has_assets,
+        });
+    }
+    let len = decoder.read_array()?;
+    let address = decoder.read_bytes()?.to_vec();
+    let (amount, has_assets) = decode_value(decoder)?;
+    skip_rest(decoder, len, 2)?;
+    Ok(AdaOutput {
+        address,
+        amount,
+        has_assets,
+    })
+}
+
+// lovelace or [lovelace, multiasset]
+fn decode_value(decoder: &mut Decoder) -> Result<(u64, bool), AdaTransactionError> {
+    if decoder.peek_type()? == UNSIGNED {
+        return Ok((decoder.read_uint()?, false));
+    }
+    decoder.expect_array(2)?;
+    let amount = decoder.read_uint()?;
+    decoder.skip()?;
+    Ok((amount, true))
+}
+
+// true when an array or map of len has another item after read items
+fn has_next(decoder: &mut Decoder, len: Option<u64>, read: u64) -> bool {
+    match len {
+        Some(len) => read < len,
+        None => !decoder.read_break(),
+    }
+}
+
+// skip the items of an array after the read ones
+fn skip_rest(
+    decoder: &mut Decoder,
+    len: Option<u64>,
+    mut read: u64,
+) -> Result<(), AdaTransactionError> {
+    while has_next(decoder, len, read) {
+        read += 1;
+        decoder.skip()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+use std::fmt;
+
+use bech32::{ToBase32, Variant};
+use blake2::digest::consts::U28;
+use blake2::Blake2b;
+use k256::elliptic_curve::sec1::ToEncodedPoint;
+use ripemd::Ripemd160;
+use sha2::Sha256;
+use sha3::{Digest, Keccak256};
+
+use crate::address::eip55_checksum;
+use crate::encoding::{EncodingError, PubkeyHex};
+use crate::{
+    Blockchain, Coin, GetAddressRequest, GetAddressResult, KeyScheme, WalletCreationConfig,
+};
+
+// base58check version bytes of P2PKH addresses
+const P2PKH_MAINNET: u8 = 0x00;
+const P2PKH_TESTNET: u8 = 0x6f;
+// Shelley enterprise address header: type 6 (payment key hash) in the high 4 bits, network id
+// in the low 4 bits
+const ENTERPRISE_MAINNET_HEADER: u8 = 0x61;
+const ENTERPRISE_TESTNET_HEADER: u8 = 0x60;
+
+// Reason why the wallet address cannot be derived
+#[derive(Clone, PartialEq, Debug)]
+pub enum DerivationError {
+    // wallet config has no pubkey of the key scheme used by the blockchain
+    MissingPubkey {
+        key_scheme: KeyScheme,
+    },
+    // pubkey is not hex of a public key of the key scheme
+    InvalidPubkey {
+        key_scheme: KeyScheme,
+        error: EncodingError,
+    },
+    // secp256k1 pubkey is not a point on the curve
+    InvalidCurvePoint {
+        pubkey: String,
+    },
+    // coin does not exist on the blockchain
+    UnsupportedCoin {
+        coin: Coin,
+        blockchain: Blockchain,
+    },
+}
+
+impl fmt::Display for DerivationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DerivationError::MissingPubkey { key_scheme } => {
+                write!(f, "wallet has no {:?} pubkey", key_scheme)
+            }
+            DerivationError::InvalidPubkey { key_scheme, error } => write!(
+                f,
+                "pubkey is not a valid {:?} public key: {}",
+                key_scheme, error
+            ),
+            DerivationError::InvalidCurvePoint { pubkey } => {
+                write!(f, "pubkey {} is not a point on secp256k1", pubkey)
+            }
+            DerivationError::UnsupportedCoin { coin, blockchain } => {
+                write!(f, "{:?} is not supported on {:?}", coin, blockchain)
+            }
+        }
+    }
+}
+
+impl std::error::Error for DerivationError {}
+
+impl WalletCreationConfig {
+    // Address of the wallet on the blockchain. Tokens use the address of the native coin.
+    // BITCOIN: P2WPKH when isSegwit, P2PKH otherwise from the ECDSA pubkey
+    // ETHEREUM, POLYGON: EIP-55 address from the keccak hash of the ECDSA pubkey
+    // CARDANO: enterprise address from the blake2b-224 hash of the EDDSA pubkey
+    pub fn derive_address(
+        &self,
+        blockchain: &Blockchain,
+        coin: &Coin,
+    ) -> Result<String, DerivationError> {
+        if !blockchain.supports_coin(coin) {
+            return Err(DerivationError::UnsupportedCoin {
+                coin: coin.clone(),
+                blockchain: blockchain.clone(),
+            });
+        }
+        let key_scheme = blockchain.key_scheme();
+        let pubkey = self
+            .pubkeys
+            .iter()
+            .find(|pubkey| pubkey.key_scheme == key_scheme)
+            .ok_or_else(|| DerivationError::MissingPubkey {
+                key_scheme: key_scheme.clone(),
+            })?;
+        let parsed = PubkeyHex::parse(&key_scheme, &pubkey.pubkey).map_err(|error| {
+            DerivationError::InvalidPubkey {
+                key_scheme: key_scheme.clone(),
+                error,
+            }
+        })?;
+        match blockchain {
+            Blockchain::BITCOIN => self.bitcoin_address(&parsed),
+            Blockchain::ETHEREUM | Blockchain::POLYGON => evm_address(&parsed),
+            Blockchain::CARDANO => Ok(self.cardano_address(&parsed)),
+        }
+    }
+
+    fn bitcoin_address(&self, pubkey: &PubkeyHex) -> Result<String, DerivationError> {
+        if self.is_segwit {
+            // segwit only allows compressed keys
+            let program = hash160(&secp256k1_point(pubkey, true)?);
+            let hrp = if self.is_mainnet { "bc" } else { "tb" };
+            let mut data = vec![bech32::u5::try_from_u8(0).expect("witness version 0")];
+            data.extend(program.to_base32());
+            Ok(bech32::encode(hrp, data, Variant::Bech32).expect("valid bech32 hrp"))
+        } else {
+            let version = if self.is_mainnet {
+                P2PKH_MAINNET
+            } else {
+                P2PKH_TESTNET
+            };
+            // legacy wallets hash the key in the form it was created, compressed or not
+            let is_compressed = pubkey.to_bytes().len() == 33;
+            Ok(
+                bs58::encode(hash160(&secp256k1_point(pubkey, is_compressed)?))
+                    .with_check_version(version)
+                    .into_string(),
+            )
+        }
+    }
+
+    fn cardano_address(&self, pubkey: &PubkeyHex) -> String {
+        let (header, hrp) = if self.is_mainnet {
+            (ENTERPRISE_MAINNET_HEADER, "addr")
+        } else {
+            (ENTERPRISE_TESTNET_HEADER, "addr_test")
+        };
+        let mut bytes = vec![header];
+        bytes.extend_from_slice(&Blake2b::<U28>::digest(pubkey.to_bytes()));
+        bech32::encode(hrp, bytes.to_base32(), Variant::Bech32).expect("valid bech32 hrp")
+    }
+}
+
+impl GetAddressRequest {
+    pub fn derive_address(&self) -> Result<GetAddressResult, DerivationError> {
+        Ok(GetAddressResult {
+            address: self
+                .wallet_config
+                .derive_address(&self.blockchain, &self.coin)?,
+        })
+    }
+}
+
+fn evm_address(pubkey: &PubkeyHex) -> Result<String, DerivationError> {
+    let point = secp256k1_point(pubkey, false)?;
+    Ok(eip55_checksum(&evm_account(&point)))
+}
+
+// last 20 bytes of the keccak hash of the uncompressed key without its 04 prefix
+pub(crate) fn evm_account(uncompressed_point: &[u8]) -> [u8; 20] {
+    let hash = Keccak256::digest(&uncompressed_point[1..]);
+    let mut account = [0u8; 20];
+    account.copy_from_slice(&hash[12..]);
+    account
+}
+
+// SEC1 encoding of the secp256k1 pubkey, compressed or uncompressed
+fn secp256k1_point(pubkey: &PubkeyHex, compress: bool) -> Result<Vec<u8>, DerivationError> {
+    let key = k256::PublicKey::from_sec1_bytes(&pubkey.to_bytes()).map_err(|_| {
+        DerivationError::InvalidCurvePoint {
+            pubkey: pubkey.as_str().to_string(),
+        }
+    })?;
+    Ok(key.to_encoded_point(compress).as_bytes().to_vec())
+}
+
+pub(crate) fn hash160(bytes: &[u8]) -> [u8; 20] {
+    let mut hash = [0u8; 20];
+    hash.copy_from_slice(&Ripemd160::digest(Sha256::digest(bytes)));
+    hash
+}
@@ -0,0 +1,317 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::KeyScheme;
+
+// Reason why a string is not a valid encoding
+#[derive(Clone, PartialEq, Debug)]
+pub enum EncodingError {
+    // not hex or odd number of digits
+    InvalidHex {
+        value: String,
+    },
+    // not standard base64
+    InvalidBase64 {
+        value: String,
+    },
+    // decoded bytes have an unexpected length
+    InvalidLength {
+        expected: &'static str,
+        actual: usize,
+    },
+    // public key does not start with a valid prefix byte
+    InvalidPubkeyPrefix {
+        prefix: u8,
+    },
+}
+
+impl fmt::Display for EncodingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EncodingError::InvalidHex { value } => write!(f, "'{}' is not valid hex", value),
+            EncodingError::InvalidBase64 { value } => {
+                write!(f, "'{}' is not valid base64", value)
+            }
+            EncodingError::InvalidLength { expected, actual } => {
+                write!(f, "expected {} bytes but got {}", expected, actual)
+            }
+            EncodingError::InvalidPubkeyPrefix { prefix } => {
+                write!(f, "invalid public key prefix {:#04x}", prefix)
+            }
+        }
+    }
+}
+
+impl std::error::Error for EncodingError {}
+
+fn strip_hex_prefix(value: &str) -> &str {
+    value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value)
+}
+
+// strip optional 0x prefix, lower case and check the value is hex
+fn normalize_hex(value: &str) -> Result<(String, Vec<u8>), EncodingError> {
+    let normalized = strip_hex_prefix(value).to_ascii_lowercase();
+    let bytes = hex::decode(&normalized).map_err(|_| EncodingError::InvalidHex {
+        value: value.to_string(),
+    })?;
+    Ok((normalized, bytes))
+}
+
+// Implements the string conversions shared by every encoded newtype. The value is validated
+// by $parse when converting from a string so an instance always holds a normalized value.
+macro_rules! encoded_string {
+    ($name:ident, $parse:expr) => {
+        impl $name {
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = EncodingError;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                let parse: fn(&str) -> Result<String, EncodingError> = $parse;
+                parse(&value).map($name)
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = EncodingError;
+
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                let parse: fn(&str) -> Result<String, EncodingError> = $parse;
+                parse(value).map($name)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = EncodingError;
+
+            fn from_str(value: &str) -> Result<Self, Self::Err> {
+                $name::try_from(value)
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> Self {
+                value.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+    };
+}
+
+// Implements byte access for newtypes holding normalized hex
+macro_rules! hex_bytes {
+    ($name:ident) => {
+        impl $name {
+            pub fn to_bytes(&self) -> Vec<u8> {
+                hex::decode(&self.0).expect("value is validated hex")
+            }
+        }
+    };
+}
+
+// Arbitrary bytes in hex. No 0x prefix. Lower case.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct HexBytes(String);
+
+encoded_string!(HexBytes, |value| normalize_hex(value).map(|(hex, _)| hex));
+hex_bytes!(HexBytes);
+
+impl HexBytes {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        HexBytes(hex::encode(bytes))
+    }
+}
+
+// 32 bytes value in hex e.g. a hash to sign. No 0x prefix. Lower case.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Hash32Hex(String);
+
+encoded_string!(Hash32Hex, |value| {
+    let (hex, bytes) = normalize_hex(value)?;
+    check_length(&bytes, &[32], "32")?;
+    Ok(hex)
+});
+hex_bytes!(Hash32Hex);
+
+impl Hash32Hex {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Hash32Hex(hex::encode(bytes))
+    }
+
+    pub fn to_array(&self) -> [u8; 32] {
+        let mut array = [0u8; 32];
+        array.copy_from_slice(&self.to_bytes());
+        array
+    }
+}
+
+// secp256k1 signature scalar r or s in hex. Signers may strip leading zeros so 1 to 32 bytes
+// and odd digit counts are accepted and left padded to 32 bytes. No 0x prefix. Lower case.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ScalarHex(String);
+
+encoded_string!(ScalarHex, |value| {
+    let digits = strip_hex_prefix(value);
+    if digits.is_empty() || digits.len() > 64 {
+        return Err(EncodingError::InvalidLength {
+            expected: "1 to 32",
+            actual: (digits.len() + 1) / 2,
+        });
+    }
+    normalize_hex(&format!("{:0>64}", digits))
+        .map(|(hex, _)| hex)
+        .map_err(|_| EncodingError::InvalidHex {
+            value: value.to_string(),
+        })
+});
+hex_bytes!(ScalarHex);
+
+impl ScalarHex {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        ScalarHex(hex::encode(bytes))
+    }
+
+    pub fn to_array(&self) -> [u8; 32] {
+        let mut array = [0u8; 32];
+        array.copy_from_slice(&self.to_bytes());
+        array
+    }
+}
+
+// secp256k1 public key in hex. Either 33 bytes compressed or 65 bytes uncompressed. No 0x prefix. Lower case.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Secp256k1PubkeyHex(String);
+
+encoded_string!(Secp256k1PubkeyHex, |value| {
+    let (hex, bytes) = normalize_hex(value)?;
+    check_length(&bytes, &[33, 65], "33 or 65")?;
+    let prefix = bytes[0];
+    let valid_prefix = match bytes.len() {
+        33 => prefix == 0x02 || prefix == 0x03,
+        _ => prefix == 0x04,
+    };
+    if !valid_prefix {
+        return Err(EncodingError::InvalidPubkeyPrefix { prefix });
+    }
+    Ok(hex)
+});
+hex_bytes!(Secp256k1PubkeyHex);
+
+impl Secp256k1PubkeyHex {
+    pub fn is_compressed(&self) -> bool {
+        self.0.len() == 66
+    }
+}
+
+// ed25519 public key in hex. 32 bytes. No 0x prefix. Lower case.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Ed25519PubkeyHex(String);
+
+encoded_string!(Ed25519PubkeyHex, |value| {
+    let (hex, bytes) = normalize_hex(value)?;
+    check_length(&bytes, &[32], "32")?;
+    Ok(hex)
+});
+hex_bytes!(Ed25519PubkeyHex);
+
+impl Ed25519PubkeyHex {
+    pub fn to_array(&self) -> [u8; 32] {
+        let mut array = [0u8; 32];
+        array.copy_from_slice(&self.to_bytes());
+        array
+    }
+}
+
+// Arbitrary bytes in standard base64 with padding. The value is kept as it is.
+#[derive(Clone, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Base64Bytes(String);
+
+encoded_string!(Base64Bytes, |value| {
+    base64::decode(value).map_err(|_| EncodingError::InvalidBase64 {
+        value: value.to_string(),
+    })?;
+    Ok(value.to_string())
+});
+
+impl Base64Bytes {
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        Base64Bytes(base64::encode(bytes))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        base64::decode(&self.0).expect("value is validated base64")
+    }
+}
+
+// Wallet public key of either key scheme. Wire fields carrying a pubkey stay plain strings because the
+// curve depends on a sibling keyScheme field, use this to parse them.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub enum PubkeyHex {
+    Secp256k1(Secp256k1PubkeyHex),
+    Ed25519(Ed25519PubkeyHex),
+}
+
+impl PubkeyHex {
+    pub fn parse(key_scheme: &KeyScheme, value: &str) -> Result<Self, EncodingError> {
+        match key_scheme {
+            KeyScheme::ECDSA => value.parse().map(PubkeyHex::Secp256k1),
+            KeyScheme::EDDSA => value.parse().map(PubkeyHex::Ed25519),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            PubkeyHex::Secp256k1(pubkey) => pubkey.as_str(),
+            PubkeyHex::Ed25519(pubkey) => pubkey.as_str(),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            PubkeyHex::Secp256k1(pubkey) => pubkey.to_bytes(),
+            PubkeyHex::Ed25519(pubkey) => pubkey.to_bytes(),
+        }
+    }
+}
+
+fn check_length(
+    bytes: &[u8],
+    allowed: &[usize],
+    expected: &'static str,
+) -> Result<(), EncodingError> {
+    if allowed.contains(&bytes.len()) {
+        Ok(())
+    } else {
+        Err(EncodingError::InvalidLength {
+            expected,
+            actual: bytes.len(),
+        })
+    }
+}
@@ -0,0 +1,793 @@
+use std::fmt;
+
+use bigdecimal::num_bigint::{BigInt, Sign};
+use serde::Deserialize;
+use sha3::{Digest, Keccak256};
+
+use crate::address::eip55_checksum;
+use crate::encoding::{EncodingError, HexBytes};
+use crate::EthContractRequest;
+
+// ABI encodes every value in 32 bytes words
+const WORD: usize = 32;
+// nesting of tuples and arrays in a type
+const MAX_DEPTH: usize = 16;
+
+// Functions of the built-in table by signature and argument names
+const WELL_KNOWN_FUNCTIONS: &[(&str, &[&str])] = &[
+    // ERC-20. transferFrom and approve share their selectors with ERC-721
+    ("transfer(address,uint256)", &["to", "amount"]),
+    ("approve(address,uint256)", &["spender", "amount"]),
+    (
+        "transferFrom(address,address,uint256)",
+        &["from", "to", "amount"],
+    ),
+    (
+        "increaseAllowance(address,uint256)",
+        &["spender", "addedValue"],
+    ),
+    (
+        "decreaseAllowance(address,uint256)",
+        &["spender", "subtractedValue"],
+    ),
+    // ERC-721
+    (
+        "safeTransferFrom(address,address,uint256)",
+        &["from", "to", "tokenId"],
+    ),
+    (
+        "safeTransferFrom(address,address,uint256,bytes)",
+        &["from", "to", "tokenId", "data"],
+    ),
+    ("setApprovalForAll(address,bool)", &["operator", "approved"]),
+    // ERC-1155
+    (
+        "safeTransferFrom(address,address,uint256,uint256,bytes)",
+        &["from", "to", "id", "amount", "data"],
+    ),
+    (
+        "safeBatchTransferFrom(address,address,uint256[],uint256[],bytes)",
+        &["from", "to", "ids", "amounts", "data"],
+    ),
+    // WETH
+    ("deposit()", &[]),
+    ("withdraw(uint256)", &["amount"]),
+    // Uniswap v2 router
+    (
+        "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+        &["amountIn", "amountOutMin", "path", "to", "deadline"],
+    ),
+    (
+        "swapTokensForExactTokens(uint256,uint256,address[],address,uint256)",
+        &["amountOut", "amountInMax", "path", "to", "deadline"],
+    ),
+    (
+        "swapExactETHForTokens(uint256,address[],address,uint256)",
+        &["amountOutMin", "path", "to", "deadline"],
+    ),
+    (
+        "swapTokensForExactETH(uint256,uint256,address[],address,uint256)",
+        &["amountOut", "amountInMax", "path", "to", "deadline"],
+    ),
+    (
+        "swapExactTokensForETH(uint256,uint256,address[],address,uint256)",
+        &["amountIn", "amountOutMin", "path", "to", "deadline"],
+    ),
+    (
+        "swapETHForExactTokens(uint256,address[],address,uint256)",
+        &["amountOut", "path", "to", "deadline"],
+    ),
+    // Uniswap v3 SwapRouter
+    (
+        "exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+        &["params"],
+    ),
+    (
+        "exactInput((bytes,address,uint256,uint256,uint256))",
+        &["params"],
+    ),
+    (
+        "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+        &["params"],
+    ),
+    (
+        "exactOutput((bytes,address,uint256,uint256,uint256))",
+        &["params"],
+    ),
+    // Uniswap v3 SwapRouter02 without deadline
+    (
+        "exactInputSingle((address,address,uint24,address,uint256,uint256,uint160))",
+        &["params"],
+    ),
+    ("exactInput((bytes,address,uint256,uint256))", &["params"]),
+    (
+        "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint160))",
+        &["params"],
+    ),
+    ("exactOutput((bytes,address,uint256,uint256))", &["params"]),
+    // multicall of Uniswap v3 periphery and Multicall3
+    ("multicall(bytes[])", &["data"]),
+    ("multicall(uint256,bytes[])", &["deadline", "data"]),
+    ("aggregate((address,bytes)[])", &["calls"]),
+    ("aggregate3((address,bool,bytes)[])", &["calls"]),
+];
+
+// Reason why an ABI or call data cannot be decoded
+#[derive(Clone, PartialEq, Debug)]
+pub enum AbiError {
+    // ABI is not JSON of a list of ABI entries
+    InvalidJson { error: String },
+    // type is not a Solidity ABI type
+    InvalidType { type_name: String },
+    // function signature is not name(type,...)
+    InvalidSignature { signature: String },
+    // call data ended before a value
+    UnexpectedEnd,
+    // offset or length of a dynamic value points outside of the call data
+    InvalidOffset { offset: BigInt },
+    // dynamic values share bytes of the call data, which a valid encoding never does
+    OverlappingValues,
+    // value has bits set that its type does not allow e.g. an address with a non zero padding
+    InvalidValue { type_name: String },
+    // smart contract data is not hex
+    InvalidData(EncodingError),
+}
+
+impl fmt::Display for AbiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiError::InvalidJson { error } => write!(f, "invalid ABI JSON: {}", error),
+            AbiError::InvalidType { type_name } => {
+                write!(f, "{} is not a valid ABI type", type_name)
+            }
+            AbiError::InvalidSignature { signature } => {
+                write!(f, "{} is not a valid function signature", signature)
+            }
+            AbiError::UnexpectedEnd => write!(f, "unexpected end of call data"),
+            AbiError::InvalidOffset { offset } => {
+                write!(f, "offset {} is outside of the call data", offset)
+            }
+            AbiError::OverlappingValues => write!(f, "values of the call data overlap"),
+            AbiError::InvalidValue { type_name } => {
+                write!(f, "call data has an invalid {} value", type_name)
+            }
+            AbiError::InvalidData(error) => write!(f, "data: {}", error),
+        }
+    }
+}
+
+impl std::error::Error for AbiError {}
+
+// Solidity ABI type
+#[derive(Clone, PartialEq, Debug)]
+pub enum AbiType {
+    Address,
+    Bool,
+    // unsigned integer of 8 to 256 bits
+    Uint(usize),
+    // two's complement signed integer of 8 to 256 bits
+    Int(usize),
+    // bytes1 to bytes32
+    FixedBytes(usize),
+    Bytes,
+    String,
+    // T[]
+    Array(Box<AbiType>),
+    // T[n]
+    FixedArray(Box<AbiType>, usize),
+    Tuple(Vec<AbiType>),
+}
+
+impl AbiType {
+    // type of its canonical name e.g. uint256, address[] or (address,uint24)[2]
+    pub fn parse(type_name: &str) -> Result<AbiType, AbiError> {
+        parse_type(type_name.trim(), 0)
+    }
+
+    // true when the value is encoded after the head of its tuple
+    pub fn is_dynamic(&self) -> bool {
+        match self {
+            AbiType::Bytes | AbiType::String | AbiType::Array(_) => true,
+            AbiType::FixedArray(item, _) => item.is_dynamic(),
+            AbiType::Tuple(items) => items.iter().any(AbiType::is_dynamic),
+            _ => false,
+        }
+    }
+
+    // size of the value in the head of its tuple: an offset for dynamic values
+    fn head_size(&self) -> usize {
+        match self {
+            _ if self.is_dynamic() => WORD,
+            AbiType::FixedArray(item, len) => item.head_size().saturating_mul(*len),
+            AbiType::Tuple(items) => items
+                .iter()
+                .fold(0, |size, item| size.saturating_add(item.head_size())),
+            _ => WORD,
+        }
+    }
+}
+
+impl fmt::Display for AbiType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiType::Address => write!(f, "address"),
+            AbiType::Bool => write!(f, "bool"),
+            AbiType::Uint(bits) => write!(f, "uint{}", bits),
+            AbiType::Int(bits) => write!(f, "int{}", bits),
+            AbiType::FixedBytes(len) => write!(f, "bytes{}", len),
+            AbiType::Bytes => write!(f, "bytes"),
+            AbiType::String => write!(f, "string"),
+            AbiType::Array(item) => write!(f, "{}[]", item),
+            AbiType::FixedArray(item, len) => write!(f, "{}[{}]", item, len),
+            AbiType::Tuple(items) => write!(f, "({})", join(items)),
+        }
+    }
+}
+
+// A decoded value
+#[derive(Clone, PartialEq, Debug)]
+pub enum AbiValue {
+    Address([u8; 20]),
+    Bool(bool),
+    Uint(BigInt),
+    Int(BigInt),
+    // fixed and dynamic bytes
+    Bytes(Vec<u8>),
+    String(String),
+    // items of a fixed or dynamic array
+    Array(Vec<AbiValue>),
+    Tuple(Vec<AbiValue>),
+}
+
+// addresses in EIP-55, integers in decimal and bytes in 0x hex
+impl fmt::Display for AbiValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AbiValue::Address(address) => write!(f, "{}", eip55_checksum(address)),
+            AbiValue::Bool(value) => write!(f, "{}", value),
+            AbiValue::Uint(value) | AbiValue::Int(value) => write!(f, "{}", value),
+            AbiValue::Bytes(bytes) => write!(f, "0x{}", hex::encode(bytes)),
+            AbiValue::String(value) => write!(f, "{:?}", value),
+            AbiValue::Array(items) => write!(f, "[{}]", join(items)),
+            AbiValue::Tuple(items) => write!(f, "({})", join(items)),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AbiParam {
+    // empty when the ABI does not name the parameter
+    pub name: String,
+    pub kind: AbiType,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AbiFunction {
+    pub name: String,
+    pub inputs: Vec<AbiParam>,
+}
+
+impl AbiFunction {
+    // Function of a signature e.g. transfer(address,uint256) and the names of its parameters.
+    // Missing names are left empty.
+    pub fn parse(signature: &str, names: &[&str]) -> Result<AbiFunction, AbiError> {
+        let invalid = || AbiError::InvalidSignature {
+            signature: signature.to_string(),
+        };
+        let signature = signature.trim();
+        let open = signature.find('(').ok_or_else(invalid)?;
+        let name = &signature[..open];
+        if name.is_empty() || !is_identifier(name) {
+            return Err(invalid());
+        }
+        let inputs = match parse_type(&signature[open..], 0)? {
+            AbiType::Tuple(inputs) => inputs,
+            _ => return Err(invalid()),
+        };
+        Ok(AbiFunction {
+            name: name.to_string(),
+            inputs: inputs
+                .into_iter()
+                .enumerate()
+                .map(|(index, kind)| AbiParam {
+                    name: names.get(index).unwrap_or(&"").to_string(),
+                    kind,
+                })
+                .collect(),
+        })
+    }
+
+    // canonical signature the selector is computed from
+    pub fn signature(&self) -> String {
+        let types: Vec<&AbiType> = self.inputs.iter().map(|input| &input.kind).collect();
+        format!("{}({})", self.name, join(&types))
+    }
+
+    // first 4 bytes of keccak256 of the signature
+    pub fn selector(&self) -> [u8; 4] {
+        let mut selector = [0u8; 4];
+        selector.copy_from_slice(&Keccak256::digest(self.signature().as_bytes())[..4]);
+        selector
+    }
+
+    // arguments of call data without its selector
+    pub fn decode_arguments(&self, arguments: &[u8]) -> Result<Vec<DecodedArgument>, AbiError> {
+        let types: Vec<AbiType> = self.inputs.iter().map(|input| input.kind.clone()).collect();
+        // every decoded word and byte has its own place in a valid encoding so decoding more
+        // than the call data means offsets point into each other
+        let mut budget = arguments.len();
+        let values = decode_tuple(&types, arguments, &mut budget)?;
+        Ok(self
+            .inputs
+            .iter()
+            .zip(values)
+            .map(|(input, value)| DecodedArgument {
+                name: input.name.clone(),
+                kind: input.kind.clone(),
+                value,
+            })
+            .collect())
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct DecodedArgument {
+    pub name: String,
+    pub kind: AbiType,
+    pub value: AbiValue,
+}
+
+// function and arguments of call data
+#[derive(Clone, PartialEq, Debug)]
+pub struct DecodedCall {
+    pub name: String,
+    pub signature: String,
+    pub arguments: Vec<DecodedArgument>,
+}
+
+// e.g. transfer(address to: 0x3535..., uint256 amount: 1500000)
+impl fmt::Display for DecodedCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let arguments: Vec<String> = self
+            .arguments
+            .iter()
+            .map(|argument| match argument.name.as_str() {
+                "" => format!("{} {}", argument.kind, argument.value),
+                name => format!("{} {}: {}", argument.kind, name, argument.value),
+            })
+            .collect();
+        write!(f, "{}({})", self.name, arguments.join(", "))
+    }
+}
+
+// What call data does
+#[derive(Clone, PartialEq, Debug)]
+pub enum ContractCall {
+    Decoded(DecodedCall),
+    // no known function has the selector. Call data shorter than a selector is its own selector
+    UnknownSelector { selector: String },
+}
+
+impl fmt::Display for ContractCall {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ContractCall::Decoded(call) => write!(f, "{}", call),
+            ContractCall::UnknownSelector { selector } => {
+                write!(f, "unknown selector 0x{}", selector)
+            }
+        }
+    }
+}
+
+// Functions that call data is decoded with, looked up by selector in order
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct AbiDecoder {
+    functions: Vec<AbiFunction>,
+}
+
+impl AbiDecoder {
+    pub fn new(functions: Vec<AbiFunction>) -> Self {
+        AbiDecoder { functions }
+    }
+
+    // functions of well-known token, WETH, Uniswap and multicall contracts
+    pub fn well_known() -> Self {
+        AbiDecoder::new(
+            WELL_KNOWN_FUNCTIONS
+                .iter()
+                .map(|(signature, names)| {
+                    AbiFunction::parse(signature, names).expect("valid built-in signature")
+                })
+                .collect(),
+        )
+    }
+
+    // functions of a contract ABI JSON. Other entries e.g. events are ignored
+    pub fn from_json(json: &str) -> Result<Self, AbiError> {
+        let entries: Vec<JsonEntry> =
+            serde_json::from_str(json).map_err(|error| AbiError::InvalidJson {
+                error: error.to_string(),
+            })?;
+        let mut functions = Vec::new();
+        for entry in entries {
+            if entry.kind.as_deref().unwrap_or("function") != "function" {
+                continue;
+            }
+            let mut inputs = Vec::with_capacity(entry.inputs.len());
+            for input in &entry.inputs {
+                inputs.push(AbiParam {
+                    name: input.name.clone(),
+                    kind: input.to_type(0)?,
+                });
+            }
+            functions.push(AbiFunction {
+                name: entry.name,
+                inputs,
+            });
+        }
+        Ok(AbiDecoder::new(functions))
+    }
+
+    // functions of the other decoder are looked up after the ones of this decoder
+    pub fn extend(&mut self, other: AbiDecoder) {
+        self.functions.extend(other.functions);
+    }
+
+    pub fn functions(&self) -> &[AbiFunction] {
+        &self.functions
+    }
+
+    // Decode with the first function of the selector. Fails when the arguments do not match it
+    pub fn decode(&self, data: &[u8]) -> Result<ContractCall, AbiError> {
+        if data.len() < 4 {
+            return Ok(ContractCall::UnknownSelector {
+                selector: hex::encode(data),
+            });
+        }
+        let (selector, arguments) = data.split_at(4);
+        let function = match self
+            .functions
+            .iter()
+            .find(|function| function.selector() == selector)
+        {
+            Some(function) => function,
+            None => {
+                return Ok(ContractCall::UnknownSelector {
+                    selector: hex::encode(selector),
+                })
+            }
+        };
+        Ok(ContractCall::Decoded(DecodedCall {
+            name: function.name.clone(),
+            signature: function.signature(),
+            arguments: function.decode_arguments(arguments)?,
+        }))
+    }
+}
+
+impl EthContractRequest {
+    // what the data calls using the built-in table of well-known functions
+    pub fn decode_data(&self) -> Result<ContractCall, AbiError> {
+        self.decode_data_with(&AbiDecoder::well_known())
+    }
+
+    pub fn decode_data_with(&self, decoder: &AbiDecoder) -> Result<ContractCall, AbiError> {
+        let data = HexBytes::try_from(self.data.trim())
+            .map_err(AbiError::InvalidData)?
+            .to_bytes();
+        decoder.decode(&data)
+    }
+}
+
+#[derive(Deserialize)]
+struct JsonEntry {
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    inputs: Vec<JsonParam>,
+}
+
+#[derive(Deserialize)]
+struct JsonParam {
+    #[serde(default)]
+    name: String,
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    components: Vec<JsonParam>,
+}
+
+impl JsonParam {
+    // tuple types name their items in components e.g. tuple[] with components [address, bytes]
+    fn to_type(&self, depth: usize) -> Result<AbiType, AbiError> {
+        let suffix = match self.kind.strip_prefix("tuple") {
+            Some(suffix) => suffix,
+            None => return parse_type(&self.kind, depth),
+        };
+        if depth > MAX_DEPTH {
+            return Err(AbiError::InvalidType {
+                type_name: self.kind.clone(),
+            });
+        }
+        let mut items = Vec::with_capacity(self.components.len());
+        for component in &self.components {
+            items.push(component.to_type(depth + 1)?);
+        }
+        array_type(AbiType::Tuple(items), suffix, &self.kind)
+    }
+}
+
+fn parse_type(type_name: &str, depth: usize) -> Result<AbiType, AbiError> {
+    let invalid = || AbiError::InvalidType {
+        type_name: type_name.to_string(),
+    };
+    if depth > MAX_DEPTH {
+        return Err(invalid());
+    }
+    if type_name.starts_with('(') {
+        let close = matching_paren(type_name).ok_or_else(invalid)?;
+        let inner = &type_name[1..close];
+        let mut items = Vec::new();
+        if !inner.is_empty() {
+            for item in split_top_level(inner) {
+                items.push(parse_type(item.trim(), depth + 1)?);
+            }
+        }
+        return array_type(AbiType::Tuple(items), &type_name[close + 1..], type_name);
+    }
+    let base_end = type_name.find('[').unwrap_or(type_name.len());
+    let (base, suffix) = type_name.split_at(base_end);
+    let bits = |digits: &str, default: usize| -> Option<usize> {
+        if digits.is_empty() {
+            return Some(default);
+        }
+        digits
+            .parse()
+            .ok()
+            .filter(|bits| *bits > 0 && bits % 8 == 0 && *bits <= 256)
+    };
+    let base_type = match base {
+        "address" => AbiType::Address,
+        "bool" => AbiType::Bool,
+        "bytes" => AbiType::Bytes,
+        "string" => AbiType::String,
+        _ => {
+            if let Some(digits) = base.strip_prefix("uint") {
+                AbiType::Uint(bits(digits, 256).ok_or_else(invalid)?)
+            } else if let Some(digits) = base.strip_prefix("int") {
+                AbiType::Int(bits(digits, 256).ok_or_else(invalid)?)
+            } else if let Some(digits) = base.strip_prefix("bytes") {
+                let len: usize = digits.parse().map_err(|_| invalid())?;
+                if !(1..=WORD).contains(&len) {
+                    return Err(invalid());
+                }
+                AbiType::FixedBytes(len)
+            } else {
+                return Err(invalid());
+            }
+        }
+    };
+    array_type(base_type, suffix, type_name)
+}
+
+// item type with array suffixes e.g. [] or [2][]
+fn array_type(item: AbiType, mut suffix: &str, type_name: &str) -> Result<AbiType, AbiError> {
+    let invalid = || AbiError::InvalidType {
+        type_name: type_name.to_string(),
+    };
+    let mut kind = item;
+    let mut depth = 0;
+    while !suffix.is_empty() {
+        depth += 1;
+        if depth > MAX_DEPTH || !suffix.starts_with('[') {
+            return Err(invalid());
+        }
+        let close = suffix.find(']').ok_or_else(invalid)?;
+        let len = &suffix[1..close];
+        kind = if len.is_empty() {
+            AbiType::Array(Box::new(kind))
+        } else {
+            AbiType::FixedArray(Box::new(kind), len.parse().map_err(|_| invalid())?)
+        };
+        suffix = &suffix[close + 1..];
+    }
+    Ok(kind)
+}
+
+// position of the parenthesis closing the one at the start
+fn matching_paren(value: &str) -> Option<usize> {
+    let mut depth = 0;
+    for (position, character) in value.char_indices() {
+        match character {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(position);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// items separated by commas outside of nested tuples
+fn split_top_level(value: &str) -> Vec<&str> {
+    let mut items = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (position, character) in value.char_indices() {
+        match character {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                items.push(&value[start..position]);
+                start = position + 1;
+            }
+            _ => {}
+        }
+    }
+    items.push(&value[start..]);
+    items
+}
+
+fn is_identifier(name: &str) -> bool {
+    name.chars()
+        .all(|character| character.is_ascii_alphanumeric() || character == '_' || character == '$')
+}
+
+// Values of a tuple encoded in data: static values in the head, dynamic values after it at the
+// offset from the start of the tuple written in the head
+// Budget is the number of bytes that can still be decoded. Dynamic values live in the tail
+// after the heads of all values of the tuple.
+fn decode_tuple(
+    types: &[AbiType],
+    data: &[u8],
+    budget: &mut usize,
+) -> Result<Vec<AbiValue>, AbiError> {
+    let mut values = Vec::with_capacity(types.len());
+    let tail = types
+        .iter()
+        .fold(0usize, |size, kind| size.saturating_add(kind.head_size()));
+    let mut head = 0;
+    for kind in types {
+        let value = if kind.is_dynamic() {
+            let offset = read_offset(data, head)?;
+            if offset < tail {
+                return Err(AbiError::InvalidOffset {
+                    offset: BigInt::from(offset),
+                });
+            }
+            decode_value(kind, &data[offset..], budget)?
+        } else {
+            let data = data.get(head..).ok_or(AbiError::UnexpectedEnd)?;
+            decode_value(kind, data, budget)?
+        };
+        values.push(value);
+        head = head.saturating_add(kind.head_size());
+    }
+    Ok(values)
+}
+
+fn decode_value(kind: &AbiType, data: &[u8], budget: &mut usize) -> Result<AbiValue, AbiError> {
+    let invalid = || AbiError::InvalidValue {
+        type_name: kind.to_string(),
+    };
+    match kind {
+        AbiType::FixedArray(..) | AbiType::Tuple(_) => {}
+        AbiType::Bytes | AbiType::String => {
+            let len = read_offset(data, 0)?;
+            spend(budget, WORD.saturating_add(len))?;
+        }
+        _ => spend(budget, WORD)?,
+    }
+    match kind {
+        AbiType::Address => {
+            let word = read_word(data, 0)?;
+            if word[..WORD - 20].iter().any(|byte| *byte != 0) {
+                return Err(invalid());
+            }
+            let mut address = [0u8; 20];
+            address.copy_from_slice(&word[WORD - 20..]);
+            Ok(AbiValue::Address(address))
+        }
+        AbiType::Bool => match read_uint(data, 0)? {
+            value if value == BigInt::from(0) => Ok(AbiValue::Bool(false)),
+            value if value == BigInt::from(1) => Ok(AbiValue::Bool(true)),
+            _ => Err(invalid()),
+        },
+        AbiType::Uint(bits) => {
+            let value = read_uint(data, 0)?;
+            if value.bits() > *bits as u64 {
+                return Err(invalid());
+            }
+            Ok(AbiValue::Uint(value))
+        }
+        AbiType::Int(bits) => {
+            let value = BigInt::from_signed_bytes_be(read_word(data, 0)?);
+            // sign extended to 256 bits so the value must fit in bits including the sign
+            let limit = BigInt::from(1) << (*bits - 1);
+            if value >= limit || value < -limit {
+                return Err(invalid());
+            }
+            Ok(AbiValue::Int(value))
+        }
+        AbiType::FixedBytes(len) => {
+            let word = read_word(data, 0)?;
+            if word[*len..].iter().any(|byte| *byte != 0) {
+                return Err(invalid());
+            }
+            Ok(AbiValue::Bytes(word[..*len].to_vec()))
+        }
+        AbiType::Bytes => Ok(AbiValue::Bytes(read_dynamic_bytes(data)?.to_vec())),
+        AbiType::String => String::from_utf8(read_dynamic_bytes(data)?.to_vec())
+            .map(AbiValue::String)
+            .map_err(|_| invalid()),
+        AbiType::Array(item) => {
+            let len = read_offset(data, 0)?;
+            let items = &data[WORD..];
+            // every item takes at least a word of the head so a longer array cannot be encoded
+            if len > items.len() / WORD {
+                return Err(AbiError::InvalidOffset {
+                    offset: BigInt::from(len),
+                });
+            }
+            decode_tuple(&vec![(**item).clone(); len], items, budget).map(AbiValue::Array)
+        }
+        AbiType::FixedArray(item, len) => {
+            if *len > data.len() / WORD {
+                return Err(AbiError::UnexpectedEnd);
+            }
+            decode_tuple(&vec![(**item).clone(); *len], data, budget).map(AbiValue::Array)
+        }
+        AbiType::Tuple(items) => decode_tuple(items, data, budget).map(AbiValue::Tuple),
+    }
+}
+
+// take size bytes of the decoding budget
+fn spend(budget: &mut usize, size: usize) -> Result<(), AbiError> {
+    *budget = budget
+        .checked_sub(size)
+        .ok_or(AbiError::OverlappingValues)?;
+    Ok(())
+}
+
+fn read_word(data: &[u8], position: usize) -> Result<&[u8], AbiError> {
+    position
+        .checked_add(WORD)
+        .and_then(|end| data.get(position..end))
+        .ok_or(AbiError::UnexpectedEnd)
+}
+
+fn read_uint(data: &[u8], position: usize) -> Result<BigInt, AbiError> {
+    Ok(BigInt::from_bytes_be(
+        Sign::Plus,
+        read_word(data, position)?,
+    ))
+}
+
+// offset or length that must point inside of the data
+fn read_offset(data: &[u8], position: usize) -> Result<usize, AbiError> {
+    let value = read_uint(data, position)?;
+    usize::try_from(&value)
+        .ok()
+        .filter(|offset| *offset <= data.len())
+        .ok_or(AbiError::InvalidOffset { offset: value })
+}
+
+// length followed by the bytes padded to whole words
+fn read_dynamic_bytes(data: &[u8]) -> Result<&[u8], AbiError> {
+    let len = read_offset(data, 0)?;
+    data.get(WORD..WORD + len).ok_or(AbiError::UnexpectedEnd)
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
@@ -0,0 +1,302 @@
+use std::fmt;
+
+use bigdecimal::num_bigint::{BigInt, Sign};
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::address::{eip55_checksum, Address, AddressError};
+use crate::amount::AmountError;
+use crate::encoding::{EncodingError, HexBytes};
+use crate::evm::fee::TOKEN_TRANSFER_GAS_LIMIT;
+use crate::{Blockchain, Coin, EthContractRequest, SendTokenRequest};
+
+// first 4 bytes of keccak256("transfer(address,uint256)")
+pub const TRANSFER_SELECTOR: [u8; 4] = [0xa9, 0x05, 0x9c, 0xbb];
+// first 4 bytes of keccak256("approve(address,uint256)")
+pub const APPROVE_SELECTOR: [u8; 4] = [0x09, 0x5e, 0xa7, 0xb3];
+// first 4 bytes of keccak256("transferFrom(address,address,uint256)")
+pub const TRANSFER_FROM_SELECTOR: [u8; 4] = [0x23, 0xb8, 0x72, 0xdd];
+// ABI encodes every static argument in a 32 bytes word
+const WORD: usize = 32;
+// addresses are left padded with 12 zero bytes
+const ADDRESS_PADDING: usize = WORD - 20;
+// decimals of an ERC-20 token is a uint8
+const MAX_DECIMALS: i32 = u8::MAX as i32;
+
+// Reason why ERC-20 call data cannot be encoded or decoded
+#[derive(Clone, PartialEq, Debug)]
+pub enum Erc20Error {
+    // call data does not start with the selector of transfer, approve or transferFrom
+    UnknownSelector {
+        data: String,
+    },
+    // arguments have a different length than the static arguments of the call
+    InvalidLength {
+        expected: usize,
+        actual: usize,
+    },
+    // address argument is not left padded with zeros
+    InvalidAddressArgument {
+        argument: &'static str,
+    },
+    // amount is negative or does not fit in uint256
+    AmountTooLarge {
+        amount: BigInt,
+    },
+    // token decimals must be 0 to 255
+    InvalidDecimals {
+        decimals: i32,
+    },
+    InvalidAmount(AmountError),
+    InvalidAddress {
+        field: &'static str,
+        error: AddressError,
+    },
+    // smart contract data is not hex
+    InvalidData(EncodingError),
+    // smart contract call is not a token transfer without native amount
+    NotTransfer,
+}
+
+impl fmt::Display for Erc20Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Erc20Error::UnknownSelector { data } => {
+                write!(
+                    f,
+                    "{} is not an ERC-20 transfer, approve or transferFrom",
+                    data
+                )
+            }
+            Erc20Error::InvalidLength { expected, actual } => write!(
+                f,
+                "arguments must be {} bytes but got {} bytes",
+                expected, actual
+            ),
+            Erc20Error::InvalidAddressArgument { argument } => {
+                write!(f, "{} is not an ABI encoded address", argument)
+            }
+            Erc20Error::AmountTooLarge { amount } => {
+                write!(f, "amount {} is not a uint256", amount)
+            }
+            Erc20Error::InvalidDecimals { decimals } => {
+                write!(f, "decimals {} must be between 0 and 255", decimals)
+            }
+            Erc20Error::InvalidAmount(error) => write!(f, "{}", error),
+            Erc20Error::InvalidAddress { field, error } => write!(f, "{}: {}", field, error),
+            Erc20Error::InvalidData(error) => write!(f, "data: {}", error),
+            Erc20Error::NotTransfer => write!(f, "smart contract call is not a token transfer"),
+        }
+    }
+}
+
+impl std::error::Error for Erc20Error {}
+
+impl From<AmountError> for Erc20Error {
+    fn from(error: AmountError) -> Self {
+        Erc20Error::InvalidAmount(error)
+    }
+}
+
+// An ERC-20 call. Amounts are in base units of the token
+#[derive(Clone, PartialEq, Debug)]
+pub enum Erc20Call {
+    Transfer {
+        to: [u8; 20],
+        amount: BigInt,
+    },
+    Approve {
+        spender: [u8; 20],
+        amount: BigInt,
+    },
+    TransferFrom {
+        from: [u8; 20],
+        to: [u8; 20],
+        amount: BigInt,
+    },
+}
+
+impl Erc20Call {
+    // fails unless the call data is exactly one of the calls with its static arguments
+    pub fn decode(data: &[u8]) -> Result<Erc20Call, Erc20Error> {
+        let unknown = || Erc20Error::UnknownSelector {
+            data: hex::encode(data),
+        };
+        if data.len() < TRANSFER_SELECTOR.len() {
+            return Err(unknown());
+        }
+        let (selector, arguments) = data.split_at(TRANSFER_SELECTOR.len());
+        let words = match selector {
+            selector if selector == TRANSFER_SELECTOR || selector == APPROVE_SELECTOR => 2,
+            selector if selector == TRANSFER_FROM_SELECTOR => 3,
+            _ => return Err(unknown()),
+        };
+        if arguments.len() != words * WORD {
+            return Err(Erc20Error::InvalidLength {
+                expected: words * WORD,
+                actual: arguments.len(),
+            });
+        }
+        let word = |index: usize| &arguments[index * WORD..(index + 1) * WORD];
+        let amount = BigInt::from_bytes_be(Sign::Plus, word(words - 1));
+        if selector == TRANSFER_FROM_SELECTOR {
+            return Ok(Erc20Call::TransferFrom {
+                from: decode_address(word(0), "from")?,
+                to: decode_address(word(1), "to")?,
+                amount,
+            });
+        }
+        if selector == APPROVE_SELECTOR {
+            return Ok(Erc20Call::Approve {
+                spender: decode_address(word(0), "spender")?,
+                amount,
+            });
+        }
+        Ok(Erc20Call::Transfer {
+            to: decode_address(word(0), "to")?,
+            amount,
+        })
+    }
+
+    // selector followed by the ABI encoded arguments
+    pub fn encode(&self) -> Result<Vec<u8>, Erc20Error> {
+        let mut data = self.selector().to_vec();
+        match self {
+            Erc20Call::Transfer { to, .. } => encode_address(&mut data, to),
+            Erc20Call::Approve { spender, .. } => encode_address(&mut data, spender),
+            Erc20Call::TransferFrom { from, to, .. } => {
+                encode_address(&mut data, from);
+                encode_address(&mut data, to);
+            }
+        }
+        data.extend_from_slice(&uint256(self.amount())?);
+        Ok(data)
+    }
+
+    pub fn selector(&self) -> [u8; 4] {
+        match self {
+            Erc20Call::Transfer { .. } => TRANSFER_SELECTOR,
+            Erc20Call::Approve { .. } => APPROVE_SELECTOR,
+            Erc20Call::TransferFrom { .. } => TRANSFER_FROM_SELECTOR,
+        }
+    }
+
+    pub fn amount(&self) -> &BigInt {
+        match self {
+            Erc20Call::Transfer { amount, .. }
+            | Erc20Call::Approve { amount, .. }
+            | Erc20Call::TransferFrom { amount, .. } => amount,
+        }
+    }
+}
+
+impl SendTokenRequest {
+    // amount x 10^decimals. Fails when the amount has more decimal places than the token or
+    // does not fit in uint256
+    pub fn base_units(&self, coin: Coin) -> Result<BigInt, Erc20Error> {
+        if self.decimals > MAX_DECIMALS {
+            return Err(Erc20Error::InvalidDecimals {
+                decimals: self.decimals,
+            });
+        }
+        let amount = self.coin_amount(coin)?.base_units().clone();
+        uint256(&amount)?;
+        Ok(amount)
+    }
+
+    // transfer of the amount to toAddress
+    pub fn transfer_call(
+        &self,
+        blockchain: &Blockchain,
+        coin: Coin,
+    ) -> Result<Erc20Call, Erc20Error> {
+        Ok(Erc20Call::Transfer {
+            to: account(blockchain, "sendTokenRequest.toAddress", &self.to_address)?,
+            amount: self.base_units(coin)?,
+        })
+    }
+
+    // The same transfer as a call to the token contract without native amount
+    pub fn to_contract_request(
+        &self,
+        blockchain: &Blockchain,
+        coin: Coin,
+    ) -> Result<EthContractRequest, Erc20Error> {
+        let data = self.transfer_call(blockchain, coin)?.encode()?;
+        let contract = account(
+            blockchain,
+            "sendTokenRequest.tokenContractAddress",
+            &self.token_contract_address,
+        )?;
+        Ok(EthContractRequest {
+            to_address: eip55_checksum(&contract),
+            amount: BigDecimal::zero(),
+            gas_limit: BigDecimal::from(TOKEN_TRANSFER_GAS_LIMIT),
+            data: HexBytes::from_bytes(&data).into(),
+        })
+    }
+
+    // Token send of a smart contract call that transfers a token with the given decimals
+    pub fn from_contract_request(
+        contract: &EthContractRequest,
+        decimals: i32,
+    ) -> Result<SendTokenRequest, Erc20Error> {
+        if !(0..=MAX_DECIMALS).contains(&decimals) {
+            return Err(Erc20Error::InvalidDecimals { decimals });
+        }
+        if !contract.amount.is_zero() {
+            return Err(Erc20Error::NotTransfer);
+        }
+        let data = HexBytes::try_from(contract.data.trim())
+            .map_err(Erc20Error::InvalidData)?
+            .to_bytes();
+        match Erc20Call::decode(&data)? {
+            Erc20Call::Transfer { to, amount } => Ok(SendTokenRequest {
+                to_address: eip55_checksum(&to),
+                token_contract_address: contract.to_address.clone(),
+                amount: BigDecimal::new(amount, decimals as i64).normalized(),
+                decimals,
+            }),
+            _ => Err(Erc20Error::NotTransfer),
+        }
+    }
+}
+
+// 20 bytes account of an EVM address
+fn account(
+    blockchain: &Blockchain,
+    field: &'static str,
+    address: &str,
+) -> Result<[u8; 20], Erc20Error> {
+    let address = Address::parse(blockchain, address)
+        .map_err(|error| Erc20Error::InvalidAddress { field, error })?;
+    let mut account = [0u8; 20];
+    account.copy_from_slice(address.payload());
+    Ok(account)
+}
+
+fn decode_address(word: &[u8], argument: &'static str) -> Result<[u8; 20], Erc20Error> {
+    if word[..ADDRESS_PADDING].iter().any(|byte| *byte != 0) {
+        return Err(Erc20Error::InvalidAddressArgument { argument });
+    }
+    let mut address = [0u8; 20];
+    address.copy_from_slice(&word[ADDRESS_PADDING..]);
+    Ok(address)
+}
+
+fn encode_address(data: &mut Vec<u8>, address: &[u8; 20]) {
+    data.extend_from_slice(&[0u8; ADDRESS_PADDING]);
+    data.extend_from_slice(address);
+}
+
+// big endian 32 bytes word of a non negative amount
+fn uint256(amount: &BigInt) -> Result<[u8; WORD], Erc20Error> {
+    let (sign, bytes) = amount.to_bytes_be();
+    if sign == Sign::Minus || bytes.len() > WORD {
+        return Err(Erc20Error::AmountTooLarge {
+            amount: amount.clone(),
+        });
+    }
+    let mut word = [0u8; WORD];
+    word[WORD - bytes.len()..].copy_from_slice(&bytes);
+    Ok(word)
+}
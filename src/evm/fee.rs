@@ -0,0 +1,302 @@
+use std::fmt;
+
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, Signed, ToPrimitive, Zero};
+
+use crate::amount::{digit_count, display_amount, pow10, strip_trailing_zeros, whole_number};
+use crate::fee::FeeLevelMultipliers;
+use crate::payload::TransactionPayload;
+use crate::{
+    Blockchain, Coin, EstimateFeeResult, FeeLevel, RequestParamsEthEip1559, RequestParamsEthLegacy,
+};
+
+// digits of u128::MAX
+const U128_DIGITS: i64 = 39;
+
+// gas used by a transfer of the native coin
+pub const SEND_GAS_LIMIT: u64 = 21_000;
+// gas limit of an ERC-20 transfer. Most tokens use about 50000, a cold recipient balance costs more
+pub const TOKEN_TRANSFER_GAS_LIMIT: u64 = 65_000;
+
+// Reason why an EVM fee cannot be calculated
+#[derive(Clone, PartialEq, Debug)]
+pub enum EvmFeeError {
+    // gas prices must be a non negative whole number of wei that fits in 128 bits
+    InvalidGasPrice {
+        field: &'static str,
+        value: BigDecimal,
+    },
+    // multipliers must not be negative
+    InvalidMultiplier {
+        value: BigDecimal,
+    },
+    // gas limit must be a whole number that fits in 64 bits
+    InvalidGasLimit {
+        gas_limit: BigDecimal,
+    },
+    // fee does not fit in 128 bits of wei
+    Overflow,
+}
+
+impl fmt::Display for EvmFeeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvmFeeError::InvalidGasPrice { field, value } => {
+                write!(
+                    f,
+                    "{} {} is not a valid amount of wei",
+                    field,
+                    display_amount(value)
+                )
+            }
+            EvmFeeError::InvalidMultiplier { value } => {
+                write!(
+                    f,
+                    "multiplier {} must not be negative",
+                    display_amount(value)
+                )
+            }
+            EvmFeeError::InvalidGasLimit { gas_limit } => {
+                write!(
+                    f,
+                    "gas limit {} is not a valid amount of gas",
+                    display_amount(gas_limit)
+                )
+            }
+            EvmFeeError::Overflow => write!(f, "fee is too large"),
+        }
+    }
+}
+
+impl std::error::Error for EvmFeeError {}
+
+// How gas prices from the network are raised for each fee level
+#[derive(Clone, PartialEq, Debug)]
+pub struct EvmFeeConfig {
+    // Headroom over the base fee in maxFeePerGas. The base fee rises by at most 12.5% per block
+    // so 2 keeps the transaction valid through about 6 full blocks.
+    pub base_fee_headroom: FeeLevelMultipliers,
+    // multiplier of the priority fee in maxPriorityFeePerGas
+    pub priority_fee: FeeLevelMultipliers,
+    // multiplier of the legacy gas price
+    pub gas_price: FeeLevelMultipliers,
+}
+
+impl Default for EvmFeeConfig {
+    fn default() -> Self {
+        EvmFeeConfig {
+            base_fee_headroom: FeeLevelMultipliers::thousandths(1125, 1500, 2000),
+            priority_fee: FeeLevelMultipliers::thousandths(1000, 1250, 2000),
+            gas_price: FeeLevelMultipliers::thousandths(1000, 1100, 1250),
+        }
+    }
+}
+
+// EIP-1559 fee per gas in wei
+#[derive(Clone, PartialEq, Debug)]
+pub struct Eip1559Fee {
+    pub max_fee_per_gas: u128,
+    pub max_priority_fee_per_gas: u128,
+}
+
+impl Eip1559Fee {
+    // most the transaction can cost in wei when it uses all of the gas limit
+    pub fn max_cost(&self, gas_limit: u64) -> Result<u128, EvmFeeError> {
+        self.max_fee_per_gas
+            .checked_mul(gas_limit as u128)
+            .ok_or(EvmFeeError::Overflow)
+    }
+}
+
+impl RequestParamsEthEip1559 {
+    // maxPriorityFeePerGas = priorityFee x multiplier and
+    // maxFeePerGas = baseGasFee x headroom + maxPriorityFeePerGas, both rounded up to whole wei
+    pub fn fee_for_level(
+        &self,
+        fee_level: &FeeLevel,
+        config: &EvmFeeConfig,
+    ) -> Result<Eip1559Fee, EvmFeeError> {
+        let base_fee = wei("baseGasFee", &self.base_gas_fee)?;
+        let priority_fee = wei("priorityFee", &self.priority_fee)?;
+        let max_priority_fee_per_gas =
+            multiply(priority_fee, config.priority_fee.for_level(fee_level))?;
+        let max_fee_per_gas = multiply(base_fee, config.base_fee_headroom.for_level(fee_level))?
+            .checked_add(max_priority_fee_per_gas)
+            .ok_or(EvmFeeError::Overflow)?;
+        Ok(Eip1559Fee {
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+        })
+    }
+
+    // worst case fee of each level in native coin unit for a transaction using up to gas_limit
+    pub fn estimate_fee(
+        &self,
+        blockchain: &Blockchain,
+        coin: Coin,
+        gas_limit: u64,
+        config: &EvmFeeConfig,
+    ) -> Result<EstimateFeeResult, EvmFeeError> {
+        estimate_fee_result(blockchain, coin, |fee_level| {
+            self.fee_for_level(fee_level, config)?.max_cost(gas_limit)
+        })
+    }
+}
+
+impl RequestParamsEthLegacy {
+    // gas price in wei = gasFee x multiplier rounded up to whole wei
+    pub fn gas_price_for_level(
+        &self,
+        fee_level: &FeeLevel,
+        config: &EvmFeeConfig,
+    ) -> Result<u128, EvmFeeError> {
+        let gas_price = wei("gasFee", &self.gas_fee)?;
+        multiply(gas_price, config.gas_price.for_level(fee_level))
+    }
+
+    // most the transaction can cost in wei when it uses all of the gas limit
+    pub fn max_cost(
+        &self,
+        fee_level: &FeeLevel,
+        gas_limit: u64,
+        config: &EvmFeeConfig,
+    ) -> Result<u128, EvmFeeError> {
+        self.gas_price_for_level(fee_level, config)?
+            .checked_mul(gas_limit as u128)
+            .ok_or(EvmFeeError::Overflow)
+    }
+
+    // worst case fee of each level in native coin unit for a transaction using up to gas_limit
+    pub fn estimate_fee(
+        &self,
+        blockchain: &Blockchain,
+        coin: Coin,
+        gas_limit: u64,
+        config: &EvmFeeConfig,
+    ) -> Result<EstimateFeeResult, EvmFeeError> {
+        estimate_fee_result(blockchain, coin, |fee_level| {
+            self.max_cost(fee_level, gas_limit, config)
+        })
+    }
+}
+
+// gas limit of the transaction. Smart contract calls use the gas limit of the request.
+// Messages are signed off chain and use no gas.
+pub fn gas_limit(payload: &TransactionPayload) -> Result<u64, EvmFeeError> {
+    match payload {
+        TransactionPayload::Send(_) => Ok(SEND_GAS_LIMIT),
+        TransactionPayload::SendToken(_) => Ok(TOKEN_TRANSFER_GAS_LIMIT),
+        TransactionPayload::PersonalSign(_) | TransactionPayload::EthSignTypedData(_) => Ok(0),
+        TransactionPayload::EthContractCall(contract) => whole_number(&contract.gas_limit)
+            .and_then(|gas_limit| gas_limit.to_u64())
+            .ok_or_else(|| EvmFeeError::InvalidGasLimit {
+                gas_limit: contract.gas_limit.clone(),
+            }),
+    }
+}
+
+// amount of wei in native coin unit e.g. ETH
+pub fn wei_to_coin_unit(blockchain: &Blockchain, wei: u128) -> BigDecimal {
+    BigDecimal::new(BigInt::from(wei), blockchain.native_decimals() as i64)
+}
+
+fn estimate_fee_result<F>(
+    blockchain: &Blockchain,
+    coin: Coin,
+    cost: F,
+) -> Result<EstimateFeeResult, EvmFeeError>
+where
+    F: Fn(&FeeLevel) -> Result<u128, EvmFeeError>,
+{
+    Ok(EstimateFeeResult {
+        blockchain: blockchain.clone(),
+        coin,
+        low_estimated_fee: wei_to_coin_unit(blockchain, cost(&FeeLevel::LOW)?),
+        medium_estimated_fee: wei_to_coin_unit(blockchain, cost(&FeeLevel::MEDIUM)?),
+        high_estimated_fee: wei_to_coin_unit(blockchain, cost(&FeeLevel::HIGH)?),
+    })
+}
+
+fn wei(field: &'static str, value: &BigDecimal) -> Result<u128, EvmFeeError> {
+    let invalid = || EvmFeeError::InvalidGasPrice {
+        field,
+        value: value.clone(),
+    };
+    whole_number(value)
+        .and_then(|wei| wei.to_u128())
+        .ok_or_else(invalid)
+}
+
+// value x multiplier rounded up to a whole number
+fn multiply(value: u128, multiplier: &BigDecimal) -> Result<u128, EvmFeeError> {
+    if multiplier.is_negative() {
+        return Err(EvmFeeError::InvalidMultiplier {
+            value: multiplier.clone(),
+        });
+    }
+    // integer arithmetic on the digits as the exponent of the multiplier is not bounded
+    let (digits, scale) = strip_trailing_zeros(multiplier);
+    let product = BigInt::from(value) * digits;
+    if product.is_zero() {
+        return Ok(0);
+    }
+    let rounded = if scale <= 0 {
+        let shift = u32::try_from(scale.unsigned_abs())
+            .ok()
+            .filter(|shift| digit_count(&product) + (*shift as i64) <= U128_DIGITS)
+            .ok_or(EvmFeeError::Overflow)?;
+        product * pow10(shift)
+    } else if scale > digit_count(&product) {
+        // a positive fraction of one wei
+        BigInt::from(1)
+    } else {
+        let divisor = pow10(scale as u32);
+        let quotient = &product / &divisor;
+        if (&product % &divisor).is_zero() {
+            quotient
+        } else {
+            quotient + 1
+        }
+    };
+    rounded.to_u128().ok_or(EvmFeeError::Overflow)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn legacy(gas_fee: &str) -> RequestParamsEthLegacy {
+        RequestParamsEthLegacy {
+            gas_fee: BigDecimal::from_str(gas_fee).unwrap(),
+            chain_id: "1".to_string(),
+            nonce: 0,
+        }
+    }
+
+    #[test]
+    fn gas_price_is_rounded_up_to_whole_wei() {
+        let config = EvmFeeConfig::default();
+        assert_eq!(
+            legacy("2E10").gas_price_for_level(&FeeLevel::MEDIUM, &config),
+            Ok(22_000_000_000)
+        );
+        assert_eq!(
+            legacy("12345").gas_price_for_level(&FeeLevel::MEDIUM, &config),
+            Ok(13_580)
+        );
+    }
+
+    // the exponent is checked before the value is rescaled
+    #[test]
+    fn gas_price_with_huge_exponent_is_invalid() {
+        let config = EvmFeeConfig::default();
+        for gas_fee in ["1E+100000000000", "1E-100000000000"] {
+            assert!(matches!(
+                legacy(gas_fee).gas_price_for_level(&FeeLevel::MEDIUM, &config),
+                Err(EvmFeeError::InvalidGasPrice { .. })
+            ));
+        }
+    }
+}
@@ -0,0 +1,449 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use bigdecimal::num_bigint::{BigInt, Sign};
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use sha3::{Digest, Keccak256};
+
+use crate::address::{eip55_checksum, Address, AddressError};
+use crate::derivation::evm_account;
+use crate::encoding::{Hash32Hex, HexBytes};
+use crate::evm::abi::AbiType;
+use crate::payload::TransactionPayload;
+use crate::signature::SignatureError;
+use crate::validation::ValidationError;
+use crate::verification::SignatureFailure;
+use crate::{
+    Blockchain, EthTypedDataRequest, PersonalSignRequest, RequestTransactionType,
+    SignatureRecidHex, SigningRequest,
+};
+
+// EIP-191 version 0x45 prefix, followed by the decimal length of the message
+const PERSONAL_MESSAGE_PREFIX: &[u8] = b"\x19Ethereum Signed Message:\n";
+// EIP-191 version 0x01 prefix of EIP-712 structured data
+const TYPED_DATA_PREFIX: [u8; 2] = [0x19, 0x01];
+const DOMAIN_TYPE: &str = "EIP712Domain";
+// EIP712Domain fields in the order of EIP-712, used when the typed data does not declare them
+const DOMAIN_FIELDS: [(&str, &str); 5] = [
+    ("name", "string"),
+    ("version", "string"),
+    ("chainId", "uint256"),
+    ("verifyingContract", "address"),
+    ("salt", "bytes32"),
+];
+// every value is encoded in a 32 bytes word
+const WORD: usize = 32;
+
+// Reason why a message cannot be hashed or its signature does not come from the wallet
+#[derive(Clone, PartialEq, Debug)]
+pub enum MessageError {
+    // request does not sign a message
+    UnsupportedTransactionType {
+        transaction_type: RequestTransactionType,
+    },
+    InvalidRequest(ValidationError),
+    // typed data is not JSON with types, primaryType, domain and message
+    InvalidTypedData {
+        error: String,
+    },
+    // type is neither declared in types nor a Solidity type
+    UnknownType {
+        type_name: String,
+    },
+    // struct value has no value for a field of its type
+    MissingField {
+        type_name: String,
+        field: String,
+    },
+    // value cannot be encoded as its type e.g. a number that does not fit in uint8
+    InvalidValue {
+        type_name: String,
+        value: String,
+    },
+    // fromAddress is not an address of the blockchain
+    InvalidAddress {
+        field: &'static str,
+        error: AddressError,
+    },
+    // signature cannot be used to recover a signer
+    InvalidSignature(SignatureFailure),
+    // signature recovers another account than fromAddress
+    SignerMismatch {
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for MessageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MessageError::UnsupportedTransactionType { transaction_type } => {
+                write!(f, "{:?} does not sign a message", transaction_type)
+            }
+            MessageError::InvalidRequest(error) => write!(f, "{}", error),
+            MessageError::InvalidTypedData { error } => {
+                write!(f, "invalid typed data: {}", error)
+            }
+            MessageError::UnknownType { type_name } => {
+                write!(f, "type {} is not declared", type_name)
+            }
+            MessageError::MissingField { type_name, field } => {
+                write!(f, "{} value has no field {}", type_name, field)
+            }
+            MessageError::InvalidValue { type_name, value } => {
+                write!(f, "{} is not a valid {} value", value, type_name)
+            }
+            MessageError::InvalidAddress { field, error } => write!(f, "{}: {}", field, error),
+            MessageError::InvalidSignature(failure) => write!(f, "{}", failure),
+            MessageError::SignerMismatch { expected, actual } => write!(
+                f,
+                "message is signed by {} but must be signed by {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MessageError {}
+
+impl From<ValidationError> for MessageError {
+    fn from(error: ValidationError) -> Self {
+        MessageError::InvalidRequest(error)
+    }
+}
+
+// keccak256 of the EIP-191 personal message: prefix, decimal length and the message
+pub fn personal_message_hash(message: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(PERSONAL_MESSAGE_PREFIX);
+    hasher.update(message.len().to_string().as_bytes());
+    hasher.update(message);
+    hasher.finalize().into()
+}
+
+impl PersonalSignRequest {
+    // Bytes that are signed. dApps send 0x prefixed hex of the message like personal_sign does.
+    // A message that only starts with 0x e.g. 0xhello is signed as text, as wallets do.
+    pub fn message_bytes(&self) -> Vec<u8> {
+        if self.message.starts_with("0x") || self.message.starts_with("0X") {
+            if let Ok(bytes) = HexBytes::try_from(self.message.as_str()) {
+                return bytes.to_bytes();
+            }
+        }
+        self.message.as_bytes().to_vec()
+    }
+
+    pub fn hash(&self) -> Hash32Hex {
+        Hash32Hex::from_bytes(personal_message_hash(&self.message_bytes()))
+    }
+}
+
+impl EthTypedDataRequest {
+    pub fn hash(&self) -> Result<Hash32Hex, MessageError> {
+        TypedData::from_json(&self.typed_data)?
+            .hash()
+            .map(Hash32Hex::from_bytes)
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct TypedDataField {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+// EIP-712 typed data as sent to eth_signTypedData_v4
+#[derive(Clone, PartialEq, Debug, Deserialize)]
+pub struct TypedData {
+    pub types: BTreeMap<String, Vec<TypedDataField>>,
+    #[serde(rename = "primaryType")]
+    pub primary_type: String,
+    #[serde(default)]
+    pub domain: Map<String, Value>,
+    #[serde(default)]
+    pub message: Map<String, Value>,
+}
+
+impl TypedData {
+    pub fn from_json(json: &str) -> Result<TypedData, MessageError> {
+        serde_json::from_str(json).map_err(|error| MessageError::InvalidTypedData {
+            error: error.to_string(),
+        })
+    }
+
+    // keccak256 of 0x19 0x01, the domain separator and the hash of the message.
+    // The message is left out when the primary type is EIP712Domain.
+    pub fn hash(&self) -> Result<[u8; 32], MessageError> {
+        let mut hasher = Keccak256::new();
+        hasher.update(TYPED_DATA_PREFIX);
+        hasher.update(self.domain_separator()?);
+        if self.primary_type != DOMAIN_TYPE {
+            hasher.update(self.hash_struct(&self.primary_type, &self.message)?);
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    pub fn domain_separator(&self) -> Result<[u8; 32], MessageError> {
+        self.hash_struct(DOMAIN_TYPE, &self.domain)
+    }
+
+    // keccak256 of the type hash followed by the encoded fields
+    pub fn hash_struct(
+        &self,
+        type_name: &str,
+        value: &Map<String, Value>,
+    ) -> Result<[u8; 32], MessageError> {
+        let fields = self.fields(type_name).ok_or_else(|| unknown(type_name))?;
+        let mut hasher = Keccak256::new();
+        hasher.update(Keccak256::digest(self.encode_type(type_name)?.as_bytes()));
+        for field in &fields {
+            let field_value = value
+                .get(&field.name)
+                .ok_or_else(|| MessageError::MissingField {
+                    type_name: type_name.to_string(),
+                    field: field.name.clone(),
+                })?;
+            hasher.update(self.encode_value(&field.kind, field_value)?);
+        }
+        Ok(hasher.finalize().into())
+    }
+
+    // Name(type name,...) of the type followed by the referenced struct types sorted by name
+    pub fn encode_type(&self, type_name: &str) -> Result<String, MessageError> {
+        let mut referenced = BTreeSet::new();
+        self.collect_references(type_name, &mut referenced)?;
+        referenced.remove(type_name);
+        let mut encoded = String::new();
+        for name in std::iter::once(type_name).chain(referenced.iter().map(String::as_str)) {
+            let fields = self.fields(name).ok_or_else(|| unknown(name))?;
+            let members: Vec<String> = fields
+                .iter()
+                .map(|field| format!("{} {}", field.kind, field.name))
+                .collect();
+            encoded.push_str(&format!("{}({})", name, members.join(",")));
+        }
+        Ok(encoded)
+    }
+
+    // declared fields of a struct type. EIP712Domain defaults to the standard fields in domain
+    fn fields(&self, type_name: &str) -> Option<Vec<TypedDataField>> {
+        if let Some(fields) = self.types.get(type_name) {
+            return Some(fields.clone());
+        }
+        if type_name != DOMAIN_TYPE {
+            return None;
+        }
+        Some(
+            DOMAIN_FIELDS
+                .iter()
+                .filter(|(name, _)| self.domain.contains_key(*name))
+                .map(|(name, kind)| TypedDataField {
+                    name: name.to_string(),
+                    kind: kind.to_string(),
+                })
+                .collect(),
+        )
+    }
+
+    fn collect_references(
+        &self,
+        type_name: &str,
+        referenced: &mut BTreeSet<String>,
+    ) -> Result<(), MessageError> {
+        let base = base_type(type_name);
+        if referenced.contains(base) {
+            return Ok(());
+        }
+        if let Some(fields) = self.fields(base) {
+            referenced.insert(base.to_string());
+            for field in &fields {
+                self.collect_references(&field.kind, referenced)?;
+            }
+        }
+        Ok(())
+    }
+
+    // 32 bytes of a value: structs, arrays, strings and bytes are hashed, atomic values are
+    // padded to a word like the ABI does
+    fn encode_value(&self, type_name: &str, value: &Value) -> Result<[u8; WORD], MessageError> {
+        let invalid = || MessageError::InvalidValue {
+            type_name: type_name.to_string(),
+            value: value.to_string(),
+        };
+        if let Some(open) = type_name.strip_suffix(']').and_then(|name| name.rfind('[')) {
+            let item_type = &type_name[..open];
+            let len = &type_name[open + 1..type_name.len() - 1];
+            let items = value.as_array().ok_or_else(invalid)?;
+            if !len.is_empty() && len.parse::<usize>().ok() != Some(items.len()) {
+                return Err(invalid());
+            }
+            let mut hasher = Keccak256::new();
+            for item in items {
+                hasher.update(self.encode_value(item_type, item)?);
+            }
+            return Ok(hasher.finalize().into());
+        }
+        if self.fields(type_name).is_some() {
+            let value = value.as_object().ok_or_else(invalid)?;
+            return self.hash_struct(type_name, value);
+        }
+        let kind = AbiType::parse(type_name).map_err(|_| unknown(type_name))?;
+        let mut word = [0u8; WORD];
+        match kind {
+            AbiType::Address => {
+                let address = value
+                    .as_str()
+                    .and_then(|address| Address::parse(&Blockchain::ETHEREUM, address).ok())
+                    .ok_or_else(invalid)?;
+                word[WORD - 20..].copy_from_slice(address.payload());
+            }
+            AbiType::Bool => {
+                word[WORD - 1] = value.as_bool().ok_or_else(invalid)? as u8;
+            }
+            AbiType::Uint(bits) => {
+                let number = integer(value).ok_or_else(invalid)?;
+                if number.sign() == Sign::Minus || number.bits() > bits as u64 {
+                    return Err(invalid());
+                }
+                word = int256(&number);
+            }
+            AbiType::Int(bits) => {
+                let number = integer(value).ok_or_else(invalid)?;
+                let limit = BigInt::from(1) << (bits - 1);
+                if number >= limit || number < -limit {
+                    return Err(invalid());
+                }
+                word = int256(&number);
+            }
+            AbiType::FixedBytes(len) => {
+                let bytes = hex_value(value).ok_or_else(invalid)?;
+                if bytes.len() > len {
+                    return Err(invalid());
+                }
+                word[..bytes.len()].copy_from_slice(&bytes);
+            }
+            AbiType::Bytes => {
+                word = Keccak256::digest(hex_value(value).ok_or_else(invalid)?).into();
+            }
+            AbiType::String => {
+                word = Keccak256::digest(value.as_str().ok_or_else(invalid)?.as_bytes()).into();
+            }
+            AbiType::Array(_) | AbiType::FixedArray(..) | AbiType::Tuple(_) => {
+                return Err(unknown(type_name))
+            }
+        }
+        Ok(word)
+    }
+}
+
+// EIP-55 address of the account whose key made the signature of the hash.
+// High s is accepted as the signature is normalized first.
+pub fn recover_signer(
+    hash: &Hash32Hex,
+    signature: &SignatureRecidHex,
+) -> Result<String, SignatureFailure> {
+    let normalized = signature
+        .normalize_s()
+        .map_err(SignatureFailure::Malformed)?;
+    let recid = normalized
+        .recovery_id()
+        .map_err(SignatureFailure::Malformed)?;
+    let ecdsa = Signature::from_scalars(normalized.r.to_array(), normalized.s.to_array())
+        .map_err(|_| SignatureFailure::Malformed(SignatureError::InvalidScalar))?;
+    let key = RecoveryId::from_byte(recid)
+        .and_then(|recovery_id| {
+            VerifyingKey::recover_from_prehash(&hash.to_array(), &ecdsa, recovery_id).ok()
+        })
+        .ok_or(SignatureFailure::Invalid)?;
+    let point = key.to_encoded_point(false);
+    Ok(eip55_checksum(&evm_account(point.as_bytes())))
+}
+
+impl SigningRequest {
+    // Digest of the message that is placed in SigningHash.hash.
+    // PERSONAL_SIGN: EIP-191 personal message hash
+    // ETH_SIGN_TYPED_DATA: EIP-712 hash of the typed data
+    pub fn message_hash(&self) -> Result<Hash32Hex, MessageError> {
+        match self.payload()? {
+            TransactionPayload::PersonalSign(message) => Ok(message.hash()),
+            TransactionPayload::EthSignTypedData(typed_data) => typed_data.hash(),
+            payload => Err(MessageError::UnsupportedTransactionType {
+                transaction_type: payload.transaction_type(),
+            }),
+        }
+    }
+
+    // Recover the signer of the message signature and check it is fromAddress.
+    // Returns the signer as an EIP-55 address.
+    pub fn verify_message_signature(
+        &self,
+        signature: &SignatureRecidHex,
+    ) -> Result<String, MessageError> {
+        let expected = Address::parse(&self.blockchain, &self.from_address).map_err(|error| {
+            MessageError::InvalidAddress {
+                field: "fromAddress",
+                error,
+            }
+        })?;
+        let actual = recover_signer(&self.message_hash()?, signature)
+            .map_err(MessageError::InvalidSignature)?;
+        if actual != expected.as_str() {
+            return Err(MessageError::SignerMismatch {
+                expected: expected.as_str().to_string(),
+                actual,
+            });
+        }
+        Ok(actual)
+    }
+}
+
+fn unknown(type_name: &str) -> MessageError {
+    MessageError::UnknownType {
+        type_name: type_name.to_string(),
+    }
+}
+
+// struct or atomic type of the items of an array type
+fn base_type(type_name: &str) -> &str {
+    type_name.split('[').next().unwrap_or(type_name)
+}
+
+// JSON number, decimal string or 0x prefixed hex string
+fn integer(value: &Value) -> Option<BigInt> {
+    match value {
+        Value::Number(number) => number
+            .as_u64()
+            .map(BigInt::from)
+            .or_else(|| number.as_i64().map(BigInt::from)),
+        Value::String(text) => {
+            let text = text.trim();
+            match text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+                Some(digits) => BigInt::parse_bytes(digits.as_bytes(), 16),
+                None => BigInt::parse_bytes(text.as_bytes(), 10),
+            }
+        }
+        _ => None,
+    }
+}
+
+// 0x prefixed hex string
+fn hex_value(value: &Value) -> Option<Vec<u8>> {
+    HexBytes::try_from(value.as_str()?)
+        .ok()
+        .map(|bytes| bytes.to_bytes())
+}
+
+// two's complement big endian word of a value that fits in 256 bits
+fn int256(number: &BigInt) -> [u8; WORD] {
+    // unsigned bytes of positive values as uint256 can use the sign bit
+    let (fill, bytes) = match number.sign() {
+        Sign::Minus => (0xff, number.to_signed_bytes_be()),
+        _ => (0, number.to_bytes_be().1),
+    };
+    let mut word = [fill; WORD];
+    word[WORD - bytes.len()..].copy_from_slice(&bytes);
+    word
+}
@@ -0,0 +1,6 @@
+pub mod abi;
+pub mod erc20;
+pub mod fee;
+pub mod message;
+pub mod rlp;
+pub mod transaction;
@@ -0,0 +1,205 @@
+use std::fmt;
+
+// single bytes below this value are their own encoding
+const SHORT_STRING: u8 = 0x80;
+const LONG_STRING: u8 = 0xb7;
+const SHORT_LIST: u8 = 0xc0;
+const LONG_LIST: u8 = 0xf7;
+// transactions nest a few levels, deeper input is rejected before it exhausts the stack
+const MAX_DEPTH: usize = 16;
+
+// Reason why bytes are not canonical RLP
+#[derive(Clone, PartialEq, Debug)]
+pub enum RlpError {
+    // input ended in the middle of an item
+    UnexpectedEnd,
+    // item is valid but not in its shortest encoding
+    NonCanonical,
+    // a byte string was expected but got a list or the reverse
+    UnexpectedList { expected_list: bool },
+    // integer has leading zeros or does not fit
+    InvalidInteger,
+    // lists are nested deeper than any transaction
+    TooDeep,
+    // bytes left after the item
+    TrailingBytes { position: usize },
+}
+
+impl fmt::Display for RlpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RlpError::UnexpectedEnd => write!(f, "unexpected end of RLP"),
+            RlpError::NonCanonical => write!(f, "RLP item is not canonical"),
+            RlpError::UnexpectedList { expected_list } => {
+                if *expected_list {
+                    write!(f, "expected RLP list but got bytes")
+                } else {
+                    write!(f, "expected RLP bytes but got a list")
+                }
+            }
+            RlpError::InvalidInteger => write!(f, "RLP integer is invalid"),
+            RlpError::TooDeep => write!(f, "RLP lists are nested too deep"),
+            RlpError::TrailingBytes { position } => {
+                write!(f, "unexpected bytes after RLP at {}", position)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RlpError {}
+
+// An RLP item: a byte string or a list of items
+#[derive(Clone, PartialEq, Debug)]
+pub enum Rlp {
+    Bytes(Vec<u8>),
+    List(Vec<Rlp>),
+}
+
+impl Rlp {
+    // big endian integer without leading zeros, zero is the empty string
+    pub fn from_u128(value: u128) -> Rlp {
+        let bytes = value.to_be_bytes();
+        let first = bytes
+            .iter()
+            .position(|byte| *byte != 0)
+            .unwrap_or(bytes.len());
+        Rlp::Bytes(bytes[first..].to_vec())
+    }
+
+    pub fn from_u64(value: u64) -> Rlp {
+        Rlp::from_u128(value as u128)
+    }
+
+    // Decode exactly one item
+    pub fn decode(bytes: &[u8]) -> Result<Rlp, RlpError> {
+        let (item, len) = decode_item(bytes, 0)?;
+        if len != bytes.len() {
+            return Err(RlpError::TrailingBytes { position: len });
+        }
+        Ok(item)
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes);
+        bytes
+    }
+
+    pub fn as_bytes(&self) -> Result<&[u8], RlpError> {
+        match self {
+            Rlp::Bytes(bytes) => Ok(bytes),
+            Rlp::List(_) => Err(RlpError::UnexpectedList {
+                expected_list: false,
+            }),
+        }
+    }
+
+    pub fn as_list(&self) -> Result<&[Rlp], RlpError> {
+        match self {
+            Rlp::List(items) => Ok(items),
+            Rlp::Bytes(_) => Err(RlpError::UnexpectedList {
+                expected_list: true,
+            }),
+        }
+    }
+
+    pub fn to_u128(&self) -> Result<u128, RlpError> {
+        let bytes = self.as_bytes()?;
+        if bytes.first() == Some(&0) || bytes.len() > 16 {
+            return Err(RlpError::InvalidInteger);
+        }
+        Ok(bytes
+            .iter()
+            .fold(0u128, |value, byte| (value << 8) | *byte as u128))
+    }
+
+    pub fn to_u64(&self) -> Result<u64, RlpError> {
+        u64::try_from(self.to_u128()?).map_err(|_| RlpError::InvalidInteger)
+    }
+
+    fn write(&self, bytes: &mut Vec<u8>) {
+        match self {
+            Rlp::Bytes(value) if value.len() == 1 && value[0] < SHORT_STRING => {
+                bytes.push(value[0])
+            }
+            Rlp::Bytes(value) => {
+                write_header(bytes, SHORT_STRING, LONG_STRING, value.len());
+                bytes.extend_from_slice(value);
+            }
+            Rlp::List(items) => {
+                let mut payload = Vec::new();
+                for item in items {
+                    item.write(&mut payload);
+                }
+                write_header(bytes, SHORT_LIST, LONG_LIST, payload.len());
+                bytes.extend_from_slice(&payload);
+            }
+        }
+    }
+}
+
+// length prefix: short form adds the length to the offset, long form adds the length of the
+// big endian length
+fn write_header(bytes: &mut Vec<u8>, short: u8, long: u8, len: usize) {
+    if len <= 55 {
+        bytes.push(short + len as u8);
+    } else {
+        let len_bytes = (len as u64).to_be_bytes();
+        let first = len_bytes.iter().position(|byte| *byte != 0).unwrap_or(7);
+        bytes.push(long + (8 - first) as u8);
+        bytes.extend_from_slice(&len_bytes[first..]);
+    }
+}
+
+// item at the start of bytes and the number of bytes it takes
+fn decode_item(bytes: &[u8], depth: usize) -> Result<(Rlp, usize), RlpError> {
+    let prefix = *bytes.first().ok_or(RlpError::UnexpectedEnd)?;
+    if prefix < SHORT_STRING {
+        return Ok((Rlp::Bytes(vec![prefix]), 1));
+    }
+    let is_list = prefix >= SHORT_LIST;
+    let (short, long) = if is_list {
+        (SHORT_LIST, LONG_LIST)
+    } else {
+        (SHORT_STRING, LONG_STRING)
+    };
+    let (offset, len) = if prefix <= long {
+        (1, (prefix - short) as usize)
+    } else {
+        let len_of_len = (prefix - long) as usize;
+        let len_bytes = bytes
+            .get(1..1 + len_of_len)
+            .ok_or(RlpError::UnexpectedEnd)?;
+        if len_bytes[0] == 0 || len_of_len > 8 {
+            return Err(RlpError::NonCanonical);
+        }
+        let len = len_bytes
+            .iter()
+            .fold(0u64, |value, byte| (value << 8) | *byte as u64);
+        if len <= 55 {
+            return Err(RlpError::NonCanonical);
+        }
+        let len = usize::try_from(len).map_err(|_| RlpError::UnexpectedEnd)?;
+        (1 + len_of_len, len)
+    };
+    let end = offset.checked_add(len).ok_or(RlpError::UnexpectedEnd)?;
+    let payload = bytes.get(offset..end).ok_or(RlpError::UnexpectedEnd)?;
+    if !is_list {
+        // single bytes below 0x80 must use their own encoding
+        if len == 1 && payload[0] < SHORT_STRING {
+            return Err(RlpError::NonCanonical);
+        }
+        return Ok((Rlp::Bytes(payload.to_vec()), end));
+    }
+    if depth == MAX_DEPTH {
+        return Err(RlpError::TooDeep);
+    }
+    let mut items = Vec::new();
+    let mut position = 0;
+    while position < payload.len() {
+        let (item, item_len) = decode_item(&payload[position..], depth + 1)?;
+        items.push(item);
+        position += item_len;
+    }
+    Ok((Rlp::List(items), end))
+}
@@ -0,0 +1,329 @@
+use std::fmt;
+
+use sha3::{Digest, Keccak256};
+
+use crate::evm::fee::Eip1559Fee;
+use crate::evm::rlp::{Rlp, RlpError};
+use crate::signature::SignatureError;
+use crate::SignatureRecidHex;
+
+// EIP-2718 type byte of EIP-1559 transactions
+pub const EIP1559_TRANSACTION_TYPE: u8 = 0x02;
+// typed transactions start with a type byte below the RLP list prefixes
+const MAX_TRANSACTION_TYPE: u8 = 0x7f;
+// v of signatures before EIP-155 is y parity + 27
+const LEGACY_V_OFFSET: u64 = 27;
+
+// Reason why bytes are not an EVM transaction
+#[derive(Clone, PartialEq, Debug)]
+pub enum EvmTransactionError {
+    Rlp(RlpError),
+    // EIP-2718 type other than legacy and EIP-1559
+    UnsupportedType {
+        transaction_type: u8,
+    },
+    // list does not have the fields of an unsigned or signed transaction
+    InvalidFieldCount {
+        actual: usize,
+    },
+    // a field has an invalid value
+    InvalidField {
+        field: &'static str,
+        error: RlpError,
+    },
+    // to is neither empty nor a 20 bytes account
+    InvalidRecipient {
+        len: usize,
+    },
+    // signature fields cannot form a signature
+    InvalidSignature(SignatureError),
+}
+
+impl fmt::Display for EvmTransactionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EvmTransactionError::Rlp(error) => write!(f, "{}", error),
+            EvmTransactionError::UnsupportedType { transaction_type } => {
+                write!(f, "transaction type {} is not supported", transaction_type)
+            }
+            EvmTransactionError::InvalidFieldCount { actual } => {
+                write!(f, "transaction has an invalid number of fields {}", actual)
+            }
+            EvmTransactionError::InvalidField { field, error } => {
+                write!(f, "{}: {}", field, error)
+            }
+            EvmTransactionError::InvalidRecipient { len } => {
+                write!(f, "to must be empty or 20 bytes but got {} bytes", len)
+            }
+            EvmTransactionError::InvalidSignature(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for EvmTransactionError {}
+
+impl From<RlpError> for EvmTransactionError {
+    fn from(error: RlpError) -> Self {
+        EvmTransactionError::Rlp(error)
+    }
+}
+
+impl From<SignatureError> for EvmTransactionError {
+    fn from(error: SignatureError) -> Self {
+        EvmTransactionError::InvalidSignature(error)
+    }
+}
+
+// Gas price of the transaction in wei
+#[derive(Clone, PartialEq, Debug)]
+pub enum GasPrice {
+    Legacy { gas_price: u128 },
+    Eip1559(Eip1559Fee),
+}
+
+// A legacy or EIP-1559 transaction, signed or not
+#[derive(Clone, PartialEq, Debug)]
+pub struct EvmTransaction {
+    // None for legacy transactions without EIP-155 replay protection
+    pub chain_id: Option<u64>,
+    pub nonce: u64,
+    pub gas_price: GasPrice,
+    pub gas_limit: u64,
+    // None for contract creation
+    pub to: Option<[u8; 20]>,
+    // amount in wei
+    pub value: u128,
+    pub data: Vec<u8>,
+    // EIP-2930 access list of EIP-1559 transactions as it is encoded
+    pub access_list: Vec<Rlp>,
+    pub signature: Option<SignatureRecidHex>,
+}
+
+impl EvmTransaction {
+    // Decode an RLP legacy transaction or an EIP-1559 typed transaction. Unsigned legacy
+    // transactions are the EIP-155 signing payload ending with chain id, 0, 0 or the pre EIP-155
+    // payload of 6 fields.
+    pub fn decode(bytes: &[u8]) -> Result<EvmTransaction, EvmTransactionError> {
+        match bytes.first() {
+            Some(&EIP1559_TRANSACTION_TYPE) => decode_eip1559(&Rlp::decode(&bytes[1..])?),
+            Some(&transaction_type) if transaction_type <= MAX_TRANSACTION_TYPE => {
+                Err(EvmTransactionError::UnsupportedType { transaction_type })
+            }
+            _ => decode_legacy(&Rlp::decode(bytes)?),
+        }
+    }
+
+    // highest gas price the transaction can pay in wei
+    pub fn max_gas_price(&self) -> u128 {
+        match &self.gas_price {
+            GasPrice::Legacy { gas_price } => *gas_price,
+            GasPrice::Eip1559(fee) => fee.max_fee_per_gas,
+        }
+    }
+
+    // highest fee the transaction can pay in wei, None on overflow
+    pub fn max_cost(&self) -> Option<u128> {
+        self.max_gas_price().checked_mul(self.gas_limit as u128)
+    }
+
+    // Bytes the sender signs: the unsigned transaction. Legacy transactions with a chain id
+    // use the EIP-155 payload, EIP-1559 transactions are prefixed with their type.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        let mut fields = self.unsigned_fields();
+        match self.gas_price {
+            GasPrice::Legacy { .. } => {
+                if let Some(chain_id) = self.chain_id {
+                    fields.extend([Rlp::from_u64(chain_id), Rlp::from_u64(0), Rlp::from_u64(0)]);
+                }
+                Rlp::List(fields).encode()
+            }
+            GasPrice::Eip1559(_) => {
+                let mut bytes = vec![EIP1559_TRANSACTION_TYPE];
+                bytes.extend(Rlp::List(fields).encode());
+                bytes
+            }
+        }
+    }
+
+    // keccak256 of the signing payload
+    pub fn signing_hash(&self) -> [u8; 32] {
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&Keccak256::digest(self.signing_payload()));
+        hash
+    }
+
+    // Signed transaction. Legacy transactions carry v of EIP-155 or 27 + y parity without a chain id,
+    // EIP-1559 transactions carry the y parity. s is normalized to the low half of the curve order.
+    pub fn encode_signed(&self, signature: &SignatureRecidHex) -> Result<Vec<u8>, SignatureError> {
+        let signature = signature.normalize_s()?;
+        let mut fields = self.unsigned_fields();
+        let r = scalar(signature.r.to_array());
+        let s = scalar(signature.s.to_array());
+        match self.gas_price {
+            GasPrice::Legacy { .. } => {
+                let v = match self.chain_id {
+                    Some(chain_id) => signature.eip155_v(chain_id)?,
+                    None => LEGACY_V_OFFSET + signature.y_parity()? as u64,
+                };
+                fields.extend([Rlp::from_u64(v), r, s]);
+                Ok(Rlp::List(fields).encode())
+            }
+            GasPrice::Eip1559(_) => {
+                fields.extend([Rlp::from_u64(signature.y_parity()? as u64), r, s]);
+                let mut bytes = vec![EIP1559_TRANSACTION_TYPE];
+                bytes.extend(Rlp::List(fields).encode());
+                Ok(bytes)
+            }
+        }
+    }
+
+    // fields without the signature in the order of the transaction type
+    fn unsigned_fields(&self) -> Vec<Rlp> {
+        let to = Rlp::Bytes(self.to.map(|to| to.to_vec()).unwrap_or_default());
+        match &self.gas_price {
+            GasPrice::Legacy { gas_price } => vec![
+                Rlp::from_u64(self.nonce),
+                Rlp::from_u128(*gas_price),
+                Rlp::from_u64(self.gas_limit),
+                to,
+                Rlp::from_u128(self.value),
+                Rlp::Bytes(self.data.clone()),
+            ],
+            GasPrice::Eip1559(fee) => vec![
+                Rlp::from_u64(self.chain_id.unwrap_or_default()),
+                Rlp::from_u64(self.nonce),
+                Rlp::from_u128(fee.max_priority_fee_per_gas),
+                Rlp::from_u128(fee.max_fee_per_gas),
+                Rlp::from_u64(self.gas_limit),
+                to,
+                Rlp::from_u128(self.value),
+                Rlp::Bytes(self.data.clone()),
+                Rlp::List(self.access_list.clone()),
+            ],
+        }
+    }
+}
+
+fn decode_legacy(rlp: &Rlp) -> Result<EvmTransaction, EvmTransactionError> {
+    let fields = rlp.as_list()?;
+    if fields.len() != 6 && fields.len() != 9 {
+        return Err(EvmTransactionError::InvalidFieldCount {
+            actual: fields.len(),
+        });
+    }
+    let mut transaction = EvmTransaction {
+        chain_id: None,
+        nonce: integer(&fields[0], "nonce")?,
+        gas_price: GasPrice::Legacy {
+            gas_price: integer(&fields[1], "gasPrice")?,
+        },
+        gas_limit: integer(&fields[2], "gasLimit")?,
+        to: to(&fields[3])?,
+        value: integer(&fields[4], "value")?,
+        data: bytes(&fields[5], "data")?.to_vec(),
+        access_list: Vec::new(),
+        signature: None,
+    };
+    if fields.len() == 9 {
+        let v: u64 = integer(&fields[6], "v")?;
+        let r = bytes(&fields[7], "r")?;
+        let s = bytes(&fields[8], "s")?;
+        if r.is_empty() && s.is_empty() {
+            // EIP-155 signing payload: chain id in place of v
+            transaction.chain_id = Some(v);
+        } else {
+            // v is 27 or 28 before EIP-155, chain id * 2 + 35 or 36 after
+            let chain_id = if v >= 35 { Some((v - 35) / 2) } else { None };
+            transaction.signature = Some(SignatureRecidHex::from_eip155(
+                word(r, "r")?,
+                word(s, "s")?,
+                v,
+                chain_id.unwrap_or(0),
+            )?);
+            transaction.chain_id = chain_id;
+        }
+    }
+    Ok(transaction)
+}
+
+fn decode_eip1559(rlp: &Rlp) -> Result<EvmTransaction, EvmTransactionError> {
+    let fields = rlp.as_list()?;
+    if fields.len() != 9 && fields.len() != 12 {
+        return Err(EvmTransactionError::InvalidFieldCount {
+            actual: fields.len(),
+        });
+    }
+    let signature = if fields.len() == 12 {
+        Some(SignatureRecidHex::from_y_parity(
+            word(bytes(&fields[10], "r")?, "r")?,
+            word(bytes(&fields[11], "s")?, "s")?,
+            integer(&fields[9], "yParity")?,
+        )?)
+    } else {
+        None
+    };
+    Ok(EvmTransaction {
+        chain_id: Some(integer(&fields[0], "chainId")?),
+        nonce: integer(&fields[1], "nonce")?,
+        gas_price: GasPrice::Eip1559(Eip1559Fee {
+            max_priority_fee_per_gas: integer(&fields[2], "maxPriorityFeePerGas")?,
+            max_fee_per_gas: integer(&fields[3], "maxFeePerGas")?,
+        }),
+        gas_limit: integer(&fields[4], "gasLimit")?,
+        to: to(&fields[5])?,
+        value: integer(&fields[6], "value")?,
+        data: bytes(&fields[7], "data")?.to_vec(),
+        access_list: fields[8]
+            .as_list()
+            .map_err(|error| EvmTransactionError::InvalidField {
+                field: "accessList",
+                error,
+            })?
+            .to_vec(),
+        signature,
+    })
+}
+
+fn integer<T: TryFrom<u128>>(rlp: &Rlp, field: &'static str) -> Result<T, EvmTransactionError> {
+    rlp.to_u128()
+        .and_then(|value| T::try_from(value).map_err(|_| RlpError::InvalidInteger))
+        .map_err(|error| EvmTransactionError::InvalidField { field, error })
+}
+
+fn bytes<'a>(rlp: &'a Rlp, field: &'static str) -> Result<&'a [u8], EvmTransactionError> {
+    rlp.as_bytes()
+        .map_err(|error| EvmTransactionError::InvalidField { field, error })
+}
+
+// recipient account, empty for contract creation
+fn to(rlp: &Rlp) -> Result<Option<[u8; 20]>, EvmTransactionError> {
+    let value = bytes(rlp, "to")?;
+    match value.len() {
+        0 => Ok(None),
+        20 => {
+            let mut account = [0u8; 20];
+            account.copy_from_slice(value);
+            Ok(Some(account))
+        }
+        len => Err(EvmTransactionError::InvalidRecipient { len }),
+    }
+}
+
+// signature scalar as an integer without leading zeros
+fn scalar(word: [u8; 32]) -> Rlp {
+    let first = word.iter().position(|byte| *byte != 0).unwrap_or(32);
+    Rlp::Bytes(word[first..].to_vec())
+}
+
+// signature scalar left padded to 32 bytes
+fn word(value: &[u8], field: &'static str) -> Result<[u8; 32], EvmTransactionError> {
+    if value.len() > 32 {
+        return Err(EvmTransactionError::InvalidField {
+            field,
+            error: RlpError::InvalidInteger,
+        });
+    }
+    let mut word = [0u8; 32];
+    word[32 - value.len()..].copy_from_slice(value);
+    Ok(word)
+}
@@ -0,0 +1,284 @@
+use std::fmt;
+
+use bigdecimal::BigDecimal;
+
+use crate::amount::AmountError;
+use crate::bitcoin::coin_selection::CoinSelectionError;
+use crate::cardano::fee::{address_len, AdaFeeError};
+use crate::evm::fee::{gas_limit, wei_to_coin_unit, EvmFeeConfig, EvmFeeError};
+use crate::payload::TransactionPayload;
+use crate::validation::ValidationError;
+use crate::{
+    Blockchain, CreateTransactionRequest, EstimateFeeResult, FeeLevel, RequestTransactionType,
+};
+
+// Reason why a fee cannot be estimated
+#[derive(Clone, PartialEq, Debug)]
+pub enum FeeError {
+    // request parameters needed by the blockchain are not set e.g. requestParamsBtc for BITCOIN
+    MissingParams {
+        blockchain: Blockchain,
+        params: &'static str,
+    },
+    // fee of the transaction type cannot be estimated on the blockchain
+    UnsupportedTransactionType {
+        transaction_type: RequestTransactionType,
+        blockchain: Blockchain,
+    },
+    // signing request detail does not match its transaction type
+    InvalidRequest(ValidationError),
+    InvalidAmount(AmountError),
+    CoinSelection(CoinSelectionError),
+    Evm(EvmFeeError),
+    Ada(AdaFeeError),
+}
+
+impl fmt::Display for FeeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeeError::MissingParams { blockchain, params } => {
+                write!(f, "{} is required for {:?}", params, blockchain)
+            }
+            FeeError::UnsupportedTransactionType {
+                transaction_type,
+                blockchain,
+            } => write!(
+                f,
+                "cannot estimate fee of {:?} on {:?}",
+                transaction_type, blockchain
+            ),
+            FeeError::InvalidRequest(error) => write!(f, "{}", error),
+            FeeError::InvalidAmount(error) => write!(f, "{}", error),
+            FeeError::CoinSelection(error) => write!(f, "{}", error),
+            FeeError::Evm(error) => write!(f, "{}", error),
+            FeeError::Ada(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for FeeError {}
+
+impl From<ValidationError> for FeeError {
+    fn from(error: ValidationError) -> Self {
+        FeeError::InvalidRequest(error)
+    }
+}
+
+impl From<AmountError> for FeeError {
+    fn from(error: AmountError) -> Self {
+        FeeError::InvalidAmount(error)
+    }
+}
+
+impl From<CoinSelectionError> for FeeError {
+    fn from(error: CoinSelectionError) -> Self {
+        FeeError::CoinSelection(error)
+    }
+}
+
+impl From<EvmFeeError> for FeeError {
+    fn from(error: EvmFeeError) -> Self {
+        FeeError::Evm(error)
+    }
+}
+
+impl From<AdaFeeError> for FeeError {
+    fn from(error: AdaFeeError) -> Self {
+        FeeError::Ada(error)
+    }
+}
+
+// A multiplier for each fee level
+#[derive(Clone, PartialEq, Debug)]
+pub struct FeeLevelMultipliers {
+    pub low: BigDecimal,
+    pub medium: BigDecimal,
+    pub high: BigDecimal,
+}
+
+impl FeeLevelMultipliers {
+    pub fn new(low: BigDecimal, medium: BigDecimal, high: BigDecimal) -> Self {
+        FeeLevelMultipliers { low, medium, high }
+    }
+
+    // multipliers given in thousandths e.g. 1250 is 1.25
+    pub fn thousandths(low: i64, medium: i64, high: i64) -> Self {
+        FeeLevelMultipliers::new(
+            BigDecimal::new(low.into(), 3),
+            BigDecimal::new(medium.into(), 3),
+            BigDecimal::new(high.into(), 3),
+        )
+    }
+
+    pub fn for_level(&self, fee_level: &FeeLevel) -> &BigDecimal {
+        match fee_level {
+            FeeLevel::LOW => &self.low,
+            FeeLevel::MEDIUM => &self.medium,
+            FeeLevel::HIGH => &self.high,
+        }
+    }
+}
+
+// Estimates the fee a transaction pays from the parameters of its blockchain.
+// The transaction builder charges the same fee for the fee level of the signing request.
+pub trait FeeEstimator {
+    // fee in native coin unit e.g. BTC, not satoshi
+    fn estimate(
+        &self,
+        request: &CreateTransactionRequest,
+        fee_level: &FeeLevel,
+    ) -> Result<BigDecimal, FeeError>;
+
+    fn estimate_fee_result(
+        &self,
+        request: &CreateTransactionRequest,
+    ) -> Result<EstimateFeeResult, FeeError> {
+        Ok(EstimateFeeResult {
+            blockchain: request.blockchain.clone(),
+            coin: request.coin.clone(),
+            low_estimated_fee: self.estimate(request, &FeeLevel::LOW)?,
+            medium_estimated_fee: self.estimate(request, &FeeLevel::MEDIUM)?,
+            high_estimated_fee: self.estimate(request, &FeeLevel::HIGH)?,
+        })
+    }
+}
+
+// Fee of the selected outputs at feePerByte x multiplier satoshi per virtual byte
+#[derive(Clone, PartialEq, Debug)]
+pub struct BitcoinFeeEstimator {
+    pub fee_rate: FeeLevelMultipliers,
+}
+
+impl Default for BitcoinFeeEstimator {
+    fn default() -> Self {
+        BitcoinFeeEstimator {
+            fee_rate: FeeLevelMultipliers::thousandths(800, 1000, 1500),
+        }
+    }
+}
+
+impl FeeEstimator for BitcoinFeeEstimator {
+    fn estimate(
+        &self,
+        request: &CreateTransactionRequest,
+        fee_level: &FeeLevel,
+    ) -> Result<BigDecimal, FeeError> {
+        let params = request
+            .request_params_btc
+            .as_ref()
+            .ok_or_else(|| missing_params(request, "requestParamsBtc"))?;
+        let send_request = match TransactionPayload::try_from(&request.signing_request)? {
+            TransactionPayload::Send(send_request) => send_request,
+            _ => return Err(unsupported_transaction_type(request)),
+        };
+        let fee_rate = &params.fee_per_byte * self.fee_rate.for_level(fee_level);
+        let is_segwit = is_segwit_address(&request.signing_request.from_address);
+        let selection = params.select_coins_at_rate(&send_request, fee_rate, is_segwit)?;
+        Ok(base_units_to_coin_unit(&request.blockchain, selection.fee))
+    }
+}
+
+// Worst case fee of the gas limit. EIP-1559 parameters are used when set, legacy gas price otherwise
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct EvmFeeEstimator {
+    pub config: EvmFeeConfig,
+}
+
+impl FeeEstimator for EvmFeeEstimator {
+    fn estimate(
+        &self,
+        request: &CreateTransactionRequest,
+        fee_level: &FeeLevel,
+    ) -> Result<BigDecimal, FeeError> {
+        let payload = TransactionPayload::try_from(&request.signing_request)?;
+        let gas_limit = gas_limit(&payload)?;
+        let cost = if let Some(params) = &request.request_params_eth_eip1559 {
+            params
+                .fee_for_level(fee_level, &self.config)?
+                .max_cost(gas_limit)?
+        } else if let Some(params) = &request.request_params_eth_legacy {
+            params.max_cost(fee_level, gas_limit, &self.config)?
+        } else {
+            return Err(missing_params(
+                request,
+                "requestParamsEthEip1559 or requestParamsEthLegacy",
+            ));
+        };
+        Ok(wei_to_coin_unit(&request.blockchain, cost))
+    }
+}
+
+// Linear fee of the transaction with the selected inputs. Cardano has no fee market so every
+// level has the same fee.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct CardanoFeeEstimator;
+
+impl FeeEstimator for CardanoFeeEstimator {
+    fn estimate(
+        &self,
+        request: &CreateTransactionRequest,
+        _fee_level: &FeeLevel,
+    ) -> Result<BigDecimal, FeeError> {
+        let params = request
+            .request_params_ada
+            .as_ref()
+            .ok_or_else(|| missing_params(request, "requestParamsAda"))?;
+        let send_request = match TransactionPayload::try_from(&request.signing_request)? {
+            TransactionPayload::Send(send_request) => send_request,
+            _ => return Err(unsupported_transaction_type(request)),
+        };
+        let selection = params.select_for_send(
+            &send_request,
+            address_len(&send_request.to_address),
+            address_len(&request.signing_request.from_address),
+        )?;
+        Ok(base_units_to_coin_unit(&request.blockchain, selection.fee))
+    }
+}
+
+// estimator with default settings for the blockchain
+pub fn fee_estimator(blockchain: &Blockchain) -> Box<dyn FeeEstimator> {
+    match blockchain {
+        Blockchain::BITCOIN => Box::<BitcoinFeeEstimator>::default(),
+        Blockchain::ETHEREUM | Blockchain::POLYGON => Box::<EvmFeeEstimator>::default(),
+        Blockchain::CARDANO => Box::new(CardanoFeeEstimator),
+    }
+}
+
+impl CreateTransactionRequest {
+    // fee of every level using the default estimator of the blockchain
+    pub fn estimate_fee(&self) -> Result<EstimateFeeResult, FeeError> {
+        fee_estimator(&self.blockchain).estimate_fee_result(self)
+    }
+
+    // fee in native coin unit at the fee level of the signing request
+    pub fn fee(&self) -> Result<BigDecimal, FeeError> {
+        fee_estimator(&self.blockchain).estimate(self, &self.signing_request.fee_level)
+    }
+}
+
+// Wallets with a bech32 address spend P2WPKH outputs
+pub(crate) fn is_segwit_address(address: &str) -> bool {
+    let address = address.to_lowercase();
+    ["bc1", "tb1", "bcrt1"]
+        .iter()
+        .any(|hrp| address.starts_with(hrp))
+}
+
+pub(crate) fn base_units_to_coin_unit(blockchain: &Blockchain, fee: u64) -> BigDecimal {
+    BigDecimal::new(fee.into(), blockchain.native_decimals() as i64)
+}
+
+pub(crate) fn missing_params(request: &CreateTransactionRequest, params: &'static str) -> FeeError {
+    FeeError::MissingParams {
+        blockchain: request.blockchain.clone(),
+        params,
+    }
+}
+
+pub(crate) fn unsupported_transaction_type(request: &CreateTransactionRequest) -> FeeError {
+    FeeError::UnsupportedTransactionType {
+        transaction_type: request.signing_request.request_transaction_type.clone(),
+        blockchain: request.blockchain.clone(),
+    }
+}
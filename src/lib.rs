@@ -1,8 +1,35 @@
 #![allow(dead_code)]
+#![allow(non_camel_case_types)]
 use bigdecimal::BigDecimal;
+use encoding::{Base64Bytes, Hash32Hex, HexBytes, ScalarHex};
+use secret::Secret;
 use serde::{Deserialize, Serialize};
 use strum_macros::EnumString;
 
+pub mod address;
+pub mod amount;
+pub mod assembly;
+pub mod bitcoin;
+pub mod blockchain;
+pub mod builder;
+pub mod cardano;
+pub mod derivation;
+pub mod encoding;
+pub mod evm;
+pub mod fee;
+pub mod merge;
+pub mod payload;
+pub mod progress;
+pub mod registry;
+pub mod secret;
+pub mod sighash;
+pub mod signature;
+pub mod status;
+pub mod transaction;
+pub mod validation;
+pub mod valuation;
+pub mod verification;
+
 // HotSigningRequest
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct HotSigningRequest {
@@ -52,21 +79,21 @@ pub struct ProtectedRegisterHotWallet {
 }
 
 // SignedPartialSignatureBase64
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SignedPartialSignatureBase64 {
     // party_id
     #[serde(rename = "party_id")]
     pub party_id: i32,
     // part_base64
     #[serde(rename = "part_base64")]
-    pub part_base64: String,
+    pub part_base64: Base64Bytes,
     // signed_at
     #[serde(rename = "signed_at")]
     pub signed_at: String,
 }
 
 // SigningStateBase64
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SigningStateBase64 {
     // t
     #[serde(rename = "t")]
@@ -86,7 +113,7 @@ pub struct SigningStateBase64 {
 }
 
 // SigningResult
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SigningResult {
     // signingHashes
     #[serde(rename = "signingHashes")]
@@ -110,7 +137,7 @@ pub struct NativeSigningRequest {
     pub state_base64: SigningStateBase64,
     // data to sign in hex format. no 0x prefix. lower case.
     #[serde(rename = "hexData")]
-    pub hex_data: String,
+    pub hex_data: HexBytes,
     // encryptedLocalKey
     #[serde(rename = "encryptedLocalKey")]
     pub encrypted_local_key: EncryptedLocalKey,
@@ -125,7 +152,7 @@ pub struct NativeSigningRequest {
     pub signers: Vec<i32>,
     // password to decrypt the generated private key
     #[serde(rename = "password")]
-    pub password: String,
+    pub password: Secret,
     // nonce index to sign. This only use for EDDSA at the moment
     #[serde(rename = "nonce")]
     pub nonce: i32,
@@ -151,7 +178,7 @@ pub struct GetSigningListResult {
 }
 
 // SigningHash
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SigningHash {
     // signing state that contains part signed from parties. If all required part signed are included, it will generate signature
     #[serde(rename = "state")]
@@ -161,11 +188,11 @@ pub struct SigningHash {
     pub nonce: i32,
     // hash to sign
     #[serde(rename = "hash")]
-    pub hash: String,
+    pub hash: Hash32Hex,
 }
 
 // SigningRequest
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SigningRequest {
     // session id
     #[serde(rename = "id")]
@@ -212,13 +239,19 @@ pub struct SigningRequest {
     // detail of a request from ethereum smart contract call
     #[serde(rename = "ethSmartContractRequest")]
     pub eth_smart_contract_request: Option<EthContractRequest>,
+    // details of request for signing a personal message
+    #[serde(rename = "personalSignRequest")]
+    pub personal_sign_request: Option<PersonalSignRequest>,
+    // details of request for signing EIP-712 typed data
+    #[serde(rename = "ethTypedDataRequest")]
+    pub eth_typed_data_request: Option<EthTypedDataRequest>,
     // Party_id of signing members who are assigned to sign the transaction
     #[serde(rename = "signers")]
     pub signers: Vec<i32>,
     // feeLevel
     #[serde(rename = "feeLevel")]
     pub fee_level: FeeLevel,
-    // total amount needs to pay for the transaction
+    // total amount needs to pay for the transaction in native coin unit e.g. ETH, not wei
     #[serde(rename = "fee")]
     pub fee: Option<BigDecimal>,
     // current version of the transaction request. Increase one every update. When update a signing request, if the version is old, it will be rejected
@@ -230,40 +263,40 @@ pub struct SigningRequest {
 }
 
 // SignatureRecidHex
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SignatureRecidHex {
     // r
     #[serde(rename = "r")]
-    pub r: String,
+    pub r: ScalarHex,
     // s
     #[serde(rename = "s")]
-    pub s: String,
+    pub s: ScalarHex,
     // recid
     #[serde(rename = "recid")]
     pub recid: i32,
 }
 
 // SendRequest
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SendRequest {
     // toAddress
     #[serde(rename = "toAddress")]
     pub to_address: String,
-    // amount
+    // amount in coin unit e.g. BTC, not satoshi
     #[serde(rename = "amount")]
     pub amount: BigDecimal,
 }
 
 // SendRequest
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct EthContractRequest {
     // smart contract address
     #[serde(rename = "toAddress")]
     pub to_address: String,
-    // amount of native coin that we send to the contract
+    // amount of native coin that we send to the contract in coin unit e.g. ETH, not wei
     #[serde(rename = "amount")]
     pub amount: BigDecimal,
-    // gas limit provided by contract
+    // gas limit provided by contract in gas unit
     #[serde(rename = "gasLimit")]
     pub gas_limit: BigDecimal,
     // smart contract data
@@ -271,8 +304,24 @@ pub struct EthContractRequest {
     pub data: String,
 }
 
+// PersonalSignRequest
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PersonalSignRequest {
+    // message to sign. 0x prefixed hex is signed as its bytes, any other value as UTF-8 text
+    #[serde(rename = "message")]
+    pub message: String,
+}
+
+// EthTypedDataRequest
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct EthTypedDataRequest {
+    // EIP-712 typed data JSON with types, primaryType, domain and message as in eth_signTypedData_v4
+    #[serde(rename = "typedData")]
+    pub typed_data: String,
+}
+
 // SendTokenRequest
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct SendTokenRequest {
     // toAddress
     #[serde(rename = "toAddress")]
@@ -280,7 +329,7 @@ pub struct SendTokenRequest {
     // tokenContractAddress
     #[serde(rename = "tokenContractAddress")]
     pub token_contract_address: String,
-    // amount
+    // amount in token unit, not scaled by decimals
     #[serde(rename = "amount")]
     pub amount: BigDecimal,
     // decimal places of token
@@ -311,13 +360,13 @@ pub struct NativeKeygenRequest {
     pub signer_name: String,
     // password to encrypt the generated private key
     #[serde(rename = "password")]
-    pub password: String,
+    pub password: Secret,
     // requestId to easily identify the request
     #[serde(rename = "requestId")]
     pub request_id: String,
     // token to authenticate the request
     #[serde(rename = "token")]
-    pub token: String,
+    pub token: Secret,
     // unique party id 1-base index
     #[serde(rename = "partyId")]
     pub party_id: i32,
@@ -363,10 +412,10 @@ pub struct NativeGenerateDynamicNonceRequest {
     pub request_id: String,
     // token to authenticate the request
     #[serde(rename = "token")]
-    pub token: String,
+    pub token: Secret,
     // password to dencrypt the private key
     #[serde(rename = "password")]
-    pub password: String,
+    pub password: Secret,
     // nonce start index. This is 0 base and starting from the last previous generated nonce. For example, previous generated from 0 with 100 nonce. the next value starting from 100
     #[serde(rename = "nonceStartIndex")]
     pub nonce_start_index: i32,
@@ -491,7 +540,7 @@ pub struct EncryptedLocalKey {
     pub pubkey: String,
     // encryptedKey
     #[serde(rename = "encryptedKey")]
-    pub encrypted_key: String,
+    pub encrypted_key: Secret,
     // encryptedNonce
     #[serde(rename = "encryptedNonce")]
     pub encrypted_nonce: String,
@@ -613,6 +662,10 @@ pub enum RequestTransactionType {
     SEND_TOKEN,
     // Ethereum like smart contract transaction
     ETH_SMART_CONTRACT_CALL,
+    // Sign a message with personal_sign (EIP-191) e.g. a dApp login
+    PERSONAL_SIGN,
+    // Sign EIP-712 typed data e.g. a permit
+    ETH_SIGN_TYPED_DATA,
 }
 
 // Supported signature schemes
@@ -757,7 +810,7 @@ pub struct SignTransactionRequest {
     pub pubkey: String,
     // hash string form raw transaction that are signing
     #[serde(rename = "hashes")]
-    pub hashes: Vec<String>,
+    pub hashes: Vec<Hash32Hex>,
     // signature for each signing hash in hashes array
     #[serde(rename = "signatures")]
     pub signatures: Vec<SignatureRecidHex>,
@@ -783,7 +836,7 @@ pub struct GenerateTransactionResponse {
     pub fee: BigDecimal,
     // list of hashes that required user to sign. If there are multiple hashes, the order is the same as inputs in the request
     #[serde(rename = "hashes")]
-    pub hashes: Vec<String>,
+    pub hashes: Vec<Hash32Hex>,
 }
 
 // EstimateFeeResult
@@ -827,7 +880,7 @@ pub struct TransactionSigned {
 }
 
 // UnspentOutput
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
 pub struct UnspentOutput {
     // transactionHash
     #[serde(rename = "transactionHash")]
@@ -838,7 +891,7 @@ pub struct UnspentOutput {
     // script
     #[serde(rename = "script")]
     pub script: String,
-    // unspent amount
+    // unspent amount in coin unit e.g. BTC, not satoshi
     #[serde(rename = "amount")]
     pub amount: BigDecimal,
 }
@@ -972,12 +1025,12 @@ pub struct CreateTransactionResult {
     // created raw transaction from the request
     #[serde(rename = "rawTransaction")]
     pub raw_transaction: String,
-    // total amount needs to pay for the transaction
+    // total amount needs to pay for the transaction in native coin unit e.g. ETH, not wei
     #[serde(rename = "fee")]
     pub fee: BigDecimal,
     // hashes of the created raw transaction
     #[serde(rename = "hashes")]
-    pub hashes: Vec<String>,
+    pub hashes: Vec<Hash32Hex>,
 }
 
 // CreateSignTransactionRequest
@@ -997,7 +1050,7 @@ pub struct CreateSignTransactionRequest {
     pub raw_transaction: String,
     // hashes of the transaction
     #[serde(rename = "hashes")]
-    pub hashes: Vec<String>,
+    pub hashes: Vec<Hash32Hex>,
     // signatures of the transaction
     #[serde(rename = "signatures")]
     pub signatures: Vec<SignatureRecidHex>,
@@ -1090,7 +1143,7 @@ pub struct CreateSignTransactionResult {
 // RequestParamsEth
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct RequestParamsEthLegacy {
-    // gas price
+    // gas price in wei
     #[serde(rename = "gasFee")]
     pub gas_fee: BigDecimal,
     // specify chain id for blockchain that may have different chain id in mainnet and testnet e.g. ETH
@@ -1141,10 +1194,10 @@ pub struct RequestParamsEthEip1559 {
     // account nonce number
     #[serde(rename = "nonce")]
     pub nonce: i32,
-    // base gas price
+    // base gas price in wei
     #[serde(rename = "baseGasFee")]
     pub base_gas_fee: BigDecimal,
-    // priority fee for transaction
+    // priority fee per gas for transaction in wei
     #[serde(rename = "priorityFee")]
     pub priority_fee: BigDecimal,
 }
@@ -1276,4 +1329,3 @@ pub struct UserPing {
     #[serde(rename = "pingAt")]
     pub ping_at: String,
 }
-
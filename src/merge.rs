@@ -0,0 +1,245 @@
+use std::fmt;
+
+use crate::{SignedPartialSignatureBase64, SigningHash, SigningRequest, SigningStateBase64};
+
+// Reason why a stale signing request update cannot be merged into the stored one
+#[derive(Clone, PartialEq, Debug)]
+pub enum MergeConflict {
+    // incoming request is not older than the stored one so it must be applied as a normal update
+    NotStale {
+        stored_version: i32,
+        incoming_version: i32,
+    },
+    // a field of the request has a different value
+    FieldChanged {
+        field: &'static str,
+    },
+    // signing hash at the index has a different hash, nonce or signing state parameters
+    SigningHashChanged {
+        index: usize,
+    },
+    // a party submitted a different partial signature for a hash it has already signed
+    PartialSignatureChanged {
+        index: usize,
+        party_id: i32,
+    },
+    // a partial signature was added by a party that is not assigned to sign the request
+    UnassignedSigner {
+        index: usize,
+        party_id: i32,
+    },
+    // merged version cannot be increased
+    VersionOverflow {
+        version: i32,
+    },
+}
+
+impl fmt::Display for MergeConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeConflict::NotStale {
+                stored_version,
+                incoming_version,
+            } => write!(
+                f,
+                "incoming version {} is not older than stored version {}",
+                incoming_version, stored_version
+            ),
+            MergeConflict::FieldChanged { field } => write!(f, "{} has been changed", field),
+            MergeConflict::SigningHashChanged { index } => {
+                write!(f, "signing hash {} has been changed", index)
+            }
+            MergeConflict::PartialSignatureChanged { index, party_id } => write!(
+                f,
+                "party {} submitted a different partial signature for signing hash {}",
+                party_id, index
+            ),
+            MergeConflict::UnassignedSigner { index, party_id } => write!(
+                f,
+                "party {} is not assigned to sign but signed signing hash {}",
+                party_id, index
+            ),
+            MergeConflict::VersionOverflow { version } => {
+                write!(f, "signing request version {} cannot be increased", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for MergeConflict {}
+
+impl SigningRequest {
+    // Merge an update that was made on an older version of this (stored) request.
+    // The update is accepted only when everything except the version is unchanged apart from
+    // partial signatures added to the signing hashes. The result contains the partial signatures of both
+    // requests and the version following the stored one. Otherwise every conflict found is returned.
+    pub fn merge_stale(
+        &self,
+        incoming: &SigningRequest,
+    ) -> Result<SigningRequest, Vec<MergeConflict>> {
+        if incoming.version >= self.version {
+            return Err(vec![MergeConflict::NotStale {
+                stored_version: self.version,
+                incoming_version: incoming.version,
+            }]);
+        }
+
+        let mut conflicts = Vec::new();
+        let mut check = |field, same: bool| {
+            if !same {
+                conflicts.push(MergeConflict::FieldChanged { field });
+            }
+        };
+        check("id", self.id == incoming.id);
+        check("walletId", self.wallet_id == incoming.wallet_id);
+        check("blockchain", self.blockchain == incoming.blockchain);
+        check("coin", self.coin == incoming.coin);
+        check("keyScheme", self.key_scheme == incoming.key_scheme);
+        check("pubkey", self.pubkey == incoming.pubkey);
+        check("fromAddress", self.from_address == incoming.from_address);
+        check("threshold", self.threshold == incoming.threshold);
+        check(
+            "requestTransactionType",
+            self.request_transaction_type == incoming.request_transaction_type,
+        );
+        check("status", self.status == incoming.status);
+        check("message", self.message == incoming.message);
+        check("sendRequest", self.send_request == incoming.send_request);
+        check(
+            "sendTokenRequest",
+            self.send_token_request == incoming.send_token_request,
+        );
+        check(
+            "ethSmartContractRequest",
+            self.eth_smart_contract_request == incoming.eth_smart_contract_request,
+        );
+        check(
+            "personalSignRequest",
+            self.personal_sign_request == incoming.personal_sign_request,
+        );
+        check(
+            "ethTypedDataRequest",
+            self.eth_typed_data_request == incoming.eth_typed_data_request,
+        );
+        check("signers", self.signers == incoming.signers);
+        check("feeLevel", self.fee_level == incoming.fee_level);
+        check("fee", self.fee == incoming.fee);
+        check("createdAt", self.created_at == incoming.created_at);
+
+        let mut merged = self.clone();
+        match (&mut merged.signing_result, &incoming.signing_result) {
+            (Some(stored), Some(incoming_result)) => {
+                check(
+                    "signingResult.unsignedTransaction",
+                    stored.unsigned_transaction == incoming_result.unsigned_transaction,
+                );
+                check(
+                    "signingResult.transactionHash",
+                    stored.transaction_hash == incoming_result.transaction_hash,
+                );
+                check(
+                    "signingResult.signedTransaction",
+                    stored.signed_transaction == incoming_result.signed_transaction,
+                );
+                if stored.signing_hashes.len() != incoming_result.signing_hashes.len() {
+                    conflicts.push(MergeConflict::FieldChanged {
+                        field: "signingResult.signingHashes",
+                    });
+                } else {
+                    for (index, (stored_hash, incoming_hash)) in stored
+                        .signing_hashes
+                        .iter_mut()
+                        .zip(&incoming_result.signing_hashes)
+                        .enumerate()
+                    {
+                        merge_signing_hash(
+                            index,
+                            stored_hash,
+                            incoming_hash,
+                            &self.signers,
+                            &mut conflicts,
+                        );
+                    }
+                }
+            }
+            (None, None) => {}
+            _ => conflicts.push(MergeConflict::FieldChanged {
+                field: "signingResult",
+            }),
+        }
+
+        match self.version.checked_add(1) {
+            Some(version) => merged.version = version,
+            None => conflicts.push(MergeConflict::VersionOverflow {
+                version: self.version,
+            }),
+        }
+
+        if conflicts.is_empty() {
+            Ok(merged)
+        } else {
+            Err(conflicts)
+        }
+    }
+}
+
+fn merge_signing_hash(
+    index: usize,
+    stored: &mut SigningHash,
+    incoming: &SigningHash,
+    signers: &[i32],
+    conflicts: &mut Vec<MergeConflict>,
+) {
+    if stored.hash != incoming.hash || stored.nonce != incoming.nonce {
+        conflicts.push(MergeConflict::SigningHashChanged { index });
+        return;
+    }
+    let incoming_state = match &incoming.state {
+        Some(state) => state,
+        // incoming has not seen any partial signature yet
+        None => return,
+    };
+    let stored_state = match &mut stored.state {
+        Some(state) => state,
+        // stored has not seen any partial signature yet, all incoming ones are new
+        None => stored.state.insert(SigningStateBase64 {
+            signing_parts_base64: Vec::new(),
+            signature: None,
+            ..incoming_state.clone()
+        }),
+    };
+    let incoming_signature_is_stale =
+        incoming_state.signature.is_none() || incoming_state.signature == stored_state.signature;
+    if stored_state.t != incoming_state.t
+        || stored_state.n != incoming_state.n
+        || stored_state.key_scheme != incoming_state.key_scheme
+        || !incoming_signature_is_stale
+    {
+        conflicts.push(MergeConflict::SigningHashChanged { index });
+        return;
+    }
+
+    let mut added: Vec<SignedPartialSignatureBase64> = Vec::new();
+    for part in &incoming_state.signing_parts_base64 {
+        match stored_state
+            .signing_parts_base64
+            .iter()
+            .chain(added.iter())
+            .find(|existing| existing.party_id == part.party_id)
+        {
+            Some(existing) if existing == part => {}
+            Some(_) => conflicts.push(MergeConflict::PartialSignatureChanged {
+                index,
+                party_id: part.party_id,
+            }),
+            None if !signers.contains(&part.party_id) => {
+                conflicts.push(MergeConflict::UnassignedSigner {
+                    index,
+                    party_id: part.party_id,
+                })
+            }
+            None => added.push(part.clone()),
+        }
+    }
+    stored_state.signing_parts_base64.extend(added);
+}
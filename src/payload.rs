@@ -0,0 +1,249 @@
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::validation::ValidationError;
+use crate::{
+    EthContractRequest, EthTypedDataRequest, PersonalSignRequest, RequestTransactionType,
+    SendRequest, SendTokenRequest, SigningRequest,
+};
+
+// Details of the transaction that a SigningRequest asks to sign. Exactly one variant exists for each
+// RequestTransactionType so an illegal combination cannot be represented.
+// On the wire it keeps the SigningRequest shape: requestTransactionType plus the optional details
+// where only the matching one is set, so it can be used with #[serde(flatten)].
+#[derive(Clone, Debug)]
+pub enum TransactionPayload {
+    // send native coin
+    Send(SendRequest),
+    // send token
+    SendToken(SendTokenRequest),
+    // ethereum like smart contract call
+    EthContractCall(EthContractRequest),
+    // EIP-191 personal message
+    PersonalSign(PersonalSignRequest),
+    // EIP-712 typed data
+    EthSignTypedData(EthTypedDataRequest),
+}
+
+// requestTransactionType followed by the details in SigningRequest field order
+pub type PayloadParts = (
+    RequestTransactionType,
+    Option<SendRequest>,
+    Option<SendTokenRequest>,
+    Option<EthContractRequest>,
+    Option<PersonalSignRequest>,
+    Option<EthTypedDataRequest>,
+);
+
+// wire shape used for serialization
+#[derive(Serialize)]
+struct PayloadWireRef<'a> {
+    #[serde(rename = "requestTransactionType")]
+    request_transaction_type: RequestTransactionType,
+    #[serde(rename = "sendRequest")]
+    send_request: Option<&'a SendRequest>,
+    #[serde(rename = "sendTokenRequest")]
+    send_token_request: Option<&'a SendTokenRequest>,
+    #[serde(rename = "ethSmartContractRequest")]
+    eth_smart_contract_request: Option<&'a EthContractRequest>,
+    #[serde(rename = "personalSignRequest")]
+    personal_sign_request: Option<&'a PersonalSignRequest>,
+    #[serde(rename = "ethTypedDataRequest")]
+    eth_typed_data_request: Option<&'a EthTypedDataRequest>,
+}
+
+// wire shape used for deserialization
+#[derive(Deserialize)]
+struct PayloadWire {
+    #[serde(rename = "requestTransactionType")]
+    request_transaction_type: RequestTransactionType,
+    #[serde(rename = "sendRequest")]
+    send_request: Option<SendRequest>,
+    #[serde(rename = "sendTokenRequest")]
+    send_token_request: Option<SendTokenRequest>,
+    #[serde(rename = "ethSmartContractRequest")]
+    eth_smart_contract_request: Option<EthContractRequest>,
+    #[serde(rename = "personalSignRequest")]
+    personal_sign_request: Option<PersonalSignRequest>,
+    #[serde(rename = "ethTypedDataRequest")]
+    eth_typed_data_request: Option<EthTypedDataRequest>,
+}
+
+impl TransactionPayload {
+    pub fn transaction_type(&self) -> RequestTransactionType {
+        match self {
+            TransactionPayload::Send(_) => RequestTransactionType::SEND,
+            TransactionPayload::SendToken(_) => RequestTransactionType::SEND_TOKEN,
+            TransactionPayload::EthContractCall(_) => {
+                RequestTransactionType::ETH_SMART_CONTRACT_CALL
+            }
+            TransactionPayload::PersonalSign(_) => RequestTransactionType::PERSONAL_SIGN,
+            TransactionPayload::EthSignTypedData(_) => RequestTransactionType::ETH_SIGN_TYPED_DATA,
+        }
+    }
+
+    // Build the payload from the wire fields. Fails when the detail matching the type is missing
+    // or when a detail of another type is also set.
+    pub fn from_parts(
+        transaction_type: RequestTransactionType,
+        mut send_request: Option<SendRequest>,
+        mut send_token_request: Option<SendTokenRequest>,
+        mut eth_smart_contract_request: Option<EthContractRequest>,
+        mut personal_sign_request: Option<PersonalSignRequest>,
+        mut eth_typed_data_request: Option<EthTypedDataRequest>,
+    ) -> Result<Self, ValidationError> {
+        let payload = match transaction_type {
+            RequestTransactionType::SEND => send_request.take().map(TransactionPayload::Send),
+            RequestTransactionType::SEND_TOKEN => {
+                send_token_request.take().map(TransactionPayload::SendToken)
+            }
+            RequestTransactionType::ETH_SMART_CONTRACT_CALL => eth_smart_contract_request
+                .take()
+                .map(TransactionPayload::EthContractCall),
+            RequestTransactionType::PERSONAL_SIGN => personal_sign_request
+                .take()
+                .map(TransactionPayload::PersonalSign),
+            RequestTransactionType::ETH_SIGN_TYPED_DATA => eth_typed_data_request
+                .take()
+                .map(TransactionPayload::EthSignTypedData),
+        };
+        let leftover = [
+            ("sendRequest", send_request.is_some()),
+            ("sendTokenRequest", send_token_request.is_some()),
+            (
+                "ethSmartContractRequest",
+                eth_smart_contract_request.is_some(),
+            ),
+            ("personalSignRequest", personal_sign_request.is_some()),
+            ("ethTypedDataRequest", eth_typed_data_request.is_some()),
+        ];
+        if let Some((field, _)) = leftover.into_iter().find(|(_, is_set)| *is_set) {
+            return Err(ValidationError::UnexpectedPayload {
+                transaction_type,
+                field,
+            });
+        }
+        payload.ok_or_else(|| ValidationError::MissingPayload {
+            field: payload_field(&transaction_type),
+            transaction_type,
+        })
+    }
+
+    // Split the payload back into the wire fields
+    pub fn into_parts(self) -> PayloadParts {
+        let transaction_type = self.transaction_type();
+        match self {
+            TransactionPayload::Send(send) => {
+                (transaction_type, Some(send), None, None, None, None)
+            }
+            TransactionPayload::SendToken(send_token) => {
+                (transaction_type, None, Some(send_token), None, None, None)
+            }
+            TransactionPayload::EthContractCall(contract) => {
+                (transaction_type, None, None, Some(contract), None, None)
+            }
+            TransactionPayload::PersonalSign(message) => {
+                (transaction_type, None, None, None, Some(message), None)
+            }
+            TransactionPayload::EthSignTypedData(typed_data) => {
+                (transaction_type, None, None, None, None, Some(typed_data))
+            }
+        }
+    }
+}
+
+// name of the SigningRequest field that carries the details of the transaction type
+pub(crate) fn payload_field(transaction_type: &RequestTransactionType) -> &'static str {
+    match transaction_type {
+        RequestTransactionType::SEND => "sendRequest",
+        RequestTransactionType::SEND_TOKEN => "sendTokenRequest",
+        RequestTransactionType::ETH_SMART_CONTRACT_CALL => "ethSmartContractRequest",
+        RequestTransactionType::PERSONAL_SIGN => "personalSignRequest",
+        RequestTransactionType::ETH_SIGN_TYPED_DATA => "ethTypedDataRequest",
+    }
+}
+
+impl Serialize for TransactionPayload {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let wire = PayloadWireRef {
+            request_transaction_type: self.transaction_type(),
+            send_request: match self {
+                TransactionPayload::Send(send) => Some(send),
+                _ => None,
+            },
+            send_token_request: match self {
+                TransactionPayload::SendToken(send_token) => Some(send_token),
+                _ => None,
+            },
+            eth_smart_contract_request: match self {
+                TransactionPayload::EthContractCall(contract) => Some(contract),
+                _ => None,
+            },
+            personal_sign_request: match self {
+                TransactionPayload::PersonalSign(message) => Some(message),
+                _ => None,
+            },
+            eth_typed_data_request: match self {
+                TransactionPayload::EthSignTypedData(typed_data) => Some(typed_data),
+                _ => None,
+            },
+        };
+        wire.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for TransactionPayload {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let wire = PayloadWire::deserialize(deserializer)?;
+        TransactionPayload::from_parts(
+            wire.request_transaction_type,
+            wire.send_request,
+            wire.send_token_request,
+            wire.eth_smart_contract_request,
+            wire.personal_sign_request,
+            wire.eth_typed_data_request,
+        )
+        .map_err(serde::de::Error::custom)
+    }
+}
+
+impl TryFrom<&SigningRequest> for TransactionPayload {
+    type Error = ValidationError;
+
+    fn try_from(request: &SigningRequest) -> Result<Self, Self::Error> {
+        TransactionPayload::from_parts(
+            request.request_transaction_type.clone(),
+            request.send_request.clone(),
+            request.send_token_request.clone(),
+            request.eth_smart_contract_request.clone(),
+            request.personal_sign_request.clone(),
+            request.eth_typed_data_request.clone(),
+        )
+    }
+}
+
+impl SigningRequest {
+    // typed view of the transaction details of this request
+    pub fn payload(&self) -> Result<TransactionPayload, ValidationError> {
+        TransactionPayload::try_from(self)
+    }
+
+    // replace requestTransactionType and the transaction details with the given payload
+    pub fn set_payload(&mut self, payload: TransactionPayload) {
+        let (
+            transaction_type,
+            send_request,
+            send_token_request,
+            eth_smart_contract_request,
+            personal_sign_request,
+            eth_typed_data_request,
+        ) = payload.into_parts();
+        self.request_transaction_type = transaction_type;
+        self.send_request = send_request;
+        self.send_token_request = send_token_request;
+        self.eth_smart_contract_request = eth_smart_contract_request;
+        self.personal_sign_request = personal_sign_request;
+        self.eth_typed_data_request = eth_typed_data_request;
+    }
+}
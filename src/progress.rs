@@ -0,0 +1,166 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use crate::{NativeSigningRequest, SigningRequest, SigningResult, SigningStateBase64};
+
+impl SigningStateBase64 {
+    // number of partial signatures required to generate the signature
+    pub fn required_parts(&self) -> usize {
+        self.t.max(0) as usize
+    }
+
+    // number of distinct parties with a valid party id that have signed
+    pub fn collected_parts(&self) -> usize {
+        self.signed_party_ids().len()
+    }
+
+    // whether enough parties have signed to generate the signature
+    pub fn is_complete(&self) -> bool {
+        self.collected_parts() >= self.required_parts()
+    }
+
+    // distinct party ids with a valid party id that have signed, in ascending order
+    pub fn signed_party_ids(&self) -> Vec<i32> {
+        let valid: BTreeSet<i32> = self
+            .signing_parts_base64
+            .iter()
+            .map(|part| part.party_id)
+            .filter(|party_id| self.is_party_in_range(*party_id))
+            .collect();
+        valid.into_iter().collect()
+    }
+
+    // assigned signers that have not signed yet
+    pub fn missing_signers(&self, signers: &[i32]) -> Vec<i32> {
+        missing_from(signers, &self.signed_party_ids())
+    }
+
+    // party ids that have more than one partial signature
+    pub fn duplicate_party_ids(&self) -> Vec<i32> {
+        let mut seen = BTreeSet::new();
+        let duplicates: BTreeSet<i32> = self
+            .signing_parts_base64
+            .iter()
+            .map(|part| part.party_id)
+            .filter(|party_id| !seen.insert(*party_id))
+            .collect();
+        duplicates.into_iter().collect()
+    }
+
+    // party ids outside of 1..=n
+    pub fn out_of_range_party_ids(&self) -> Vec<i32> {
+        let invalid: BTreeSet<i32> = self
+            .signing_parts_base64
+            .iter()
+            .map(|part| part.party_id)
+            .filter(|party_id| !self.is_party_in_range(*party_id))
+            .collect();
+        invalid.into_iter().collect()
+    }
+
+    fn is_party_in_range(&self, party_id: i32) -> bool {
+        party_id >= 1 && party_id <= self.n
+    }
+}
+
+impl NativeSigningRequest {
+    // signers assigned to this message that have not signed yet
+    pub fn missing_signers(&self) -> Vec<i32> {
+        self.state_base64.missing_signers(&self.signers)
+    }
+}
+
+// Aggregate signing progress over all hashes of a transaction.
+// A party is counted as signed only when it has signed every hash.
+#[derive(Clone, PartialEq, Debug)]
+pub struct SigningProgress {
+    // parties that have signed every hash
+    pub signed_parties: Vec<i32>,
+    // number of parties required to sign
+    pub required: usize,
+    // hashes that have enough partial signatures
+    pub completed_hashes: usize,
+    // total number of hashes to sign
+    pub total_hashes: usize,
+}
+
+impl SigningProgress {
+    pub fn collected(&self) -> usize {
+        self.signed_parties.len()
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.total_hashes > 0 && self.completed_hashes == self.total_hashes
+    }
+}
+
+impl fmt::Display for SigningProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} signed", self.collected(), self.required)
+    }
+}
+
+impl SigningResult {
+    // Progress across every signing hash. Required parts are taken from the signing states;
+    // hashes without a state count as unsigned.
+    pub fn progress(&self) -> SigningProgress {
+        let mut signed_parties: Option<BTreeSet<i32>> = None;
+        let mut required = 0;
+        let mut completed_hashes = 0;
+        for signing_hash in &self.signing_hashes {
+            let signed: BTreeSet<i32> = match &signing_hash.state {
+                Some(state) => {
+                    required = required.max(state.required_parts());
+                    if state.is_complete() {
+                        completed_hashes += 1;
+                    }
+                    state.signed_party_ids().into_iter().collect()
+                }
+                None => BTreeSet::new(),
+            };
+            signed_parties = Some(match signed_parties {
+                Some(parties) => parties.intersection(&signed).copied().collect(),
+                None => signed,
+            });
+        }
+        SigningProgress {
+            signed_parties: signed_parties.unwrap_or_default().into_iter().collect(),
+            required,
+            completed_hashes,
+            total_hashes: self.signing_hashes.len(),
+        }
+    }
+}
+
+impl SigningRequest {
+    // Progress of the request. The request threshold is used as the required number of parties so it can be
+    // reported before any signing state exists.
+    pub fn signing_progress(&self) -> SigningProgress {
+        let mut progress = match &self.signing_result {
+            Some(result) => result.progress(),
+            None => SigningProgress {
+                signed_parties: Vec::new(),
+                required: 0,
+                completed_hashes: 0,
+                total_hashes: 0,
+            },
+        };
+        progress.required = self.threshold.max(0) as usize;
+        progress
+    }
+
+    // assigned signers that have not signed every hash yet
+    pub fn missing_signers(&self) -> Vec<i32> {
+        missing_from(&self.signers, &self.signing_progress().signed_parties)
+    }
+}
+
+// signers in assignment order that are not in signed, each reported once
+fn missing_from(signers: &[i32], signed: &[i32]) -> Vec<i32> {
+    let mut reported = BTreeSet::new();
+    signers
+        .iter()
+        .copied()
+        .filter(|party_id| !signed.contains(party_id) && reported.insert(*party_id))
+        .collect()
+}
@@ -0,0 +1,479 @@
+use std::fmt;
+
+use bigdecimal::BigDecimal;
+
+use crate::amount::{AmountError, CoinAmount};
+use crate::{
+    Blockchain, BlockchainCoinConfig, BlockchainConfig, Coin, CoinConfig, ConfigForBlockchain,
+    KeyScheme,
+};
+
+// Contradiction found while building a Registry from config
+#[derive(Clone, PartialEq, Debug)]
+pub enum RegistryError {
+    // blockchain is configured more than once
+    DuplicateBlockchain {
+        blockchain: Blockchain,
+    },
+    // key scheme is not the one used by the blockchain
+    KeySchemeMismatch {
+        blockchain: Blockchain,
+        expected: KeyScheme,
+        actual: KeyScheme,
+    },
+    // coin is configured for a blockchain that has no BlockchainConfig
+    UnknownBlockchain {
+        blockchain: Blockchain,
+        coin: Coin,
+    },
+    // coin does not exist on the blockchain
+    UnsupportedCoin {
+        blockchain: Blockchain,
+        coin: Coin,
+    },
+    // coin is configured more than once in the same config list
+    DuplicateCoin {
+        blockchain: Blockchain,
+        coin: Coin,
+    },
+    // BlockchainCoinConfig and CoinConfig disagree on a field
+    ConflictingCoinConfig {
+        blockchain: Blockchain,
+        coin: Coin,
+        field: &'static str,
+    },
+    // isNative does not match the native coin of the blockchain
+    NativeFlagMismatch {
+        blockchain: Blockchain,
+        coin: Coin,
+        is_native: bool,
+    },
+    // native coin must not have a contract address
+    NativeCoinWithContract {
+        blockchain: Blockchain,
+        coin: Coin,
+    },
+    // token must have a contract address
+    TokenWithoutContract {
+        blockchain: Blockchain,
+        coin: Coin,
+    },
+    // decimals are negative or differ from the protocol decimals of the native coin
+    InvalidDecimals {
+        blockchain: Blockchain,
+        coin: Coin,
+        decimals: i32,
+    },
+    // coin is enabled on a disabled blockchain
+    EnabledCoinOnDisabledBlockchain {
+        blockchain: Blockchain,
+        coin: Coin,
+    },
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::DuplicateBlockchain { blockchain } => {
+                write!(f, "{:?} is configured more than once", blockchain)
+            }
+            RegistryError::KeySchemeMismatch {
+                blockchain,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{:?} requires key scheme {:?} but is configured with {:?}",
+                blockchain, expected, actual
+            ),
+            RegistryError::UnknownBlockchain { blockchain, coin } => write!(
+                f,
+                "{:?} is configured on {:?} which has no blockchain config",
+                coin, blockchain
+            ),
+            RegistryError::UnsupportedCoin { blockchain, coin } => {
+                write!(f, "{:?} is not supported on {:?}", coin, blockchain)
+            }
+            RegistryError::DuplicateCoin { blockchain, coin } => write!(
+                f,
+                "{:?} on {:?} is configured more than once",
+                coin, blockchain
+            ),
+            RegistryError::ConflictingCoinConfig {
+                blockchain,
+                coin,
+                field,
+            } => write!(
+                f,
+                "{:?} on {:?} has conflicting {} between coin configs",
+                coin, blockchain, field
+            ),
+            RegistryError::NativeFlagMismatch {
+                blockchain,
+                coin,
+                is_native,
+            } => write!(
+                f,
+                "{:?} on {:?} is configured with isNative {}",
+                coin, blockchain, is_native
+            ),
+            RegistryError::NativeCoinWithContract { blockchain, coin } => write!(
+                f,
+                "native coin {:?} on {:?} must not have a contract address",
+                coin, blockchain
+            ),
+            RegistryError::TokenWithoutContract { blockchain, coin } => write!(
+                f,
+                "token {:?} on {:?} requires a contract address",
+                coin, blockchain
+            ),
+            RegistryError::InvalidDecimals {
+                blockchain,
+                coin,
+                decimals,
+            } => write!(
+                f,
+                "{:?} on {:?} has invalid decimals {}",
+                coin, blockchain, decimals
+            ),
+            RegistryError::EnabledCoinOnDisabledBlockchain { blockchain, coin } => write!(
+                f,
+                "{:?} is enabled on disabled blockchain {:?}",
+                coin, blockchain
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+// Everything known about one coin on one blockchain
+#[derive(Clone, PartialEq, Debug)]
+pub struct RegistryCoin {
+    pub blockchain: Blockchain,
+    pub coin: Coin,
+    pub coin_name: String,
+    pub price_feed_id: String,
+    pub decimals: u32,
+    pub is_native: bool,
+    pub contract_address: Option<String>,
+    pub flags: Vec<String>,
+    pub enabled: bool,
+}
+
+// Blockchains and coins built from config lists after checking they do not contradict each other
+#[derive(Clone, Debug)]
+pub struct Registry {
+    blockchains: Vec<BlockchainConfig>,
+    coins: Vec<RegistryCoin>,
+}
+
+impl Registry {
+    // Build the registry from the config lists. A coin may be configured by a BlockchainCoinConfig,
+    // a CoinConfig or both as long as they agree. Every contradiction found is returned.
+    pub fn new(
+        blockchains: &[BlockchainConfig],
+        blockchain_coins: &[BlockchainCoinConfig],
+        coins: &[CoinConfig],
+    ) -> Result<Registry, Vec<RegistryError>> {
+        let mut errors = Vec::new();
+
+        let mut registry = Registry {
+            blockchains: Vec::new(),
+            coins: Vec::new(),
+        };
+        for config in blockchains {
+            if registry.blockchain(&config.blockchain).is_some() {
+                errors.push(RegistryError::DuplicateBlockchain {
+                    blockchain: config.blockchain.clone(),
+                });
+                continue;
+            }
+            if config.key_scheme != config.blockchain.key_scheme() {
+                errors.push(RegistryError::KeySchemeMismatch {
+                    blockchain: config.blockchain.clone(),
+                    expected: config.blockchain.key_scheme(),
+                    actual: config.key_scheme.clone(),
+                });
+            }
+            registry.blockchains.push(config.clone());
+        }
+
+        let from_blockchain_coins: Vec<(RegistryCoin, i32)> = blockchain_coins
+            .iter()
+            .map(|config| {
+                let entry = RegistryCoin {
+                    blockchain: config.blockchain.clone(),
+                    coin: config.coin.clone(),
+                    coin_name: config.coin_name.clone(),
+                    price_feed_id: config.price_feed_id.clone(),
+                    decimals: 0,
+                    is_native: config.is_native,
+                    contract_address: config.contract_address.clone(),
+                    flags: config.flags.clone(),
+                    enabled: config.enabled,
+                };
+                (entry, config.decimals)
+            })
+            .collect();
+        let from_coins: Vec<(RegistryCoin, i32)> = coins
+            .iter()
+            .flat_map(|coin| {
+                coin.config_for_blockchain
+                    .iter()
+                    .map(move |config: &ConfigForBlockchain| {
+                        let entry = RegistryCoin {
+                            blockchain: config.blockchain.clone(),
+                            coin: coin.coin.clone(),
+                            coin_name: coin.coin_name.clone(),
+                            price_feed_id: coin.price_feed_id.clone(),
+                            decimals: 0,
+                            is_native: config.is_native,
+                            contract_address: config.contract_address.clone(),
+                            flags: config.flags.clone(),
+                            enabled: config.enabled,
+                        };
+                        (entry, config.decimals)
+                    })
+            })
+            .collect();
+
+        for (entry, decimals) in from_blockchain_coins {
+            let entry = match with_decimals(entry, decimals, &mut errors) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            if registry.coin(&entry.blockchain, &entry.coin).is_some() {
+                errors.push(RegistryError::DuplicateCoin {
+                    blockchain: entry.blockchain,
+                    coin: entry.coin,
+                });
+                continue;
+            }
+            registry.add_coin(entry, &mut errors);
+        }
+
+        // CoinConfig may repeat a BlockchainCoinConfig entry as long as both agree
+        let mut seen: Vec<(Blockchain, Coin)> = Vec::new();
+        for (entry, decimals) in from_coins {
+            let key = (entry.blockchain.clone(), entry.coin.clone());
+            if seen.contains(&key) {
+                errors.push(RegistryError::DuplicateCoin {
+                    blockchain: key.0,
+                    coin: key.1,
+                });
+                continue;
+            }
+            seen.push(key);
+            let entry = match with_decimals(entry, decimals, &mut errors) {
+                Some(entry) => entry,
+                None => continue,
+            };
+            match registry.coin(&entry.blockchain, &entry.coin) {
+                Some(existing) => {
+                    for field in conflicting_fields(existing, &entry) {
+                        errors.push(RegistryError::ConflictingCoinConfig {
+                            blockchain: entry.blockchain.clone(),
+                            coin: entry.coin.clone(),
+                            field,
+                        });
+                    }
+                }
+                None => registry.add_coin(entry, &mut errors),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(registry)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn add_coin(&mut self, entry: RegistryCoin, errors: &mut Vec<RegistryError>) {
+        self.check_coin(&entry, errors);
+        self.coins.push(entry);
+    }
+
+    fn check_coin(&self, entry: &RegistryCoin, errors: &mut Vec<RegistryError>) {
+        let blockchain = &entry.blockchain;
+        let coin = &entry.coin;
+        match self.blockchain(blockchain) {
+            None => errors.push(RegistryError::UnknownBlockchain {
+                blockchain: blockchain.clone(),
+                coin: coin.clone(),
+            }),
+            Some(config) if !config.enabled && entry.enabled => {
+                errors.push(RegistryError::EnabledCoinOnDisabledBlockchain {
+                    blockchain: blockchain.clone(),
+                    coin: coin.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+        if !blockchain.supports_coin(coin) {
+            errors.push(RegistryError::UnsupportedCoin {
+                blockchain: blockchain.clone(),
+                coin: coin.clone(),
+            });
+            return;
+        }
+        if entry.is_native != (&blockchain.native_coin() == coin) {
+            errors.push(RegistryError::NativeFlagMismatch {
+                blockchain: blockchain.clone(),
+                coin: coin.clone(),
+                is_native: entry.is_native,
+            });
+        }
+        let has_contract = entry
+            .contract_address
+            .as_ref()
+            .map_or(false, |address| !address.is_empty());
+        if entry.is_native && has_contract {
+            errors.push(RegistryError::NativeCoinWithContract {
+                blockchain: blockchain.clone(),
+                coin: coin.clone(),
+            });
+        }
+        if !entry.is_native && !has_contract {
+            errors.push(RegistryError::TokenWithoutContract {
+                blockchain: blockchain.clone(),
+                coin: coin.clone(),
+            });
+        }
+    }
+
+    pub fn blockchain(&self, blockchain: &Blockchain) -> Option<&BlockchainConfig> {
+        self.blockchains
+            .iter()
+            .find(|config| &config.blockchain == blockchain)
+    }
+
+    pub fn blockchains(&self) -> &[BlockchainConfig] {
+        &self.blockchains
+    }
+
+    pub fn coin(&self, blockchain: &Blockchain, coin: &Coin) -> Option<&RegistryCoin> {
+        self.coins
+            .iter()
+            .find(|entry| &entry.blockchain == blockchain && &entry.coin == coin)
+    }
+
+    pub fn coins(&self, blockchain: &Blockchain) -> impl Iterator<Item = &RegistryCoin> {
+        let blockchain = blockchain.clone();
+        self.coins
+            .iter()
+            .filter(move |entry| entry.blockchain == blockchain)
+    }
+
+    // whether both the blockchain and the coin on it are enabled
+    pub fn is_enabled(&self, blockchain: &Blockchain, coin: &Coin) -> bool {
+        self.blockchain(blockchain)
+            .map_or(false, |config| config.enabled)
+            && self
+                .coin(blockchain, coin)
+                .map_or(false, |entry| entry.enabled)
+    }
+
+    // enabled coins of an enabled blockchain
+    pub fn enabled_coins(&self, blockchain: &Blockchain) -> Vec<&RegistryCoin> {
+        if !self
+            .blockchain(blockchain)
+            .map_or(false, |config| config.enabled)
+        {
+            return Vec::new();
+        }
+        self.coins(blockchain)
+            .filter(|entry| entry.enabled)
+            .collect()
+    }
+
+    // token with the given contract address. EVM addresses are compared case insensitively
+    pub fn token_by_contract(
+        &self,
+        blockchain: &Blockchain,
+        contract_address: &str,
+    ) -> Option<&RegistryCoin> {
+        self.coins(blockchain).find(|entry| {
+            entry.contract_address.as_ref().map_or(false, |address| {
+                same_contract(blockchain, address, contract_address)
+            })
+        })
+    }
+
+    pub fn decimals(&self, blockchain: &Blockchain, coin: &Coin) -> Option<u32> {
+        self.coin(blockchain, coin).map(|entry| entry.decimals)
+    }
+
+    // amount in coin unit of a coin on a blockchain. None when the coin is not configured
+    pub fn amount(
+        &self,
+        blockchain: &Blockchain,
+        coin: &Coin,
+        amount: &BigDecimal,
+    ) -> Option<Result<CoinAmount, AmountError>> {
+        self.coin(blockchain, coin)
+            .map(|entry| CoinAmount::from_coin_unit(coin.clone(), entry.decimals, amount))
+    }
+}
+
+// set decimals of the entry when they are valid for the coin
+fn with_decimals(
+    mut entry: RegistryCoin,
+    decimals: i32,
+    errors: &mut Vec<RegistryError>,
+) -> Option<RegistryCoin> {
+    match u32::try_from(decimals) {
+        Ok(valid) if !entry.is_native || valid == entry.blockchain.native_decimals() => {
+            entry.decimals = valid;
+            Some(entry)
+        }
+        _ => {
+            errors.push(RegistryError::InvalidDecimals {
+                blockchain: entry.blockchain,
+                coin: entry.coin,
+                decimals,
+            });
+            None
+        }
+    }
+}
+
+// whether two contract addresses are the same on the blockchain
+pub(crate) fn same_contract(blockchain: &Blockchain, left: &str, right: &str) -> bool {
+    if blockchain.is_evm() {
+        left.eq_ignore_ascii_case(right)
+    } else {
+        left == right
+    }
+}
+
+fn conflicting_fields(left: &RegistryCoin, right: &RegistryCoin) -> Vec<&'static str> {
+    let mut fields = Vec::new();
+    if left.coin_name != right.coin_name {
+        fields.push("coinName");
+    }
+    if left.price_feed_id != right.price_feed_id {
+        fields.push("priceFeedId");
+    }
+    if left.decimals != right.decimals {
+        fields.push("decimals");
+    }
+    if left.is_native != right.is_native {
+        fields.push("isNative");
+    }
+    let same_contract_address = match (&left.contract_address, &right.contract_address) {
+        (Some(left_address), Some(right_address)) => {
+            same_contract(&left.blockchain, left_address, right_address)
+        }
+        (None, None) => true,
+        _ => false,
+    };
+    if !same_contract_address {
+        fields.push("contractAddress");
+    }
+    if left.enabled != right.enabled {
+        fields.push("enabled");
+    }
+    fields
+}
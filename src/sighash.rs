@@ -0,0 +1,390 @@
+use std::fmt;
+
+use bigdecimal::BigDecimal;
+
+use crate::amount::AmountError;
+use crate::bitcoin::sighash::p2wpkh_script_code;
+use crate::bitcoin::transaction::BtcTransaction;
+use crate::cardano::transaction::body_hash;
+use crate::encoding::{EncodingError, Hash32Hex, HexBytes};
+use crate::transaction::{DecodedTransaction, TransactionDecodeError};
+use crate::{
+    Blockchain, CreateTransactionRequest, CreateTransactionResult, GenerateTransactionResponse,
+    UnspentOutput,
+};
+
+// Reason why the hashes of a raw transaction cannot be recomputed or do not match
+#[derive(Clone, PartialEq, Debug)]
+pub enum SighashError {
+    InvalidTransaction(TransactionDecodeError),
+    // Bitcoin input spends an output that is not in the unspent outputs
+    MissingUnspentOutput {
+        index: usize,
+        transaction_hash: String,
+        output_index: u32,
+    },
+    // script of the spent output is not hex
+    InvalidScript {
+        index: usize,
+        error: EncodingError,
+    },
+    // spent output is not P2PKH or P2WPKH so the wallet cannot sign it
+    UnsupportedScript {
+        index: usize,
+        script: String,
+    },
+    // amount of the spent output does not fit in 64 bits of satoshi
+    AmountTooLarge {
+        amount: BigDecimal,
+    },
+    InvalidAmount(AmountError),
+    // number of supplied hashes differs from the transaction
+    HashCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    // supplied hash differs from the recomputed one at the same position
+    HashMismatch {
+        index: usize,
+        expected: Hash32Hex,
+        actual: Hash32Hex,
+    },
+}
+
+impl fmt::Display for SighashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SighashError::InvalidTransaction(error) => {
+                write!(f, "raw transaction cannot be decoded: {}", error)
+            }
+            SighashError::MissingUnspentOutput {
+                index,
+                transaction_hash,
+                output_index,
+            } => write!(
+                f,
+                "input {} spends {}:{} which is not an unspent output",
+                index, transaction_hash, output_index
+            ),
+            SighashError::InvalidScript { index, error } => {
+                write!(f, "script of input {}: {}", index, error)
+            }
+            SighashError::UnsupportedScript { index, script } => {
+                write!(f, "input {} spends unsupported script {}", index, script)
+            }
+            SighashError::AmountTooLarge { amount } => {
+                write!(f, "amount {} BTC is too large", amount)
+            }
+            SighashError::InvalidAmount(error) => write!(f, "{}", error),
+            SighashError::HashCountMismatch { expected, actual } => write!(
+                f,
+                "transaction has {} hashes to sign but got {}",
+                expected, actual
+            ),
+            SighashError::HashMismatch {
+                index,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "hash {} is {} but transaction requires {}",
+                index, actual, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SighashError {}
+
+impl From<TransactionDecodeError> for SighashError {
+    fn from(error: TransactionDecodeError) -> Self {
+        SighashError::InvalidTransaction(error)
+    }
+}
+
+impl From<AmountError> for SighashError {
+    fn from(error: AmountError) -> Self {
+        SighashError::InvalidAmount(error)
+    }
+}
+
+// Hashes that must be signed for the raw transaction in order.
+// BITCOIN: one SIGHASH_ALL hash per input, legacy for P2PKH and BIP-143 for P2WPKH outputs
+// ETHEREUM, POLYGON: keccak256 of the unsigned legacy EIP-155 or EIP-1559 payload
+// CARDANO: blake2b-256 of the transaction body
+pub fn signing_hashes(
+    blockchain: &Blockchain,
+    raw_transaction: &str,
+    unspent_outputs: &[UnspentOutput],
+) -> Result<Vec<Hash32Hex>, SighashError> {
+    match DecodedTransaction::decode(blockchain, raw_transaction)? {
+        DecodedTransaction::Bitcoin(transaction) => bitcoin_hashes(&transaction, unspent_outputs),
+        DecodedTransaction::Evm(transaction) => {
+            Ok(vec![Hash32Hex::from_bytes(transaction.signing_hash())])
+        }
+        DecodedTransaction::Cardano(_) => {
+            let bytes = HexBytes::try_from(raw_transaction.trim())
+                .map_err(TransactionDecodeError::from)?
+                .to_bytes();
+            let hash = body_hash(&bytes).map_err(TransactionDecodeError::from)?;
+            Ok(vec![Hash32Hex::from_bytes(hash)])
+        }
+    }
+}
+
+// Recompute the hashes of the raw transaction and check the supplied ones are the same in
+// the same order, so a transaction builder cannot have signers sign something else
+pub fn verify_hashes(
+    blockchain: &Blockchain,
+    raw_transaction: &str,
+    unspent_outputs: &[UnspentOutput],
+    hashes: &[Hash32Hex],
+) -> Result<(), SighashError> {
+    let expected = signing_hashes(blockchain, raw_transaction, unspent_outputs)?;
+    if expected.len() != hashes.len() {
+        return Err(SighashError::HashCountMismatch {
+            expected: expected.len(),
+            actual: hashes.len(),
+        });
+    }
+    for (index, (expected, actual)) in expected.into_iter().zip(hashes).enumerate() {
+        if &expected != actual {
+            return Err(SighashError::HashMismatch {
+                index,
+                expected,
+                actual: actual.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+impl GenerateTransactionResponse {
+    // unspent outputs are only needed for Bitcoin
+    pub fn verify_hashes(&self, unspent_outputs: &[UnspentOutput]) -> Result<(), SighashError> {
+        verify_hashes(
+            &self.blockchain,
+            &self.raw_tx,
+            unspent_outputs,
+            &self.hashes,
+        )
+    }
+}
+
+impl CreateTransactionResult {
+    // unspent outputs are only needed for Bitcoin
+    pub fn verify_hashes(
+        &self,
+        blockchain: &Blockchain,
+        unspent_outputs: &[UnspentOutput],
+    ) -> Result<(), SighashError> {
+        verify_hashes(
+            blockchain,
+            &self.raw_transaction,
+            unspent_outputs,
+            &self.hashes,
+        )
+    }
+}
+
+impl CreateTransactionRequest {
+    // check the hashes of a result created for this request against its unspent outputs
+    pub fn verify_hashes(&self, result: &CreateTransactionResult) -> Result<(), SighashError> {
+        let unspent_outputs = self
+            .request_params_btc
+            .as_ref()
+            .map(|params| params.unspent_outputs.as_slice())
+            .unwrap_or_default();
+        result.verify_hashes(&self.blockchain, unspent_outputs)
+    }
+}
+
+// Output script kinds the wallet can spend, with the hash160 of the key that spends them
+pub(crate) enum SpentScript<'a> {
+    // OP_DUP OP_HASH160 <key hash> OP_EQUALVERIFY OP_CHECKSIG
+    P2pkh { key_hash: &'a [u8] },
+    // OP_0 <key hash>
+    P2wpkh { key_hash: &'a [u8] },
+}
+
+impl<'a> SpentScript<'a> {
+    pub(crate) fn parse(script: &'a [u8]) -> Option<Self> {
+        match script {
+            [0x00, 0x14, key_hash @ ..] if key_hash.len() == 20 => {
+                Some(SpentScript::P2wpkh { key_hash })
+            }
+            [0x76, 0xa9, 0x14, key_hash @ .., 0x88, 0xac] if key_hash.len() == 20 => {
+                Some(SpentScript::P2pkh { key_hash })
+            }
+            _ => None,
+        }
+    }
+}
+
+// Unspent output spent by the input at index and its script
+pub(crate) fn spent_output<'a>(
+    transaction: &BtcTransaction,
+    index: usize,
+    unspent_outputs: &'a [UnspentOutput],
+) -> Result<(&'a UnspentOutput, Vec<u8>), SighashError> {
+    let input = &transaction.inputs[index];
+    let transaction_hash = hex::encode(input.previous_txid);
+    let spent = unspent_outputs
+        .iter()
+        .find(|output| {
+            output.index as i64 == input.previous_index as i64
+                && HexBytes::try_from(output.transaction_hash.trim())
+                    .map_or(false, |hash| hash.as_str() == transaction_hash)
+        })
+        .ok_or_else(|| SighashError::MissingUnspentOutput {
+            index,
+            transaction_hash: transaction_hash.clone(),
+            output_index: input.previous_index,
+        })?;
+    let script = HexBytes::try_from(spent.script.trim())
+        .map_err(|error| SighashError::InvalidScript { index, error })?
+        .to_bytes();
+    Ok((spent, script))
+}
+
+fn bitcoin_hashes(
+    transaction: &BtcTransaction,
+    unspent_outputs: &[UnspentOutput],
+) -> Result<Vec<Hash32Hex>, SighashError> {
+    let mut hashes = Vec::with_capacity(transaction.inputs.len());
+    for index in 0..transaction.inputs.len() {
+        let (spent, script) = spent_output(transaction, index, unspent_outputs)?;
+        let hash = match SpentScript::parse(&script) {
+            Some(SpentScript::P2wpkh { key_hash }) => {
+                let amount = spent.coin_amount(&Blockchain::BITCOIN)?;
+                let satoshi = amount
+                    .to_u64()
+                    .ok_or_else(|| SighashError::AmountTooLarge {
+                        amount: amount.to_coin_unit(),
+                    })?;
+                transaction.segwit_v0_sighash(index, &p2wpkh_script_code(key_hash), satoshi)
+            }
+            Some(SpentScript::P2pkh { .. }) => transaction.legacy_sighash(index, &script),
+            None => {
+                return Err(SighashError::UnsupportedScript {
+                    index,
+                    script: hex::encode(&script),
+                })
+            }
+        };
+        hashes.push(Hash32Hex::from_bytes(hash));
+    }
+    Ok(hashes)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+    use crate::bitcoin::sighash::tests::{BIP143_KEY_HASH, BIP143_TRANSACTION};
+
+    fn unspent_output(
+        transaction_hash: &str,
+        index: i32,
+        script: String,
+        amount: &str,
+    ) -> UnspentOutput {
+        UnspentOutput {
+            transaction_hash: transaction_hash.to_string(),
+            index,
+            script,
+            amount: BigDecimal::from_str(amount).unwrap(),
+        }
+    }
+
+    // outputs spent by the BIP-143 example with input 0 spending P2PKH of the key of input 1
+    fn bip143_unspent_outputs(amount: &str) -> Vec<UnspentOutput> {
+        vec![
+            unspent_output(
+                "9f96ade4b41d5433f4eda31e1738ec2b36f6e7d1420d94a6af99801a88f7f7ff",
+                0,
+                format!("76a914{}88ac", BIP143_KEY_HASH),
+                "6.25",
+            ),
+            unspent_output(
+                "8ac60eb9575db5b2d987e29f301b5b819ea83a5c6579d282d189cc04b8e151ef",
+                1,
+                format!("0014{}", BIP143_KEY_HASH),
+                amount,
+            ),
+        ]
+    }
+
+    fn hashes(values: &[&str]) -> Vec<Hash32Hex> {
+        values
+            .iter()
+            .map(|value| Hash32Hex::try_from(*value).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn bitcoin_hashes_by_spent_script() {
+        let actual = signing_hashes(
+            &Blockchain::BITCOIN,
+            BIP143_TRANSACTION,
+            &bip143_unspent_outputs("6"),
+        )
+        .unwrap();
+        assert_eq!(
+            actual,
+            hashes(&[
+                "47194bc3c303a30aa5f78e45c7c2980b3be1284a9d69b1ea9ec0d29aac5f6848",
+                "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670",
+            ])
+        );
+    }
+
+    // BIP-143 hashes commit to the spent amount so a hash for another amount is rejected
+    #[test]
+    fn bitcoin_hash_of_other_amount_mismatches() {
+        let error = verify_hashes(
+            &Blockchain::BITCOIN,
+            BIP143_TRANSACTION,
+            &bip143_unspent_outputs("0.06"),
+            &hashes(&[
+                "47194bc3c303a30aa5f78e45c7c2980b3be1284a9d69b1ea9ec0d29aac5f6848",
+                "c37af31116d1b27caf68aae9e3ac82f1477929014d5b917657d0eb49478cb670",
+            ]),
+        )
+        .unwrap_err();
+        assert!(matches!(error, SighashError::HashMismatch { index: 1, .. }));
+    }
+
+    #[test]
+    fn bitcoin_input_without_unspent_output() {
+        let error = signing_hashes(&Blockchain::BITCOIN, BIP143_TRANSACTION, &[]).unwrap_err();
+        assert!(matches!(
+            error,
+            SighashError::MissingUnspentOutput { index: 0, .. }
+        ));
+    }
+
+    // signing data and hash of the EIP-155 example: nonce 9, gas price 20 gwei, gas limit 21000,
+    // 1 ether to 0x3535353535353535353535353535353535353535 on chain 1
+    #[test]
+    fn evm_eip155_hash() {
+        let raw_transaction = "ec098504a817c800825208943535353535353535353535353535353535353535880de0b6b3a764000080018080";
+        assert_eq!(
+            signing_hashes(&Blockchain::ETHEREUM, raw_transaction, &[]).unwrap(),
+            hashes(&["daf5a779ae972f972197303d7b574746c7ef83eadac0f2791ad23db92e4c8e53"])
+        );
+    }
+
+    // the body is hashed as it is encoded, here with a fee in 8 bytes where 4 would do
+    #[test]
+    fn cardano_body_hash() {
+        let raw_transaction = "84a40081825820000102030405060708090a0b0c0d0e0f101112131415161718\
+            191a1b1c1d1e1f00018182581d6111111111111111111111111111111111111111111111111111111111\
+            1a000f4240021b0000000000029810031903e8a0f5f6";
+        assert_eq!(
+            signing_hashes(&Blockchain::CARDANO, raw_transaction, &[]).unwrap(),
+            hashes(&["ef1493796a3babd85ed62a173f1c13abdeeb093c64fa843390e629ee04ea8bc9"])
+        );
+    }
+}
@@ -0,0 +1,266 @@
+use std::fmt;
+
+use k256::ecdsa::Signature;
+
+use crate::encoding::ScalarHex;
+use crate::SignatureRecidHex;
+
+// sighash type signing all inputs and outputs of a Bitcoin transaction
+pub const SIGHASH_ALL: u8 = 0x01;
+// v of signatures before EIP-155 is recid + 27
+const LEGACY_V_OFFSET: u64 = 27;
+// v of EIP-155 signatures is recid + 35 + 2 * chainId
+const EIP155_V_OFFSET: u64 = 35;
+
+// Reason why a signature cannot be converted
+#[derive(Clone, PartialEq, Debug)]
+pub enum SignatureError {
+    // recid must be 0 to 3, or 0 to 1 where only the y parity is kept
+    InvalidRecid { recid: i64 },
+    // r or s is zero or not below the secp256k1 curve order
+    InvalidScalar,
+    // bytes are not a strict DER encoded signature
+    InvalidDer,
+    // raw signature bytes have the wrong length
+    InvalidLength { expected: usize, actual: usize },
+    // v does not belong to the chain id
+    InvalidV { v: u64, chain_id: u64 },
+    // v does not fit in 64 bits for the chain id
+    ChainIdTooLarge { chain_id: u64 },
+}
+
+impl fmt::Display for SignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureError::InvalidRecid { recid } => write!(f, "recid {} is invalid", recid),
+            SignatureError::InvalidScalar => {
+                write!(f, "r and s must be in the range of the curve order")
+            }
+            SignatureError::InvalidDer => write!(f, "signature is not strict DER"),
+            SignatureError::InvalidLength { expected, actual } => {
+                write!(f, "signature must be {} bytes but got {}", expected, actual)
+            }
+            SignatureError::InvalidV { v, chain_id } => {
+                write!(f, "v {} is not valid for chain id {}", v, chain_id)
+            }
+            SignatureError::ChainIdTooLarge { chain_id } => {
+                write!(f, "chain id {} is too large", chain_id)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SignatureError {}
+
+impl SignatureRecidHex {
+    pub fn from_parts(r: [u8; 32], s: [u8; 32], recid: u8) -> Self {
+        SignatureRecidHex {
+            r: ScalarHex::from_bytes(r),
+            s: ScalarHex::from_bytes(s),
+            recid: recid as i32,
+        }
+    }
+
+    // Recovery id 0 to 3. Bit 0 is the parity of y of the nonce point R, bit 1 is set when
+    // the x of R overflowed the curve order
+    pub fn recovery_id(&self) -> Result<u8, SignatureError> {
+        match self.recid {
+            0..=3 => Ok(self.recid as u8),
+            _ => Err(SignatureError::InvalidRecid {
+                recid: self.recid as i64,
+            }),
+        }
+    }
+
+    // s is at most half of the curve order as required by Bitcoin and Ethereum
+    pub fn is_low_s(&self) -> Result<bool, SignatureError> {
+        Ok(self.ecdsa()?.normalize_s().is_none())
+    }
+
+    // Same signature with s replaced by n - s when s is high. Negating s mirrors R so the parity of
+    // the recovery id flips
+    pub fn normalize_s(&self) -> Result<SignatureRecidHex, SignatureError> {
+        let recid = self.recovery_id()?;
+        match self.ecdsa()?.normalize_s() {
+            Some(normalized) => Ok(from_ecdsa(&normalized, recid ^ 1)),
+            None => Ok(self.clone()),
+        }
+    }
+
+    // DER encoding of r and s as they are
+    pub fn to_der(&self) -> Result<Vec<u8>, SignatureError> {
+        Ok(self.ecdsa()?.to_der().as_bytes().to_vec())
+    }
+
+    // DER does not carry the recovery id so it is given by the caller
+    pub fn from_der(der: &[u8], recid: u8) -> Result<SignatureRecidHex, SignatureError> {
+        check_recid(recid, 3)?;
+        let signature = Signature::from_der(der).map_err(|_| SignatureError::InvalidDer)?;
+        Ok(from_ecdsa(&signature, recid))
+    }
+
+    // Bitcoin script signature: DER of the low S signature followed by the sighash type
+    pub fn to_bitcoin(&self, sighash_type: u8) -> Result<Vec<u8>, SignatureError> {
+        let mut bytes = self.normalize_s()?.to_der()?;
+        bytes.push(sighash_type);
+        Ok(bytes)
+    }
+
+    // signature and sighash type of a Bitcoin script signature
+    pub fn from_bitcoin(
+        bytes: &[u8],
+        recid: u8,
+    ) -> Result<(SignatureRecidHex, u8), SignatureError> {
+        let (sighash_type, der) = bytes.split_last().ok_or(SignatureError::InvalidDer)?;
+        Ok((SignatureRecidHex::from_der(der, recid)?, *sighash_type))
+    }
+
+    // y parity of EIP-2930 and EIP-1559 transactions. recid 2 and 3 mean r overflowed the curve order,
+    // which EVM transactions cannot encode
+    pub fn y_parity(&self) -> Result<u8, SignatureError> {
+        let recid = self.recovery_id()?;
+        check_recid(recid, 1)?;
+        Ok(recid)
+    }
+
+    pub fn from_y_parity(r: [u8; 32], s: [u8; 32], y_parity: u8) -> Result<Self, SignatureError> {
+        check_recid(y_parity, 1)?;
+        Ok(SignatureRecidHex::from_parts(r, s, y_parity))
+    }
+
+    // v of a legacy EVM transaction replay protected by EIP-155
+    pub fn eip155_v(&self, chain_id: u64) -> Result<u64, SignatureError> {
+        Ok(eip155_v_offset(chain_id)? + self.y_parity()? as u64)
+    }
+
+    // Signature of a legacy EVM transaction from v. Accepts EIP-155 v of the chain and pre EIP-155 v of 27 or 28
+    pub fn from_eip155(
+        r: [u8; 32],
+        s: [u8; 32],
+        v: u64,
+        chain_id: u64,
+    ) -> Result<SignatureRecidHex, SignatureError> {
+        let offset = eip155_v_offset(chain_id)?;
+        let y_parity = if v == LEGACY_V_OFFSET || v == LEGACY_V_OFFSET + 1 {
+            v - LEGACY_V_OFFSET
+        } else if v == offset || v == offset + 1 {
+            v - offset
+        } else {
+            return Err(SignatureError::InvalidV { v, chain_id });
+        };
+        SignatureRecidHex::from_y_parity(r, s, y_parity as u8)
+    }
+
+    // r || s || v with v in big endian without leading zeros. 65 bytes when v is below 256
+    pub fn to_rsv(&self, v: u64) -> Vec<u8> {
+        let mut bytes = self.to_rs().to_vec();
+        let v_bytes = v.to_be_bytes();
+        let first = v_bytes.iter().position(|byte| *byte != 0).unwrap_or(7);
+        bytes.extend_from_slice(&v_bytes[first..]);
+        bytes
+    }
+
+    // 65 bytes r || s || (27 + recid) used by eth_sign and personal_sign
+    pub fn to_rsv_legacy(&self) -> Result<[u8; 65], SignatureError> {
+        let mut bytes = [0u8; 65];
+        bytes[..64].copy_from_slice(&self.to_rs());
+        bytes[64] = LEGACY_V_OFFSET as u8 + self.y_parity()?;
+        Ok(bytes)
+    }
+
+    // signature from 65 bytes r || s || v where v is 27, 28, 0 or 1
+    pub fn from_rsv_legacy(bytes: &[u8]) -> Result<SignatureRecidHex, SignatureError> {
+        let (r, s) = split_rs(bytes, 65)?;
+        let v = bytes[64];
+        let y_parity = match v {
+            0 | 1 => v,
+            27 | 28 => v - LEGACY_V_OFFSET as u8,
+            _ => return Err(SignatureError::InvalidRecid { recid: v as i64 }),
+        };
+        SignatureRecidHex::from_y_parity(r, s, y_parity)
+    }
+
+    // 64 bytes r || s, the raw encoding of Ed25519 signatures where r is the encoded point R
+    pub fn to_rs(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r.to_array());
+        bytes[32..].copy_from_slice(&self.s.to_array());
+        bytes
+    }
+
+    // Ed25519 signature from its 64 bytes. Ed25519 has no recovery id so recid is 0
+    pub fn from_ed25519(bytes: &[u8]) -> Result<SignatureRecidHex, SignatureError> {
+        let (r, s) = split_rs(bytes, 64)?;
+        Ok(SignatureRecidHex::from_parts(r, s, 0))
+    }
+
+    pub fn to_ed25519(&self) -> [u8; 64] {
+        self.to_rs()
+    }
+
+    fn ecdsa(&self) -> Result<Signature, SignatureError> {
+        Signature::from_scalars(self.r.to_array(), self.s.to_array())
+            .map_err(|_| SignatureError::InvalidScalar)
+    }
+}
+
+fn from_ecdsa(signature: &Signature, recid: u8) -> SignatureRecidHex {
+    let (r, s) = signature.split_bytes();
+    SignatureRecidHex::from_parts(r.into(), s.into(), recid)
+}
+
+// recid 0 of EIP-155 v
+fn eip155_v_offset(chain_id: u64) -> Result<u64, SignatureError> {
+    chain_id
+        .checked_mul(2)
+        .and_then(|doubled| doubled.checked_add(EIP155_V_OFFSET))
+        .ok_or(SignatureError::ChainIdTooLarge { chain_id })
+}
+
+fn check_recid(recid: u8, max: u8) -> Result<(), SignatureError> {
+    if recid > max {
+        return Err(SignatureError::InvalidRecid {
+            recid: recid as i64,
+        });
+    }
+    Ok(())
+}
+
+// r and s from the first 64 bytes of a raw signature of the given length
+fn split_rs(bytes: &[u8], len: usize) -> Result<([u8; 32], [u8; 32]), SignatureError> {
+    if bytes.len() != len {
+        return Err(SignatureError::InvalidLength {
+            expected: len,
+            actual: bytes.len(),
+        });
+    }
+    let mut r = [0u8; 32];
+    let mut s = [0u8; 32];
+    r.copy_from_slice(&bytes[..32]);
+    s.copy_from_slice(&bytes[32..64]);
+    Ok((r, s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn y_parity_rejects_recid_above_one() {
+        for recid in 0..=1 {
+            let signature = SignatureRecidHex::from_parts([1; 32], [2; 32], recid);
+            assert_eq!(signature.y_parity().unwrap(), recid);
+            assert_eq!(signature.eip155_v(1).unwrap(), 37 + recid as u64);
+            assert_eq!(signature.to_rsv_legacy().unwrap()[64], 27 + recid);
+        }
+        for recid in 2..=3 {
+            let signature = SignatureRecidHex::from_parts([1; 32], [2; 32], recid);
+            let invalid = SignatureError::InvalidRecid {
+                recid: recid as i64,
+            };
+            assert_eq!(signature.y_parity(), Err(invalid.clone()));
+            assert_eq!(signature.eip155_v(1), Err(invalid.clone()));
+            assert_eq!(signature.to_rsv_legacy(), Err(invalid));
+        }
+    }
+}
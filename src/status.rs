@@ -0,0 +1,92 @@
+use std::fmt;
+
+use crate::{SigningRequest, SigningStatus};
+
+// Reason why a signing request could not move to a new status
+#[derive(Clone, PartialEq, Debug)]
+pub enum TransitionError {
+    // the lifecycle does not allow moving from one status to the other
+    IllegalTransition {
+        from: SigningStatus,
+        to: SigningStatus,
+    },
+    // version cannot be increased any further
+    VersionOverflow {
+        version: i32,
+    },
+}
+
+impl fmt::Display for TransitionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransitionError::IllegalTransition { from, to } => {
+                write!(f, "signing status cannot move from {:?} to {:?}", from, to)
+            }
+            TransitionError::VersionOverflow { version } => {
+                write!(f, "signing request version {} cannot be increased", version)
+            }
+        }
+    }
+}
+
+impl std::error::Error for TransitionError {}
+
+impl SigningStatus {
+    // Lifecycle of a signing session:
+    // SIGNING_SESSION_CREATED -> SIGNING_IN_PROGRESS -> SIGNING_COMPLETED -> SIGNING_BROADCASTED
+    // Any status before broadcast can move to SIGNING_FAILED. Created and in progress sessions can be
+    // updated without changing status e.g. when a party adds a partial signature.
+    // SIGNING_BROADCASTED and SIGNING_FAILED are final.
+    pub fn can_transition_to(&self, next: &SigningStatus) -> bool {
+        use SigningStatus::*;
+        matches!(
+            (self, next),
+            (SIGNING_SESSION_CREATED, SIGNING_SESSION_CREATED)
+                | (SIGNING_SESSION_CREATED, SIGNING_IN_PROGRESS)
+                | (SIGNING_SESSION_CREATED, SIGNING_FAILED)
+                | (SIGNING_IN_PROGRESS, SIGNING_IN_PROGRESS)
+                | (SIGNING_IN_PROGRESS, SIGNING_COMPLETED)
+                | (SIGNING_IN_PROGRESS, SIGNING_FAILED)
+                | (SIGNING_COMPLETED, SIGNING_BROADCASTED)
+                | (SIGNING_COMPLETED, SIGNING_FAILED)
+        )
+    }
+
+    // no further transition is allowed
+    pub fn is_final(&self) -> bool {
+        matches!(
+            self,
+            SigningStatus::SIGNING_BROADCASTED | SigningStatus::SIGNING_FAILED
+        )
+    }
+}
+
+impl SigningRequest {
+    // Move the request to a new status and increase the version by one.
+    // The request is left untouched when the transition is not allowed.
+    pub fn advance_to(
+        &mut self,
+        status: SigningStatus,
+        message: Option<String>,
+    ) -> Result<(), TransitionError> {
+        if !self.status.can_transition_to(&status) {
+            return Err(TransitionError::IllegalTransition {
+                from: self.status.clone(),
+                to: status,
+            });
+        }
+        self.version = self.next_version()?;
+        self.status = status;
+        self.message = message;
+        Ok(())
+    }
+
+    // version that the next update of this request must carry
+    pub fn next_version(&self) -> Result<i32, TransitionError> {
+        self.version
+            .checked_add(1)
+            .ok_or(TransitionError::VersionOverflow {
+                version: self.version,
+            })
+    }
+}
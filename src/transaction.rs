@@ -0,0 +1,835 @@
+use std::fmt;
+
+use bigdecimal::num_bigint::BigInt;
+use bigdecimal::{BigDecimal, ToPrimitive, Zero};
+
+use crate::address::{eip55_checksum, Address};
+use crate::amount::{display_amount, normalized, whole_number, AmountError, CoinAmount};
+use crate::bitcoin::transaction::{BtcTransaction, BtcTransactionError};
+use crate::cardano::transaction::{AdaTransactionBody, AdaTransactionError};
+use crate::encoding::{EncodingError, HexBytes};
+use crate::evm::erc20::Erc20Call;
+use crate::evm::fee::wei_to_coin_unit;
+use crate::evm::transaction::{EvmTransaction, EvmTransactionError};
+use crate::payload::TransactionPayload;
+use crate::registry::Registry;
+use crate::validation::ValidationError;
+use crate::{
+    Blockchain, Coin, EthContractRequest, RequestTransactionType, SendRequest, SendTokenRequest,
+    SigningRequest, VerifyTransactionRequest, VerifyTransactionResult,
+};
+
+// Reason why a raw transaction cannot be decoded
+#[derive(Clone, PartialEq, Debug)]
+pub enum TransactionDecodeError {
+    // raw transaction is not hex
+    Encoding(EncodingError),
+    Bitcoin(BtcTransactionError),
+    Evm(EvmTransactionError),
+    Cardano(AdaTransactionError),
+}
+
+impl fmt::Display for TransactionDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionDecodeError::Encoding(error) => write!(f, "{}", error),
+            TransactionDecodeError::Bitcoin(error) => write!(f, "{}", error),
+            TransactionDecodeError::Evm(error) => write!(f, "{}", error),
+            TransactionDecodeError::Cardano(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl std::error::Error for TransactionDecodeError {}
+
+impl From<EncodingError> for TransactionDecodeError {
+    fn from(error: EncodingError) -> Self {
+        TransactionDecodeError::Encoding(error)
+    }
+}
+
+impl From<BtcTransactionError> for TransactionDecodeError {
+    fn from(error: BtcTransactionError) -> Self {
+        TransactionDecodeError::Bitcoin(error)
+    }
+}
+
+impl From<EvmTransactionError> for TransactionDecodeError {
+    fn from(error: EvmTransactionError) -> Self {
+        TransactionDecodeError::Evm(error)
+    }
+}
+
+impl From<AdaTransactionError> for TransactionDecodeError {
+    fn from(error: AdaTransactionError) -> Self {
+        TransactionDecodeError::Cardano(error)
+    }
+}
+
+// A raw transaction decoded by the format of its blockchain
+#[derive(Clone, PartialEq, Debug)]
+pub enum DecodedTransaction {
+    Bitcoin(BtcTransaction),
+    Evm(EvmTransaction),
+    Cardano(AdaTransactionBody),
+}
+
+impl DecodedTransaction {
+    // decode a hex raw transaction, with or without 0x prefix
+    pub fn decode(
+        blockchain: &Blockchain,
+        raw_transaction: &str,
+    ) -> Result<DecodedTransaction, TransactionDecodeError> {
+        let bytes = HexBytes::try_from(raw_transaction.trim())?.to_bytes();
+        Ok(match blockchain {
+            Blockchain::BITCOIN => DecodedTransaction::Bitcoin(BtcTransaction::decode(&bytes)?),
+            Blockchain::ETHEREUM | Blockchain::POLYGON => {
+                DecodedTransaction::Evm(EvmTransaction::decode(&bytes)?)
+            }
+            Blockchain::CARDANO => DecodedTransaction::Cardano(AdaTransactionBody::decode(&bytes)?),
+        })
+    }
+}
+
+// A difference between a raw transaction and the signing request shown to the user.
+// Amounts are in coin unit, or token unit for token transfers.
+#[derive(Clone, PartialEq, Debug)]
+pub enum TransactionMismatch {
+    // blockchain of the verify request differs from the signing request
+    BlockchainMismatch {
+        expected: Blockchain,
+        actual: Blockchain,
+    },
+    // coin of the verify request differs from the signing request
+    CoinMismatch {
+        expected: Coin,
+        actual: Coin,
+    },
+    // signing request is malformed so it cannot be compared
+    InvalidRequest(ValidationError),
+    // amount of the signing request cannot be represented in base units
+    InvalidAmount {
+        field: &'static str,
+        error: AmountError,
+    },
+    // smart contract data of the signing request is not hex
+    InvalidData(EncodingError),
+    InvalidTransaction(TransactionDecodeError),
+    // transaction type cannot be built on the blockchain
+    UnsupportedTransactionType {
+        transaction_type: RequestTransactionType,
+        blockchain: Blockchain,
+    },
+    // no output pays the recipient
+    MissingPayment {
+        address: String,
+        amount: BigDecimal,
+    },
+    // outputs pay the recipient a different amount
+    PaymentMismatch {
+        address: String,
+        expected: BigDecimal,
+        actual: BigDecimal,
+    },
+    // output pays neither the recipient nor the sender as change
+    UnexpectedOutput {
+        index: usize,
+        address: String,
+        amount: BigDecimal,
+    },
+    // output to someone other than the sender carries native assets
+    UnexpectedAssets {
+        index: usize,
+        address: String,
+    },
+    // Cardano body field that the request does not ask for e.g. certificates or withdrawals
+    UnexpectedBodyField {
+        key: u64,
+    },
+    // EVM transaction calls another account. None is a contract creation
+    RecipientMismatch {
+        expected: String,
+        actual: Option<String>,
+    },
+    // EVM transaction sends a different amount of native coin
+    ValueMismatch {
+        expected: BigDecimal,
+        actual: BigDecimal,
+    },
+    // EVM call data differs, in hex
+    DataMismatch {
+        expected: String,
+        actual: String,
+    },
+    // call data of a token send is not an ERC-20 transfer
+    NotTokenTransfer {
+        data: String,
+    },
+    TokenRecipientMismatch {
+        expected: String,
+        actual: String,
+    },
+    TokenAmountMismatch {
+        expected: BigDecimal,
+        actual: BigDecimal,
+    },
+    GasLimitMismatch {
+        expected: BigDecimal,
+        actual: u64,
+    },
+    // transaction can pay a higher fee than the user approved
+    FeeExceeded {
+        approved: BigDecimal,
+        actual: BigDecimal,
+    },
+    // legacy EVM transaction without EIP-155 replay protection
+    MissingChainId,
+    ChainIdMismatch {
+        expected: u64,
+        actual: u64,
+    },
+}
+
+impl fmt::Display for TransactionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionMismatch::BlockchainMismatch { expected, actual } => write!(
+                f,
+                "blockchain {:?} does not match signing request {:?}",
+                actual, expected
+            ),
+            TransactionMismatch::CoinMismatch { expected, actual } => write!(
+                f,
+                "coin {:?} does not match signing request {:?}",
+                actual, expected
+            ),
+            TransactionMismatch::InvalidRequest(error) => {
+                write!(f, "invalid signing request: {}", error)
+            }
+            TransactionMismatch::InvalidAmount { field, error } => {
+                write!(f, "{}: {}", field, error)
+            }
+            TransactionMismatch::InvalidData(error) => {
+                write!(f, "ethSmartContractRequest.data: {}", error)
+            }
+            TransactionMismatch::InvalidTransaction(error) => {
+                write!(f, "raw transaction cannot be decoded: {}", error)
+            }
+            TransactionMismatch::UnsupportedTransactionType {
+                transaction_type,
+                blockchain,
+            } => write!(
+                f,
+                "{:?} is not supported on {:?}",
+                transaction_type, blockchain
+            ),
+            TransactionMismatch::MissingPayment { address, amount } => {
+                write!(
+                    f,
+                    "no output pays {} to {}",
+                    display_amount(amount),
+                    address
+                )
+            }
+            TransactionMismatch::PaymentMismatch {
+                address,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "outputs pay {} to {} but signing request sends {}",
+                display_amount(actual),
+                address,
+                display_amount(expected)
+            ),
+            TransactionMismatch::UnexpectedOutput {
+                index,
+                address,
+                amount,
+            } => write!(
+                f,
+                "output {} pays {} to {} which is neither the recipient nor the sender",
+                index, amount, address
+            ),
+            TransactionMismatch::UnexpectedAssets { index, address } => {
+                write!(f, "output {} sends native assets to {}", index, address)
+            }
+            TransactionMismatch::UnexpectedBodyField { key } => {
+                write!(f, "transaction body has unexpected field {}", key)
+            }
+            TransactionMismatch::RecipientMismatch { expected, actual } => match actual {
+                Some(actual) => write!(
+                    f,
+                    "transaction is sent to {} but signing request sends to {}",
+                    actual, expected
+                ),
+                None => write!(
+                    f,
+                    "transaction creates a contract but signing request sends to {}",
+                    expected
+                ),
+            },
+            TransactionMismatch::ValueMismatch { expected, actual } => write!(
+                f,
+                "transaction sends value {} but signing request sends {}",
+                display_amount(actual),
+                display_amount(expected)
+            ),
+            TransactionMismatch::DataMismatch { expected, actual } => write!(
+                f,
+                "transaction data 0x{} does not match signing request data 0x{}",
+                actual, expected
+            ),
+            TransactionMismatch::NotTokenTransfer { data } => {
+                write!(f, "transaction data 0x{} is not an ERC-20 transfer", data)
+            }
+            TransactionMismatch::TokenRecipientMismatch { expected, actual } => write!(
+                f,
+                "token transfer is sent to {} but signing request sends to {}",
+                actual, expected
+            ),
+            TransactionMismatch::TokenAmountMismatch { expected, actual } => write!(
+                f,
+                "token transfer sends {} but signing request sends {}",
+                display_amount(actual),
+                display_amount(expected)
+            ),
+            TransactionMismatch::GasLimitMismatch { expected, actual } => write!(
+                f,
+                "gas limit {} does not match signing request gas limit {}",
+                actual,
+                display_amount(expected)
+            ),
+            TransactionMismatch::FeeExceeded { approved, actual } => write!(
+                f,
+                "transaction can pay fee {} which exceeds approved fee {}",
+                display_amount(actual),
+                display_amount(approved)
+            ),
+            TransactionMismatch::MissingChainId => {
+                write!(f, "transaction has no chain id and can be replayed")
+            }
+            TransactionMismatch::ChainIdMismatch { expected, actual } => write!(
+                f,
+                "chain id {} does not match configured chain id {}",
+                actual, expected
+            ),
+        }
+    }
+}
+
+impl VerifyTransactionRequest {
+    // Every difference between rawTransaction and signingRequest. EVM transactions are only
+    // checked to carry a chain id. Bitcoin fees cannot be checked without the spent outputs.
+    pub fn mismatches(&self) -> Vec<TransactionMismatch> {
+        self.mismatches_on_chain(None)
+    }
+
+    // Same as mismatches() and additionally check the EVM chain id configured in the registry
+    pub fn mismatches_with(&self, registry: &Registry) -> Vec<TransactionMismatch> {
+        let chain_id = registry
+            .blockchain(&self.signing_request.blockchain)
+            .and_then(|config| config.chain_id.as_deref())
+            .and_then(parse_chain_id);
+        self.mismatches_on_chain(chain_id)
+    }
+
+    pub fn verify(&self) -> VerifyTransactionResult {
+        verify_result(self.mismatches())
+    }
+
+    pub fn verify_with(&self, registry: &Registry) -> VerifyTransactionResult {
+        verify_result(self.mismatches_with(registry))
+    }
+
+    fn mismatches_on_chain(&self, chain_id: Option<u64>) -> Vec<TransactionMismatch> {
+        let request = &self.signing_request;
+        let mut mismatches = Vec::new();
+        if self.blockchain != request.blockchain {
+            mismatches.push(TransactionMismatch::BlockchainMismatch {
+                expected: request.blockchain.clone(),
+                actual: self.blockchain.clone(),
+            });
+        }
+        if self.coin != request.coin {
+            mismatches.push(TransactionMismatch::CoinMismatch {
+                expected: request.coin.clone(),
+                actual: self.coin.clone(),
+            });
+        }
+        let payload = match request.payload() {
+            Ok(payload) => payload,
+            Err(error) => {
+                mismatches.push(TransactionMismatch::InvalidRequest(error));
+                return mismatches;
+            }
+        };
+        let decoded = match DecodedTransaction::decode(&request.blockchain, &self.raw_transaction) {
+            Ok(decoded) => decoded,
+            Err(error) => {
+                mismatches.push(TransactionMismatch::InvalidTransaction(error));
+                return mismatches;
+            }
+        };
+        let mut comparison = Comparison {
+            request,
+            mismatches,
+        };
+        match (&decoded, &payload) {
+            (DecodedTransaction::Bitcoin(transaction), TransactionPayload::Send(send)) => {
+                comparison.bitcoin(transaction, send)
+            }
+            (DecodedTransaction::Cardano(body), TransactionPayload::Send(send)) => {
+                comparison.cardano(body, send)
+            }
+            (DecodedTransaction::Evm(transaction), _) => {
+                comparison.evm(transaction, &payload, chain_id)
+            }
+            _ => comparison
+                .mismatches
+                .push(TransactionMismatch::UnsupportedTransactionType {
+                    transaction_type: payload.transaction_type(),
+                    blockchain: request.blockchain.clone(),
+                }),
+        }
+        comparison.mismatches
+    }
+}
+
+// failed reason lists every mismatch
+fn verify_result(mismatches: Vec<TransactionMismatch>) -> VerifyTransactionResult {
+    let failed_reason = if mismatches.is_empty() {
+        None
+    } else {
+        Some(
+            mismatches
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<String>>()
+                .join("; "),
+        )
+    };
+    VerifyTransactionResult { failed_reason }
+}
+
+// chain id of BlockchainConfig in decimal or 0x hex
+pub(crate) fn parse_chain_id(chain_id: &str) -> Option<u64> {
+    match chain_id
+        .strip_prefix("0x")
+        .or_else(|| chain_id.strip_prefix("0X"))
+    {
+        Some(digits) => u64::from_str_radix(digits, 16).ok(),
+        None => chain_id.parse().ok(),
+    }
+}
+
+// collects mismatches between a decoded transaction and the signing request
+struct Comparison<'a> {
+    request: &'a SigningRequest,
+    mismatches: Vec<TransactionMismatch>,
+}
+
+impl<'a> Comparison<'a> {
+    // Outputs paying the recipient must add up to the amount. Every other output must return
+    // change to the sender.
+    fn bitcoin(&mut self, transaction: &BtcTransaction, send: &SendRequest) {
+        let (from, to, amount) = match self.send_parts(send) {
+            Some(parts) => parts,
+            None => return,
+        };
+        let is_mainnet = from.is_mainnet().unwrap_or(true);
+        let recipient = to.script_pubkey();
+        let change = from.script_pubkey();
+        let mut paid = BigInt::zero();
+        for (index, output) in transaction.outputs.iter().enumerate() {
+            let script = Some(&output.script_pubkey);
+            if script == recipient.as_ref() {
+                paid += output.amount;
+            } else if script != change.as_ref() {
+                let address = Address::from_script_pubkey(&output.script_pubkey, is_mainnet)
+                    .map(|address| address.to_string())
+                    .unwrap_or_else(|| format!("script {}", hex::encode(&output.script_pubkey)));
+                self.mismatches.push(TransactionMismatch::UnexpectedOutput {
+                    index,
+                    address,
+                    amount: self.native_amount(output.amount),
+                });
+            }
+        }
+        self.check_payment(&to, &amount, paid, recipient == change);
+    }
+
+    // Same as bitcoin() on address bytes. The fee is explicit so it is checked against the
+    // approved fee.
+    fn cardano(&mut self, body: &AdaTransactionBody, send: &SendRequest) {
+        let (from, to, amount) = match self.send_parts(send) {
+            Some(parts) => parts,
+            None => return,
+        };
+        let mut paid = BigInt::zero();
+        for (index, output) in body.outputs.iter().enumerate() {
+            let is_change = output.address == from.payload();
+            if output.address == to.payload() {
+                paid += output.amount;
+            } else if !is_change {
+                self.mismatches.push(TransactionMismatch::UnexpectedOutput {
+                    index,
+                    address: output.address_string(),
+                    amount: self.native_amount(output.amount),
+                });
+            }
+            if output.has_assets && !is_change {
+                self.mismatches.push(TransactionMismatch::UnexpectedAssets {
+                    index,
+                    address: output.address_string(),
+                });
+            }
+        }
+        self.check_payment(&to, &amount, paid, from.payload() == to.payload());
+        for key in &body.other_fields {
+            self.mismatches
+                .push(TransactionMismatch::UnexpectedBodyField { key: *key });
+        }
+        self.check_fee(BigInt::from(body.fee));
+    }
+
+    fn evm(
+        &mut self,
+        transaction: &EvmTransaction,
+        payload: &TransactionPayload,
+        chain_id: Option<u64>,
+    ) {
+        match (transaction.chain_id, chain_id) {
+            (None, _) => self.mismatches.push(TransactionMismatch::MissingChainId),
+            (Some(actual), Some(expected)) if actual != expected => self
+                .mismatches
+                .push(TransactionMismatch::ChainIdMismatch { expected, actual }),
+            _ => {}
+        }
+        let max_cost =
+            BigInt::from(transaction.max_gas_price()) * BigInt::from(transaction.gas_limit);
+        self.check_fee(max_cost);
+        match payload {
+            TransactionPayload::Send(send) => self.evm_send(transaction, send),
+            TransactionPayload::SendToken(send_token) => {
+                self.evm_send_token(transaction, send_token)
+            }
+            TransactionPayload::EthContractCall(contract) => {
+                self.evm_contract_call(transaction, contract)
+            }
+            TransactionPayload::PersonalSign(_) | TransactionPayload::EthSignTypedData(_) => self
+                .mismatches
+                .push(TransactionMismatch::UnsupportedTransactionType {
+                    transaction_type: payload.transaction_type(),
+                    blockchain: self.request.blockchain.clone(),
+                }),
+        }
+    }
+
+    fn evm_send(&mut self, transaction: &EvmTransaction, send: &SendRequest) {
+        self.check_to(transaction, "sendRequest.toAddress", &send.to_address);
+        self.check_value(
+            transaction,
+            "sendRequest.amount",
+            send.coin_amount(&self.request.blockchain),
+        );
+        self.check_data(transaction, &[]);
+    }
+
+    // token sends call transfer(to, amount) on the token contract without native value
+    fn evm_send_token(&mut self, transaction: &EvmTransaction, send_token: &SendTokenRequest) {
+        self.check_to(
+            transaction,
+            "sendTokenRequest.tokenContractAddress",
+            &send_token.token_contract_address,
+        );
+        self.check_value(
+            transaction,
+            "sendTokenRequest.amount",
+            Ok(CoinAmount::zero(
+                self.request.blockchain.native_coin(),
+                self.request.blockchain.native_decimals(),
+            )),
+        );
+        let (to, amount) = match Erc20Call::decode(&transaction.data) {
+            Ok(Erc20Call::Transfer { to, amount }) => (to, amount),
+            _ => {
+                self.mismatches.push(TransactionMismatch::NotTokenTransfer {
+                    data: hex::encode(&transaction.data),
+                });
+                return;
+            }
+        };
+        if let Some(expected) = self.address("sendTokenRequest.toAddress", &send_token.to_address) {
+            if expected.payload() != to {
+                self.mismatches
+                    .push(TransactionMismatch::TokenRecipientMismatch {
+                        expected: expected.to_string(),
+                        actual: eip55_checksum(&to),
+                    });
+            }
+        }
+        match send_token.coin_amount(self.request.coin.clone()) {
+            Ok(expected) if expected.base_units() != &amount => {
+                self.mismatches
+                    .push(TransactionMismatch::TokenAmountMismatch {
+                        actual: normalized(&BigDecimal::new(amount, expected.decimals() as i64)),
+                        expected: normalized(&expected.to_coin_unit()),
+                    })
+            }
+            Ok(_) => {}
+            Err(error) => self.mismatches.push(TransactionMismatch::InvalidAmount {
+                field: "sendTokenRequest.amount",
+                error,
+            }),
+        }
+    }
+
+    fn evm_contract_call(&mut self, transaction: &EvmTransaction, contract: &EthContractRequest) {
+        self.check_to(
+            transaction,
+            "ethSmartContractRequest.toAddress",
+            &contract.to_address,
+        );
+        self.check_value(
+            transaction,
+            "ethSmartContractRequest.amount",
+            contract.coin_amount(&self.request.blockchain),
+        );
+        match HexBytes::try_from(contract.data.trim()) {
+            Ok(data) => self.check_data(transaction, &data.to_bytes()),
+            Err(error) => self
+                .mismatches
+                .push(TransactionMismatch::InvalidData(error)),
+        }
+        // compared as a whole number as BigDecimal equality rescales by the exponent difference
+        let gas_limit = whole_number(&contract.gas_limit).and_then(|gas_limit| gas_limit.to_u64());
+        if gas_limit != Some(transaction.gas_limit) {
+            self.mismatches.push(TransactionMismatch::GasLimitMismatch {
+                expected: contract.gas_limit.clone(),
+                actual: transaction.gas_limit,
+            });
+        }
+    }
+
+    fn check_to(&mut self, transaction: &EvmTransaction, field: &'static str, expected: &str) {
+        let expected = match self.address(field, expected) {
+            Some(expected) => expected,
+            None => return,
+        };
+        if transaction.to.as_ref().map(|to| &to[..]) != Some(expected.payload()) {
+            self.mismatches
+                .push(TransactionMismatch::RecipientMismatch {
+                    expected: expected.to_string(),
+                    actual: transaction.to.as_ref().map(eip55_checksum),
+                });
+        }
+    }
+
+    fn check_value(
+        &mut self,
+        transaction: &EvmTransaction,
+        field: &'static str,
+        expected: Result<CoinAmount, AmountError>,
+    ) {
+        match expected {
+            Ok(expected) if expected.base_units() != &BigInt::from(transaction.value) => {
+                self.mismatches.push(TransactionMismatch::ValueMismatch {
+                    expected: normalized(&expected.to_coin_unit()),
+                    actual: normalized(&wei_to_coin_unit(
+                        &self.request.blockchain,
+                        transaction.value,
+                    )),
+                })
+            }
+            Ok(_) => {}
+            Err(error) => self
+                .mismatches
+                .push(TransactionMismatch::InvalidAmount { field, error }),
+        }
+    }
+
+    fn check_data(&mut self, transaction: &EvmTransaction, expected: &[u8]) {
+        if transaction.data != expected {
+            self.mismatches.push(TransactionMismatch::DataMismatch {
+                expected: hex::encode(expected),
+                actual: hex::encode(&transaction.data),
+            });
+        }
+    }
+
+    // The highest fee the transaction can pay must not exceed the fee shown to the user. Both are
+    // compared in base units of the native coin.
+    fn check_fee(&mut self, actual: BigInt) {
+        let approved = match &self.request.fee {
+            Some(approved) => approved,
+            None => return,
+        };
+        match CoinAmount::native(&self.request.blockchain, approved) {
+            Ok(approved) if &actual > approved.base_units() => {
+                self.mismatches.push(TransactionMismatch::FeeExceeded {
+                    approved: normalized(&approved.to_coin_unit()),
+                    actual: self.native_amount(actual),
+                })
+            }
+            Ok(_) => {}
+            Err(error) => self.mismatches.push(TransactionMismatch::InvalidAmount {
+                field: "fee",
+                error,
+            }),
+        }
+    }
+
+    // Paying yourself cannot be told apart from change so it only needs to cover the amount
+    fn check_payment(
+        &mut self,
+        recipient: &Address,
+        expected: &CoinAmount,
+        paid: BigInt,
+        is_self_send: bool,
+    ) {
+        let actual = normalized(&BigDecimal::new(paid.clone(), expected.decimals() as i64));
+        if paid.is_zero() && !expected.is_zero() {
+            self.mismatches.push(TransactionMismatch::MissingPayment {
+                address: recipient.to_string(),
+                amount: normalized(&expected.to_coin_unit()),
+            });
+        } else if &paid != expected.base_units() && !(is_self_send && &paid > expected.base_units())
+        {
+            self.mismatches.push(TransactionMismatch::PaymentMismatch {
+                address: recipient.to_string(),
+                expected: normalized(&expected.to_coin_unit()),
+                actual,
+            });
+        }
+    }
+
+    // sender, recipient and amount of a native send
+    fn send_parts(&mut self, send: &SendRequest) -> Option<(Address, Address, CoinAmount)> {
+        let from = self.address("fromAddress", &self.request.from_address);
+        let to = self.address("sendRequest.toAddress", &send.to_address);
+        let amount = match send.coin_amount(&self.request.blockchain) {
+            Ok(amount) => Some(amount),
+            Err(error) => {
+                self.mismatches.push(TransactionMismatch::InvalidAmount {
+                    field: "sendRequest.amount",
+                    error,
+                });
+                None
+            }
+        };
+        Some((from?, to?, amount?))
+    }
+
+    fn address(&mut self, field: &'static str, address: &str) -> Option<Address> {
+        match Address::parse(&self.request.blockchain, address) {
+            Ok(address) => Some(address),
+            Err(error) => {
+                self.mismatches.push(TransactionMismatch::InvalidRequest(
+                    ValidationError::InvalidAddress { field, error },
+                ));
+                None
+            }
+        }
+    }
+
+    // base units of the native coin in coin unit
+    fn native_amount(&self, base_units: impl Into<BigInt>) -> BigDecimal {
+        normalized(&BigDecimal::new(
+            base_units.into(),
+            self.request.blockchain.native_decimals() as i64,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::SigningRequest;
+
+    // signed transaction of the EIP-155 example: 1 ether to 0x3535353535353535353535353535353535353535
+    // with gas price 20 gwei and gas limit 21000
+    const EIP155_TRANSACTION: &str = "f86c098504a817c800825208943535353535353535353535353535353535\
+        353535880de0b6b3a76400008025a028ef61340bd939bc2195fe537567866003e1a15d3c71ff63e1590620aa63\
+        6276a067cbe9d8997f761aecb703304b3800ccf555c9f3dc64214b297fb1966a3b6d83";
+    const RECIPIENT: &str = "0x3535353535353535353535353535353535353535";
+
+    fn request(fee: &str, payload: serde_json::Value) -> VerifyTransactionRequest {
+        let mut request = json!({
+            "id": "s1",
+            "walletId": "w1",
+            "blockchain": "ETHEREUM",
+            "coin": "ETH",
+            "keyScheme": "ECDSA",
+            "pubkey": "0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            "fromAddress": "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf",
+            "threshold": 2,
+            "requestTransactionType": "SEND",
+            "status": "SIGNING_SESSION_CREATED",
+            "signers": [1, 2],
+            "feeLevel": "MEDIUM",
+            "fee": fee,
+            "version": 0,
+            "createdAt": "now"
+        });
+        for (key, value) in payload.as_object().unwrap() {
+            request[key] = value.clone();
+        }
+        let signing_request: SigningRequest = serde_json::from_value(request).unwrap();
+        VerifyTransactionRequest {
+            blockchain: Blockchain::ETHEREUM,
+            coin: Coin::ETH,
+            raw_transaction: EIP155_TRANSACTION.to_string(),
+            signing_request,
+        }
+    }
+
+    fn send(fee: &str) -> VerifyTransactionRequest {
+        request(
+            fee,
+            json!({"sendRequest": {"toAddress": RECIPIENT, "amount": "1"}}),
+        )
+    }
+
+    #[test]
+    fn fee_is_compared_in_base_units() {
+        assert!(send("0.00042").mismatches().is_empty());
+        assert!(matches!(
+            send("0.000419999999999999").mismatches()[..],
+            [TransactionMismatch::FeeExceeded { .. }]
+        ));
+    }
+
+    // the exponent of the approved fee is checked before it is compared
+    #[test]
+    fn fee_with_huge_exponent_is_invalid() {
+        for fee in ["1E-1000000000", "1E+1000000000"] {
+            assert!(matches!(
+                send(fee).mismatches()[..],
+                [TransactionMismatch::InvalidAmount { field: "fee", .. }]
+            ));
+        }
+    }
+
+    #[test]
+    fn gas_limit_with_huge_exponent_mismatches() {
+        let request = request(
+            "0.00042",
+            json!({
+                "requestTransactionType": "ETH_SMART_CONTRACT_CALL",
+                "ethSmartContractRequest": {
+                    "toAddress": RECIPIENT,
+                    "amount": "1",
+                    "gasLimit": "1E+1000000000",
+                    "data": ""
+                }
+            }),
+        );
+        assert!(matches!(
+            request.mismatches()[..],
+            [TransactionMismatch::GasLimitMismatch { actual: 21000, .. }]
+        ));
+    }
+}
@@ -0,0 +1,494 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use bigdecimal::{BigDecimal, Zero};
+
+use crate::address::{Address, AddressError};
+use crate::encoding::{EncodingError, PubkeyHex};
+use crate::payload::payload_field;
+use crate::registry::{same_contract, Registry};
+use crate::{Blockchain, Coin, KeyScheme, RequestTransactionType, SigningRequest};
+
+// A single structural problem found in a SigningRequest
+#[derive(Clone, PartialEq, Debug)]
+pub enum ValidationError {
+    // the request detail matching requestTransactionType is not set
+    MissingPayload {
+        transaction_type: RequestTransactionType,
+        field: &'static str,
+    },
+    // a request detail is set that does not belong to requestTransactionType
+    UnexpectedPayload {
+        transaction_type: RequestTransactionType,
+        field: &'static str,
+    },
+    // transaction type cannot be executed on the blockchain e.g. smart contract call on BITCOIN
+    UnsupportedTransactionType {
+        transaction_type: RequestTransactionType,
+        blockchain: Blockchain,
+    },
+    // coin does not exist on the blockchain
+    UnsupportedCoin {
+        coin: Coin,
+        blockchain: Blockchain,
+    },
+    // SEND must move the native coin of the blockchain
+    NotNativeCoin {
+        coin: Coin,
+        blockchain: Blockchain,
+    },
+    // SEND_TOKEN must move a token, not the native coin
+    NotTokenCoin {
+        coin: Coin,
+        blockchain: Blockchain,
+    },
+    // key scheme is not the one used by the blockchain
+    KeySchemeMismatch {
+        blockchain: Blockchain,
+        expected: KeyScheme,
+        actual: KeyScheme,
+    },
+    // threshold must be at least one
+    InvalidThreshold {
+        threshold: i32,
+    },
+    // more signatures are required than there are assigned signers
+    ThresholdExceedsSigners {
+        threshold: i32,
+        signers: usize,
+    },
+    // party ids are 1-base index
+    InvalidSigner {
+        party_id: i32,
+    },
+    // the same party is assigned more than once
+    DuplicateSigner {
+        party_id: i32,
+    },
+    // pubkey is not valid for the key scheme
+    InvalidPubkey {
+        key_scheme: KeyScheme,
+        error: EncodingError,
+    },
+    // a required string field is empty
+    EmptyField {
+        field: &'static str,
+    },
+    // an amount that must be greater than zero
+    NonPositiveAmount {
+        field: &'static str,
+        amount: BigDecimal,
+    },
+    // an amount that must not be negative
+    NegativeAmount {
+        field: &'static str,
+        amount: BigDecimal,
+    },
+    // token decimals must not be negative
+    InvalidDecimals {
+        decimals: i32,
+    },
+    // version must not be negative
+    InvalidVersion {
+        version: i32,
+    },
+    // blockchain or coin is not configured or not enabled in the registry
+    CoinNotEnabled {
+        coin: Coin,
+        blockchain: Blockchain,
+    },
+    // token contract address differs from the registry
+    TokenContractMismatch {
+        expected: String,
+        actual: String,
+    },
+    // token decimals differ from the registry
+    TokenDecimalsMismatch {
+        expected: u32,
+        actual: i32,
+    },
+    // an address field is not a valid address of the blockchain and network
+    InvalidAddress {
+        field: &'static str,
+        error: AddressError,
+    },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::MissingPayload {
+                transaction_type,
+                field,
+            } => write!(f, "{} is required for {:?}", field, transaction_type),
+            ValidationError::UnexpectedPayload {
+                transaction_type,
+                field,
+            } => write!(f, "{} must not be set for {:?}", field, transaction_type),
+            ValidationError::UnsupportedTransactionType {
+                transaction_type,
+                blockchain,
+            } => write!(
+                f,
+                "{:?} is not supported on {:?}",
+                transaction_type, blockchain
+            ),
+            ValidationError::UnsupportedCoin { coin, blockchain } => {
+                write!(f, "coin {:?} is not supported on {:?}", coin, blockchain)
+            }
+            ValidationError::NotNativeCoin { coin, blockchain } => {
+                write!(
+                    f,
+                    "coin {:?} is not the native coin of {:?}",
+                    coin, blockchain
+                )
+            }
+            ValidationError::NotTokenCoin { coin, blockchain } => {
+                write!(
+                    f,
+                    "coin {:?} is the native coin of {:?}, not a token",
+                    coin, blockchain
+                )
+            }
+            ValidationError::KeySchemeMismatch {
+                blockchain,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "{:?} requires key scheme {:?} but got {:?}",
+                blockchain, expected, actual
+            ),
+            ValidationError::InvalidThreshold { threshold } => {
+                write!(f, "threshold {} must be at least 1", threshold)
+            }
+            ValidationError::ThresholdExceedsSigners { threshold, signers } => write!(
+                f,
+                "threshold {} is larger than number of signers {}",
+                threshold, signers
+            ),
+            ValidationError::InvalidSigner { party_id } => {
+                write!(f, "signer party id {} must be at least 1", party_id)
+            }
+            ValidationError::DuplicateSigner { party_id } => {
+                write!(f, "signer party id {} is assigned more than once", party_id)
+            }
+            ValidationError::InvalidPubkey { key_scheme, error } => {
+                write!(
+                    f,
+                    "pubkey is not a valid {:?} public key: {}",
+                    key_scheme, error
+                )
+            }
+            ValidationError::EmptyField { field } => write!(f, "{} must not be empty", field),
+            ValidationError::NonPositiveAmount { field, amount } => {
+                write!(f, "{} must be greater than zero but got {}", field, amount)
+            }
+            ValidationError::NegativeAmount { field, amount } => {
+                write!(f, "{} must not be negative but got {}", field, amount)
+            }
+            ValidationError::InvalidDecimals { decimals } => {
+                write!(f, "decimals {} must not be negative", decimals)
+            }
+            ValidationError::InvalidVersion { version } => {
+                write!(f, "version {} must not be negative", version)
+            }
+            ValidationError::CoinNotEnabled { coin, blockchain } => {
+                write!(f, "coin {:?} is not enabled on {:?}", coin, blockchain)
+            }
+            ValidationError::TokenContractMismatch { expected, actual } => write!(
+                f,
+                "token contract address {} does not match configured {}",
+                actual, expected
+            ),
+            ValidationError::TokenDecimalsMismatch { expected, actual } => write!(
+                f,
+                "token decimals {} does not match configured {}",
+                actual, expected
+            ),
+            ValidationError::InvalidAddress { field, error } => write!(f, "{}: {}", field, error),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+impl SigningRequest {
+    // Check that the request is self consistent. All violations are returned, not only the first one,
+    // so every service rejects a malformed request with the same list of reasons.
+    pub fn validate(&self) -> Result<(), Vec<ValidationError>> {
+        let mut errors = Vec::new();
+        self.validate_chain(&mut errors);
+        self.validate_payload(&mut errors);
+        self.validate_signers(&mut errors);
+        if self.wallet_id.is_empty() {
+            errors.push(ValidationError::EmptyField { field: "walletId" });
+        }
+        if self.pubkey.is_empty() {
+            errors.push(ValidationError::EmptyField { field: "pubkey" });
+        } else if let Err(error) = PubkeyHex::parse(&self.key_scheme, &self.pubkey) {
+            errors.push(ValidationError::InvalidPubkey {
+                key_scheme: self.key_scheme.clone(),
+                error,
+            });
+        }
+        if self.from_address.is_empty() {
+            errors.push(ValidationError::EmptyField {
+                field: "fromAddress",
+            });
+        }
+        if let Some(fee) = &self.fee {
+            check_not_negative(&mut errors, "fee", fee);
+        }
+        if self.version < 0 {
+            errors.push(ValidationError::InvalidVersion {
+                version: self.version,
+            });
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Same as validate() and additionally check the request against the configured blockchains and coins
+    pub fn validate_with(&self, registry: &Registry) -> Result<(), Vec<ValidationError>> {
+        let mut errors = self.validate().err().unwrap_or_default();
+        match registry.coin(&self.blockchain, &self.coin) {
+            Some(entry) if registry.is_enabled(&self.blockchain, &self.coin) => {
+                if let Some(send_token) = &self.send_token_request {
+                    let expected = entry.contract_address.clone().unwrap_or_default();
+                    if !same_contract(
+                        &self.blockchain,
+                        &expected,
+                        &send_token.token_contract_address,
+                    ) {
+                        errors.push(ValidationError::TokenContractMismatch {
+                            expected,
+                            actual: send_token.token_contract_address.clone(),
+                        });
+                    }
+                    if send_token.decimals != entry.decimals as i32 {
+                        errors.push(ValidationError::TokenDecimalsMismatch {
+                            expected: entry.decimals,
+                            actual: send_token.decimals,
+                        });
+                    }
+                }
+            }
+            _ => errors.push(ValidationError::CoinNotEnabled {
+                coin: self.coin.clone(),
+                blockchain: self.blockchain.clone(),
+            }),
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    // Check every address of the request is a valid address of the blockchain on mainnet or testnet
+    pub fn validate_addresses(&self, is_mainnet: bool) -> Result<(), Vec<ValidationError>> {
+        let mut addresses = vec![("fromAddress", &self.from_address)];
+        if let Some(send) = &self.send_request {
+            addresses.push(("sendRequest.toAddress", &send.to_address));
+        }
+        if let Some(send_token) = &self.send_token_request {
+            addresses.push(("sendTokenRequest.toAddress", &send_token.to_address));
+            addresses.push((
+                "sendTokenRequest.tokenContractAddress",
+                &send_token.token_contract_address,
+            ));
+        }
+        if let Some(contract) = &self.eth_smart_contract_request {
+            addresses.push(("ethSmartContractRequest.toAddress", &contract.to_address));
+        }
+        let errors: Vec<ValidationError> = addresses
+            .into_iter()
+            .filter_map(|(field, address)| {
+                Address::parse_on_network(&self.blockchain, address, is_mainnet)
+                    .err()
+                    .map(|error| ValidationError::InvalidAddress { field, error })
+            })
+            .collect();
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn validate_chain(&self, errors: &mut Vec<ValidationError>) {
+        let blockchain = &self.blockchain;
+        if !blockchain.supports_coin(&self.coin) {
+            errors.push(ValidationError::UnsupportedCoin {
+                coin: self.coin.clone(),
+                blockchain: blockchain.clone(),
+            });
+        }
+        if blockchain.key_scheme() != self.key_scheme {
+            errors.push(ValidationError::KeySchemeMismatch {
+                blockchain: blockchain.clone(),
+                expected: blockchain.key_scheme(),
+                actual: self.key_scheme.clone(),
+            });
+        }
+        let supported = match self.request_transaction_type {
+            RequestTransactionType::SEND => true,
+            // only ERC-20 like tokens are supported
+            RequestTransactionType::SEND_TOKEN => blockchain.is_evm(),
+            RequestTransactionType::ETH_SMART_CONTRACT_CALL => blockchain.is_evm(),
+            // messages are signed with the EVM account of the wallet
+            RequestTransactionType::PERSONAL_SIGN | RequestTransactionType::ETH_SIGN_TYPED_DATA => {
+                blockchain.is_evm()
+            }
+        };
+        if !supported {
+            errors.push(ValidationError::UnsupportedTransactionType {
+                transaction_type: self.request_transaction_type.clone(),
+                blockchain: blockchain.clone(),
+            });
+            return;
+        }
+        let is_native = blockchain.native_coin() == self.coin;
+        match self.request_transaction_type {
+            RequestTransactionType::SEND
+            | RequestTransactionType::ETH_SMART_CONTRACT_CALL
+            | RequestTransactionType::PERSONAL_SIGN
+            | RequestTransactionType::ETH_SIGN_TYPED_DATA
+                if !is_native && blockchain.supports_coin(&self.coin) =>
+            {
+                errors.push(ValidationError::NotNativeCoin {
+                    coin: self.coin.clone(),
+                    blockchain: blockchain.clone(),
+                })
+            }
+            RequestTransactionType::SEND_TOKEN if is_native => {
+                errors.push(ValidationError::NotTokenCoin {
+                    coin: self.coin.clone(),
+                    blockchain: blockchain.clone(),
+                })
+            }
+            _ => {}
+        }
+    }
+
+    fn validate_payload(&self, errors: &mut Vec<ValidationError>) {
+        let transaction_type = &self.request_transaction_type;
+        let expected = payload_field(transaction_type);
+        let present = [
+            ("sendRequest", self.send_request.is_some()),
+            ("sendTokenRequest", self.send_token_request.is_some()),
+            (
+                "ethSmartContractRequest",
+                self.eth_smart_contract_request.is_some(),
+            ),
+            ("personalSignRequest", self.personal_sign_request.is_some()),
+            ("ethTypedDataRequest", self.eth_typed_data_request.is_some()),
+        ];
+        for (field, is_set) in present {
+            if field == expected && !is_set {
+                errors.push(ValidationError::MissingPayload {
+                    transaction_type: transaction_type.clone(),
+                    field,
+                });
+            } else if field != expected && is_set {
+                errors.push(ValidationError::UnexpectedPayload {
+                    transaction_type: transaction_type.clone(),
+                    field,
+                });
+            }
+        }
+
+        if let Some(send) = &self.send_request {
+            check_not_empty(errors, "sendRequest.toAddress", &send.to_address);
+            check_positive(errors, "sendRequest.amount", &send.amount);
+        }
+        if let Some(send_token) = &self.send_token_request {
+            check_not_empty(errors, "sendTokenRequest.toAddress", &send_token.to_address);
+            check_not_empty(
+                errors,
+                "sendTokenRequest.tokenContractAddress",
+                &send_token.token_contract_address,
+            );
+            check_positive(errors, "sendTokenRequest.amount", &send_token.amount);
+            if send_token.decimals < 0 {
+                errors.push(ValidationError::InvalidDecimals {
+                    decimals: send_token.decimals,
+                });
+            }
+        }
+        if let Some(contract) = &self.eth_smart_contract_request {
+            check_not_empty(
+                errors,
+                "ethSmartContractRequest.toAddress",
+                &contract.to_address,
+            );
+            check_not_negative(errors, "ethSmartContractRequest.amount", &contract.amount);
+            check_positive(
+                errors,
+                "ethSmartContractRequest.gasLimit",
+                &contract.gas_limit,
+            );
+        }
+        if let Some(message) = &self.personal_sign_request {
+            check_not_empty(errors, "personalSignRequest.message", &message.message);
+        }
+        if let Some(typed_data) = &self.eth_typed_data_request {
+            check_not_empty(
+                errors,
+                "ethTypedDataRequest.typedData",
+                &typed_data.typed_data,
+            );
+        }
+    }
+
+    fn validate_signers(&self, errors: &mut Vec<ValidationError>) {
+        if self.threshold < 1 {
+            errors.push(ValidationError::InvalidThreshold {
+                threshold: self.threshold,
+            });
+        } else if self.threshold as usize > self.signers.len() {
+            errors.push(ValidationError::ThresholdExceedsSigners {
+                threshold: self.threshold,
+                signers: self.signers.len(),
+            });
+        }
+        let mut seen = HashSet::new();
+        let mut reported = HashSet::new();
+        for &party_id in &self.signers {
+            if party_id < 1 {
+                errors.push(ValidationError::InvalidSigner { party_id });
+            }
+            if !seen.insert(party_id) && reported.insert(party_id) {
+                errors.push(ValidationError::DuplicateSigner { party_id });
+            }
+        }
+    }
+}
+
+fn check_not_empty(errors: &mut Vec<ValidationError>, field: &'static str, value: &str) {
+    if value.trim().is_empty() {
+        errors.push(ValidationError::EmptyField { field });
+    }
+}
+
+fn check_positive(errors: &mut Vec<ValidationError>, field: &'static str, amount: &BigDecimal) {
+    if amount <= &BigDecimal::zero() {
+        errors.push(ValidationError::NonPositiveAmount {
+            field,
+            amount: amount.clone(),
+        });
+    }
+}
+
+fn check_not_negative(errors: &mut Vec<ValidationError>, field: &'static str, amount: &BigDecimal) {
+    if amount < &BigDecimal::zero() {
+        errors.push(ValidationError::NegativeAmount {
+            field,
+            amount: amount.clone(),
+        });
+    }
+}
@@ -0,0 +1,294 @@
+use std::fmt;
+
+use bigdecimal::{BigDecimal, Signed, Zero};
+
+use crate::amount::{digit_count, display_amount, pow10, CoinAmount, MAX_DIGITS};
+use crate::{
+    Coin, CoinPrices, Fiat, FiatConfig, RequestTransactionType, SigningRequest, WalletBalanceUpdate,
+};
+
+// decimal places shown for fiat values
+const FIAT_DECIMALS: i64 = 2;
+// Digits and decimal places of a fiat value, the product of an amount, a price and an exchange
+// rate. Division gives rates of about 100 digits so the bound is well above any real value.
+const MAX_VALUE_DIGITS: i64 = 1000;
+
+// Reason why a coin amount cannot be valued in a fiat currency
+#[derive(Clone, PartialEq, Debug)]
+pub enum ValuationError {
+    // fiat has no FiatConfig
+    FiatNotConfigured { fiat: Fiat },
+    // fiat is configured but disabled
+    FiatDisabled { fiat: Fiat },
+    // no direct price and no cross rate can be derived
+    PriceNotFound { coin: Coin, fiat: Fiat },
+    // amount or price has too many digits or decimal places to be valued
+    OutOfRange { value: BigDecimal },
+}
+
+impl fmt::Display for ValuationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValuationError::FiatNotConfigured { fiat } => {
+                write!(f, "fiat {:?} is not configured", fiat)
+            }
+            ValuationError::FiatDisabled { fiat } => write!(f, "fiat {:?} is disabled", fiat),
+            ValuationError::PriceNotFound { coin, fiat } => {
+                write!(f, "no price of {:?} in {:?}", coin, fiat)
+            }
+            ValuationError::OutOfRange { value } => {
+                write!(f, "{} is out of range", display_amount(value))
+            }
+        }
+    }
+}
+
+impl std::error::Error for ValuationError {}
+
+// Value of a coin amount in a fiat currency, not rounded
+#[derive(Clone, PartialEq, Debug)]
+pub struct FiatValue {
+    pub fiat: Fiat,
+    pub amount: BigDecimal,
+}
+
+impl FiatValue {
+    // amount rounded half away from zero to the fiat decimal places
+    pub fn rounded(&self) -> Result<BigDecimal, ValuationError> {
+        round_half_up(&self.amount, FIAT_DECIMALS)
+    }
+
+    // rounded amount with the fiat symbol and thousands separators e.g. £1,234.57
+    pub fn format(&self, config: &FiatConfig) -> Result<String, ValuationError> {
+        let rounded = self.rounded()?;
+        let sign = if rounded.is_negative() { "-" } else { "" };
+        let text = rounded.abs().to_string();
+        let (integer, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let mut grouped = String::new();
+        for (index, digit) in integer.chars().enumerate() {
+            if index > 0 && (integer.len() - index) % 3 == 0 {
+                grouped.push(',');
+            }
+            grouped.push(digit);
+        }
+        Ok(format!(
+            "{}{}{}.{:0<width$}",
+            sign,
+            config.symbol,
+            grouped,
+            fraction,
+            width = FIAT_DECIMALS as usize
+        ))
+    }
+}
+
+// Values coin amounts in fiat currencies using one price snapshot
+pub struct Valuation<'a> {
+    prices: &'a CoinPrices,
+    fiats: &'a [FiatConfig],
+}
+
+impl<'a> Valuation<'a> {
+    pub fn new(prices: &'a CoinPrices, fiats: &'a [FiatConfig]) -> Self {
+        Valuation { prices, fiats }
+    }
+
+    // config of an enabled fiat
+    pub fn fiat_config(&self, fiat: &Fiat) -> Result<&'a FiatConfig, ValuationError> {
+        let config = self
+            .fiats
+            .iter()
+            .find(|config| &config.fiat == fiat)
+            .ok_or_else(|| ValuationError::FiatNotConfigured { fiat: fiat.clone() })?;
+        if !config.enabled {
+            return Err(ValuationError::FiatDisabled { fiat: fiat.clone() });
+        }
+        Ok(config)
+    }
+
+    // Price of one coin in the fiat. When the coin is not quoted in the fiat, the price is derived
+    // from a fiat the coin is quoted in and the exchange rate between both fiats implied by a coin
+    // quoted in both e.g. GBP price of ADA = USD price of ADA * GBP price of BTC / USD price of BTC.
+    pub fn price(&self, coin: &Coin, fiat: &Fiat) -> Result<BigDecimal, ValuationError> {
+        self.fiat_config(fiat)?;
+        if let Some(price) = self.quoted(coin, fiat) {
+            return in_range(price, MAX_DIGITS).cloned();
+        }
+        let quoted_items = self
+            .prices
+            .coin_prices
+            .iter()
+            .filter(|coin_price| &coin_price.coin == coin)
+            .flat_map(|coin_price| coin_price.coin_price_items.iter());
+        for item in quoted_items {
+            if let Some(rate) = self.exchange_rate(&item.fiat, fiat) {
+                return Ok(in_range(&item.price, MAX_DIGITS)? * rate);
+            }
+        }
+        Err(ValuationError::PriceNotFound {
+            coin: coin.clone(),
+            fiat: fiat.clone(),
+        })
+    }
+
+    // value of an amount in coin unit e.g. 0.5 BTC
+    pub fn value(
+        &self,
+        coin: &Coin,
+        amount: &BigDecimal,
+        fiat: &Fiat,
+    ) -> Result<FiatValue, ValuationError> {
+        let price = self.price(coin, fiat)?;
+        Ok(FiatValue {
+            fiat: fiat.clone(),
+            amount: in_range(amount, MAX_DIGITS)? * price,
+        })
+    }
+
+    pub fn value_of(&self, amount: &CoinAmount, fiat: &Fiat) -> Result<FiatValue, ValuationError> {
+        self.value(amount.coin(), &amount.to_coin_unit(), fiat)
+    }
+
+    pub fn balance_value(
+        &self,
+        balance: &WalletBalanceUpdate,
+        fiat: &Fiat,
+    ) -> Result<FiatValue, ValuationError> {
+        self.value(&balance.coin, &balance.balance, fiat)
+    }
+
+    // Value of the amount transferred by the request. Smart contract calls transfer the native coin.
+    // Returns None when the request has no detail for its transaction type or signs a message. The
+    // value of a message is unknown as typed data like an EIP-2612 permit can authorize transfers.
+    pub fn request_value(
+        &self,
+        request: &SigningRequest,
+        fiat: &Fiat,
+    ) -> Result<Option<FiatValue>, ValuationError> {
+        let (coin, amount) = match request.request_transaction_type {
+            RequestTransactionType::SEND => match &request.send_request {
+                Some(send) => (request.coin.clone(), &send.amount),
+                None => return Ok(None),
+            },
+            RequestTransactionType::SEND_TOKEN => match &request.send_token_request {
+                Some(send_token) => (request.coin.clone(), &send_token.amount),
+                None => return Ok(None),
+            },
+            RequestTransactionType::ETH_SMART_CONTRACT_CALL => {
+                match &request.eth_smart_contract_request {
+                    Some(contract) => (request.blockchain.native_coin(), &contract.amount),
+                    None => return Ok(None),
+                }
+            }
+            RequestTransactionType::PERSONAL_SIGN | RequestTransactionType::ETH_SIGN_TYPED_DATA => {
+                return Ok(None)
+            }
+        };
+        self.value(&coin, amount, fiat).map(Some)
+    }
+
+    // Value of the fee of the request which is paid in the native coin.
+    // Returns None when the fee is not known yet.
+    pub fn fee_value(
+        &self,
+        request: &SigningRequest,
+        fiat: &Fiat,
+    ) -> Result<Option<FiatValue>, ValuationError> {
+        match &request.fee {
+            Some(fee) => self
+                .value(&request.blockchain.native_coin(), fee, fiat)
+                .map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn quoted(&self, coin: &Coin, fiat: &Fiat) -> Option<&'a BigDecimal> {
+        self.prices
+            .coin_prices
+            .iter()
+            .filter(|coin_price| &coin_price.coin == coin)
+            .flat_map(|coin_price| coin_price.coin_price_items.iter())
+            .find(|item| &item.fiat == fiat)
+            .map(|item| &item.price)
+    }
+
+    // Amount of `to` for one unit of `from`, implied by the first coin quoted in both fiats.
+    // Coins with a price out of range are skipped.
+    fn exchange_rate(&self, from: &Fiat, to: &Fiat) -> Option<BigDecimal> {
+        self.prices.coin_prices.iter().find_map(|coin_price| {
+            let from_price = self.quoted(&coin_price.coin, from)?;
+            let to_price = self.quoted(&coin_price.coin, to)?;
+            in_range(from_price, MAX_DIGITS).ok()?;
+            in_range(to_price, MAX_DIGITS).ok()?;
+            if from_price.is_zero() {
+                return None;
+            }
+            Some(to_price / from_price)
+        })
+    }
+}
+
+// Value that has at most max_digits digits and decimal places, so arithmetic on it does not
+// compute large powers of ten
+fn in_range(value: &BigDecimal, max_digits: i64) -> Result<&BigDecimal, ValuationError> {
+    let (digits, exponent) = value.as_bigint_and_exponent();
+    if exponent.unsigned_abs() > max_digits as u64 || digit_count(&digits) > max_digits {
+        return Err(ValuationError::OutOfRange {
+            value: value.clone(),
+        });
+    }
+    Ok(value)
+}
+
+// round half away from zero to the given decimal places
+fn round_half_up(value: &BigDecimal, scale: i64) -> Result<BigDecimal, ValuationError> {
+    let (digits, exponent) = in_range(value, MAX_VALUE_DIGITS)?.as_bigint_and_exponent();
+    if exponent <= scale {
+        return Ok(value.with_scale(scale));
+    }
+    let shift = u32::try_from(exponent - scale).map_err(|_| ValuationError::OutOfRange {
+        value: value.clone(),
+    })?;
+    let divisor = pow10(shift);
+    let quotient = &digits / &divisor;
+    let remainder = &digits % &divisor;
+    let rounded = if remainder.abs() * 2 >= divisor {
+        quotient + digits.signum()
+    } else {
+        quotient
+    };
+    Ok(BigDecimal::new(rounded, scale))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn value(amount: &str) -> FiatValue {
+        FiatValue {
+            fiat: Fiat::USD,
+            amount: BigDecimal::from_str(amount).unwrap(),
+        }
+    }
+
+    #[test]
+    fn rounds_half_away_from_zero() {
+        let rounded = |amount| value(amount).rounded().unwrap().to_string();
+        assert_eq!(rounded("1234.565"), "1234.57");
+        assert_eq!(rounded("-0.005"), "-0.01");
+        assert_eq!(rounded("12E+3"), "12000.00");
+    }
+
+    // the exponent is checked before the value is rescaled
+    #[test]
+    fn huge_exponent_is_out_of_range() {
+        for amount in ["1E+1000000000", "1E-1000000000", "1E-9223372036854775807"] {
+            assert!(matches!(
+                value(amount).rounded(),
+                Err(ValuationError::OutOfRange { .. })
+            ));
+        }
+    }
+}
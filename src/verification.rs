@@ -0,0 +1,244 @@
+use std::fmt;
+
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
+use k256::ecdsa::{RecoveryId, Signature, VerifyingKey};
+
+use crate::encoding::{EncodingError, Hash32Hex, PubkeyHex};
+use crate::signature::SignatureError;
+use crate::{
+    CreateSignTransactionRequest, KeyScheme, SignTransactionRequest, SignatureRecidHex,
+    SigningRequest,
+};
+
+// Reason why signatures cannot be checked at all
+#[derive(Clone, PartialEq, Debug)]
+pub enum VerificationError {
+    // pubkey is not hex of a public key of the key scheme
+    InvalidPubkey {
+        key_scheme: KeyScheme,
+        error: EncodingError,
+    },
+    // pubkey has the right encoding but is not a point of the curve
+    InvalidCurvePoint {
+        pubkey: String,
+    },
+    // more signatures than hashes
+    TooManySignatures {
+        hashes: usize,
+        signatures: usize,
+    },
+}
+
+impl fmt::Display for VerificationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VerificationError::InvalidPubkey { key_scheme, error } => write!(
+                f,
+                "pubkey is not a valid {:?} public key: {}",
+                key_scheme, error
+            ),
+            VerificationError::InvalidCurvePoint { pubkey } => {
+                write!(f, "pubkey {} is not a point on the curve", pubkey)
+            }
+            VerificationError::TooManySignatures { hashes, signatures } => {
+                write!(f, "{} signatures for {} hashes", signatures, hashes)
+            }
+        }
+    }
+}
+
+impl std::error::Error for VerificationError {}
+
+// Why a signature does not prove the pubkey signed the hash
+#[derive(Clone, PartialEq, Debug)]
+pub enum SignatureFailure {
+    // r, s or recid cannot form a signature
+    Malformed(SignatureError),
+    // signature equation does not hold for the pubkey and hash
+    Invalid,
+    // signature verifies but recid recovers another key so recovery based chains would reject it
+    RecoveredKeyMismatch { recid: u8 },
+}
+
+impl fmt::Display for SignatureFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignatureFailure::Malformed(error) => write!(f, "{}", error),
+            SignatureFailure::Invalid => write!(f, "signature does not match pubkey and hash"),
+            SignatureFailure::RecoveredKeyMismatch { recid } => {
+                write!(f, "recid {} recovers a different pubkey", recid)
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum VerificationOutcome {
+    Valid,
+    // hash is not signed yet
+    MissingSignature,
+    Failed(SignatureFailure),
+}
+
+// Result of checking the signature of one hash
+#[derive(Clone, PartialEq, Debug)]
+pub struct HashVerification {
+    // position of the hash in the request
+    pub index: usize,
+    pub hash: Hash32Hex,
+    pub outcome: VerificationOutcome,
+}
+
+impl HashVerification {
+    pub fn is_valid(&self) -> bool {
+        self.outcome == VerificationOutcome::Valid
+    }
+}
+
+// true when every hash has a valid signature
+pub fn all_valid(verifications: &[HashVerification]) -> bool {
+    verifications.iter().all(HashVerification::is_valid)
+}
+
+// A public key that signatures are checked against
+pub enum Verifier {
+    Secp256k1(VerifyingKey),
+    Ed25519(ed25519_dalek::VerifyingKey),
+}
+
+impl Verifier {
+    pub fn new(key_scheme: &KeyScheme, pubkey: &str) -> Result<Verifier, VerificationError> {
+        let parsed = PubkeyHex::parse(key_scheme, pubkey).map_err(|error| {
+            VerificationError::InvalidPubkey {
+                key_scheme: key_scheme.clone(),
+                error,
+            }
+        })?;
+        let invalid_point = || VerificationError::InvalidCurvePoint {
+            pubkey: parsed.as_str().to_string(),
+        };
+        match &parsed {
+            PubkeyHex::Secp256k1(pubkey) => VerifyingKey::from_sec1_bytes(&pubkey.to_bytes())
+                .map(Verifier::Secp256k1)
+                .map_err(|_| invalid_point()),
+            PubkeyHex::Ed25519(pubkey) => {
+                ed25519_dalek::VerifyingKey::from_bytes(&pubkey.to_array())
+                    .map(Verifier::Ed25519)
+                    .map_err(|_| invalid_point())
+            }
+        }
+    }
+
+    // ECDSA signatures must verify the prehashed hash and recover the pubkey from recid.
+    // High s is accepted as the assembler normalizes it. Ed25519 signatures sign the hash as message.
+    pub fn verify(
+        &self,
+        hash: &Hash32Hex,
+        signature: &SignatureRecidHex,
+    ) -> Result<(), SignatureFailure> {
+        match self {
+            Verifier::Secp256k1(key) => {
+                let normalized = signature
+                    .normalize_s()
+                    .map_err(SignatureFailure::Malformed)?;
+                let recid = normalized
+                    .recovery_id()
+                    .map_err(SignatureFailure::Malformed)?;
+                let ecdsa =
+                    Signature::from_scalars(normalized.r.to_array(), normalized.s.to_array())
+                        .map_err(|_| SignatureFailure::Malformed(SignatureError::InvalidScalar))?;
+                let prehash = hash.to_array();
+                key.verify_prehash(&prehash, &ecdsa)
+                    .map_err(|_| SignatureFailure::Invalid)?;
+                let recovered = RecoveryId::from_byte(recid).and_then(|recovery_id| {
+                    VerifyingKey::recover_from_prehash(&prehash, &ecdsa, recovery_id).ok()
+                });
+                if recovered.as_ref() != Some(key) {
+                    return Err(SignatureFailure::RecoveredKeyMismatch { recid });
+                }
+                Ok(())
+            }
+            Verifier::Ed25519(key) => {
+                let ed25519 = ed25519_dalek::Signature::from_bytes(&signature.to_ed25519());
+                key.verify_strict(&hash.to_array(), &ed25519)
+                    .map_err(|_| SignatureFailure::Invalid)
+            }
+        }
+    }
+
+    // check signatures against hashes in order. Hashes without a signature are reported missing
+    pub fn verify_all(
+        &self,
+        hashes: &[Hash32Hex],
+        signatures: &[Option<&SignatureRecidHex>],
+    ) -> Result<Vec<HashVerification>, VerificationError> {
+        if signatures.len() > hashes.len() {
+            return Err(VerificationError::TooManySignatures {
+                hashes: hashes.len(),
+                signatures: signatures.len(),
+            });
+        }
+        Ok(hashes
+            .iter()
+            .enumerate()
+            .map(|(index, hash)| {
+                let outcome = match signatures.get(index).copied().flatten() {
+                    Some(signature) => match self.verify(hash, signature) {
+                        Ok(()) => VerificationOutcome::Valid,
+                        Err(failure) => VerificationOutcome::Failed(failure),
+                    },
+                    None => VerificationOutcome::MissingSignature,
+                };
+                HashVerification {
+                    index,
+                    hash: hash.clone(),
+                    outcome,
+                }
+            })
+            .collect())
+    }
+}
+
+impl SigningRequest {
+    // Check the aggregated signature of each signing hash against the request pubkey.
+    // Returns no result when the transaction is not created yet.
+    pub fn verify_signatures(&self) -> Result<Vec<HashVerification>, VerificationError> {
+        let verifier = Verifier::new(&self.key_scheme, &self.pubkey)?;
+        let signing_hashes = match &self.signing_result {
+            Some(result) => &result.signing_hashes,
+            None => return Ok(Vec::new()),
+        };
+        let hashes: Vec<Hash32Hex> = signing_hashes
+            .iter()
+            .map(|signing_hash| signing_hash.hash.clone())
+            .collect();
+        let signatures: Vec<Option<&SignatureRecidHex>> = signing_hashes
+            .iter()
+            .map(|signing_hash| {
+                signing_hash
+                    .state
+                    .as_ref()
+                    .and_then(|state| state.signature.as_ref())
+            })
+            .collect();
+        verifier.verify_all(&hashes, &signatures)
+    }
+}
+
+impl CreateSignTransactionRequest {
+    pub fn verify_signatures(&self) -> Result<Vec<HashVerification>, VerificationError> {
+        let verifier = Verifier::new(&self.blockchain.key_scheme(), &self.pubkey)?;
+        let signatures: Vec<Option<&SignatureRecidHex>> =
+            self.signatures.iter().map(Some).collect();
+        verifier.verify_all(&self.hashes, &signatures)
+    }
+}
+
+impl SignTransactionRequest {
+    pub fn verify_signatures(&self) -> Result<Vec<HashVerification>, VerificationError> {
+        let verifier = Verifier::new(&self.blockchain.key_scheme(), &self.pubkey)?;
+        let signatures: Vec<Option<&SignatureRecidHex>> =
+            self.signatures.iter().map(Some).collect();
+        verifier.verify_all(&self.hashes, &signatures)
+    }
+}